@@ -25,7 +25,7 @@
 //! 3. The [`PassByEnum`](derive.PassByInner.html) derive macro for implementing `PassBy` with `Enum`.
 //! 4. The [`PassByInner`](derive.PassByInner.html) derive macro for implementing `PassBy` with `Inner`.
 
-use syn::{parse_macro_input, ItemTrait, DeriveInput};
+use syn::{parse_macro_input, Item, DeriveInput, Error, spanned::Spanned};
 
 mod pass_by;
 mod runtime_interface;
@@ -36,10 +36,16 @@ pub fn runtime_interface(
 	attrs: proc_macro::TokenStream,
 	input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-	let trait_def = parse_macro_input!(input as ItemTrait);
-	let wasm_only = parse_macro_input!(attrs as Option<runtime_interface::keywords::wasm_only>);
+	let trait_def = match parse_macro_input!(input as Item) {
+		Item::Trait(trait_def) => trait_def,
+		item => return Error::new(
+			item.span(),
+			"`#[runtime_interface]` can only be used on a trait declaration",
+		).to_compile_error().into(),
+	};
+	let options = parse_macro_input!(attrs as runtime_interface::Options);
 
-	runtime_interface::runtime_interface_impl(trait_def, wasm_only.is_some())
+	runtime_interface::runtime_interface_impl(trait_def, options)
 		.unwrap_or_else(|e| e.to_compile_error())
 		.into()
 }
@@ -31,7 +31,7 @@ use proc_macro2::TokenStream;
 pub fn derive_impl(mut input: DeriveInput) -> Result<TokenStream> {
 	add_trait_bounds(&mut input.generics);
 	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
-	let crate_include = generate_runtime_interface_include();
+	let crate_include = generate_runtime_interface_include(None);
 	let crate_ = generate_crate_access();
 	let ident = input.ident;
 
@@ -28,7 +28,7 @@ use proc_macro2::{TokenStream, Span};
 
 /// The derive implementation for `PassBy` with `Enum`.
 pub fn derive_impl(input: DeriveInput) -> Result<TokenStream> {
-	let crate_include = generate_runtime_interface_include();
+	let crate_include = generate_runtime_interface_include(None);
 	let crate_ = generate_crate_access();
 	let ident = input.ident;
 	let enum_fields = get_enum_field_idents(&input.data)?
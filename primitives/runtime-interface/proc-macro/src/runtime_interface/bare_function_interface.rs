@@ -31,21 +31,34 @@
 use crate::utils::{
 	generate_crate_access, create_exchangeable_host_function_ident, get_function_arguments,
 	get_function_argument_names, get_runtime_interface, create_function_ident_with_version,
+	is_runtime_interface_attribute, get_item_span_name,
+	generate_enter_span, RuntimeInterface, get_exported_name, get_function_argument_types,
+	get_cfg_attributes, replace_raw_ptr_arguments, create_function_name_const_ident,
+	replace_out_arguments, is_wasm_only_method, create_wasm_only_trait_ident,
+	replace_codec_arguments, get_function_argument_types_without_ref,
+	generate_host_impl_cfg, is_native_only_method, get_default_argument,
+	generate_function_context_type, remove_zero_sized_arguments, generate_allow_generated_lints,
+	generate_for_lifetimes, check_return_type_is_ffi_passable, create_async_function_ident,
 };
 
 use syn::{
-	Ident, ItemTrait, TraitItemMethod, FnArg, Signature, Result, spanned::Spanned, parse_quote,
+	Ident, ItemTrait, TraitItemMethod, FnArg, Signature, Result, Attribute, TraitItem, Error,
+	ReturnType, spanned::Spanned, parse_quote,
 };
 
-use proc_macro2::{TokenStream, Span};
+use super::{Options, host_function_interface::generate_wasm_interface_signature_for_host_function};
 
-use quote::{quote, quote_spanned};
+use proc_macro2::{TokenStream, TokenTree, Delimiter, Span};
+
+use quote::{quote, quote_spanned, ToTokens};
+
+use inflector::Inflector;
 
 use std::iter;
 
 /// Generate one bare function per trait method. The name of the bare function is equal to the name
 /// of the trait method.
-pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
+pub fn generate(trait_def: &ItemTrait, options: &Options) -> Result<TokenStream> {
 	let trait_name = &trait_def.ident;
 	let runtime_interface = get_runtime_interface(trait_def)?;
 
@@ -54,7 +67,9 @@ pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream
 		.try_fold(
 			TokenStream::new(),
 			|mut t, (latest_version, method)| {
-				t.extend(function_for_method(method, latest_version, is_wasm_only)?);
+				t.extend(
+					function_for_method(method, latest_version, options)?
+				);
 				Ok(t)
 			}
 		);
@@ -62,26 +77,421 @@ pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream
 	// earlier versions compatibility dispatch (only std variant)
 	let result: Result<TokenStream> = runtime_interface.all_versions().try_fold(token_stream?, |mut t, (version, method)|
 	{
-		t.extend(function_std_impl(trait_name, method, version, is_wasm_only)?);
+		let deprecated_from = runtime_interface.deprecated_from(&method.sig.ident);
+		let alias = runtime_interface.alias_of(&method.sig.ident, version).map(|alias| {
+			let aliased_method = runtime_interface.method(&method.sig.ident, alias)
+				.expect("Aliases refer to declared versions; qed");
+			(alias, aliased_method)
+		});
+		t.extend(
+			function_std_impl(
+				trait_name,
+				method,
+				version,
+				deprecated_from,
+				alias,
+				options,
+			)?
+		);
 		Ok(t)
 	});
 
-	result
+	let mut result = result?;
+	result.extend(
+		runtime_interface.latest_versions()
+			.filter(|(_, method)| !is_wasm_only_method(method, options.wasm_only))
+			.map(|(_, method)| generate_dispatch_function(method, &runtime_interface, options))
+	);
+	if options.is_async {
+		result.extend(
+			runtime_interface.latest_versions()
+				.filter(|(_, method)| !is_wasm_only_method(method, options.wasm_only))
+				.map(|(version, method)| {
+					let version = runtime_interface.alias_of(&method.sig.ident, version)
+						.unwrap_or(version);
+					function_async_impl(trait_name, method, version, options)
+				})
+		);
+	}
+	result.extend(generate_interface_functions(trait_name, &runtime_interface));
+	result.extend(generate_latest_versions(trait_name, &runtime_interface));
+	result.extend(generate_abi_hashes(&runtime_interface));
+	result.extend(generate_constants(trait_def)?);
+
+	if options.error_enum {
+		result.extend(generate_error_enum(trait_name, &runtime_interface));
+	}
+
+	Ok(result)
+}
+
+/// Generates the `{Trait}Error` enum with one variant per function of the interface.
+///
+/// The variants are named after the functions in pascal case and are used to tag the errors of
+/// the functions, so callers can match on the function that failed. The enum converts into the
+/// name of the function.
+fn generate_error_enum(trait_name: &Ident, runtime_interface: &RuntimeInterface) -> TokenStream {
+	let enum_name = Ident::new(&format!("{}Error", trait_name), Span::call_site());
+	let (variants, names): (Vec<_>, Vec<_>) = runtime_interface.latest_versions()
+		.map(|(_, method)| {
+			let variant = Ident::new(
+				&method.sig.ident.to_string().to_pascal_case(),
+				method.sig.ident.span(),
+			);
+			let name = method.sig.ident.to_string();
+			let cfg_attrs = get_cfg_attributes(method).collect::<Vec<_>>();
+			let doc_string = format!(" [`{}`] failed.", name);
+
+			(
+				quote!( #( #cfg_attrs )* #[doc = #doc_string] #variant ),
+				quote!( #( #cfg_attrs )* #enum_name::#variant => #name ),
+			)
+		})
+		.unzip();
+	let doc_string = format!(
+		" The function of the `{}` runtime interface that failed.",
+		trait_name,
+	);
+
+	quote! {
+		#[doc = #doc_string]
+		#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+		pub enum #enum_name {
+			#( #variants, )*
+		}
+
+		impl From<#enum_name> for &'static str {
+			fn from(error: #enum_name) -> &'static str {
+				match error {
+					#( #names, )*
+				}
+			}
+		}
+	}
+}
+
+/// Generates the `{name}_dispatch` function that calls the version of the given method that is
+/// selected at runtime.
+///
+/// The function is only generated if all versions of the method take the same arguments and
+/// return the same type. It returns `None` for a version that doesn't exist.
+fn generate_dispatch_function(
+	method: &TraitItemMethod,
+	runtime_interface: &RuntimeInterface,
+	options: &Options,
+) -> TokenStream {
+	// The versioned functions take the host side arguments.
+	let signature = |method: &TraitItemMethod| {
+		let method = replace_raw_ptr_arguments(method);
+		let arg_types = get_function_argument_types(&method.sig);
+		let output = &method.sig.output;
+		quote!( ( #( #arg_types ),* ) #output ).to_string()
+	};
+	let method_signature = signature(method);
+	let versions = runtime_interface.versions(&method.sig.ident).collect::<Vec<_>>();
+	if versions.iter().any(|(_, version)| signature(version) != method_signature) {
+		return quote!()
+	}
+
+	let method = replace_raw_ptr_arguments(method);
+	let dispatch_name = Ident::new(&format!("{}_dispatch", method.sig.ident), Span::call_site());
+	let generics = &method.sig.generics;
+	let args = get_function_arguments(&method.sig);
+	let arg_names = get_function_argument_names(&method.sig).collect::<Vec<_>>();
+	let return_type = match &method.sig.output {
+		ReturnType::Default => quote!( () ),
+		ReturnType::Type(_, ty) => quote!( #ty ),
+	};
+	let arms = versions.iter().map(|(version, version_method)| {
+		let function_name = create_function_ident_with_version(&method.sig.ident, *version);
+		let cfg_attrs = get_cfg_attributes(version_method);
+
+		quote! {
+			#( #cfg_attrs )*
+			#version => Some(#function_name( #( #arg_names, )* )),
+		}
+	});
+	let host_impl_cfg = generate_host_impl_cfg(options.registration_only);
+	let cfg_attrs = get_cfg_attributes(&method);
+	let allow_lints = generate_allow_generated_lints();
+	let doc_string = format!(
+		" Calls the given `version` of [`{}`], or returns `None` if there is no such version.",
+		method.sig.ident,
+	);
+
+	quote! {
+		#host_impl_cfg
+		#( #cfg_attrs )*
+		#[doc = #doc_string]
+		#[allow(deprecated)]
+		#allow_lints
+		pub fn #dispatch_name #generics (version: u32, #( #args, )* ) -> Option<#return_type> {
+			match version {
+				#( #arms )*
+				_ => None,
+			}
+		}
+	}
+}
+
+/// Generates the `INTERFACE_FUNCTIONS` constant that lists the name and the version of every
+/// function version that is provided by the interface.
+///
+/// The `#[native_only]` methods are not listed, as they are not provided to the wasm side.
+fn generate_interface_functions(
+	trait_name: &Ident,
+	runtime_interface: &RuntimeInterface,
+) -> TokenStream {
+	let functions = runtime_interface.all_versions()
+		.filter(|(_, method)| !is_native_only_method(method))
+		.map(|(version, method)| {
+			let name = method.sig.ident.to_string();
+			let cfg_attrs = get_cfg_attributes(method);
+			quote!( #( #cfg_attrs )* (#name, #version) )
+		});
+	let doc_string = format!(
+		" All functions with all versions provided by the `{}` runtime interface.",
+		trait_name,
+	);
+
+	quote! {
+		#[doc = #doc_string]
+		///
+		/// Each entry is the name of the function and the version. The entries are ordered by the
+		/// name and then by the version of the function.
+		pub const INTERFACE_FUNCTIONS: &[(&str, u32)] = &[ #( #functions ),* ];
+	}
+}
+
+/// Generates the `{name}_async` function of an `async` interface, that returns the future of the
+/// implementation of the given version.
+///
+/// Unlike the bare function, which drives the future to completion before it returns, this
+/// function can be awaited on the native side. A method taking `self` takes the `Externalities`
+/// as first argument, as they are only provided for a synchronous call.
+fn function_async_impl(
+	trait_name: &Ident,
+	method: &TraitItemMethod,
+	version: u32,
+	options: &Options,
+) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let function_name = create_async_function_ident(&method.sig.ident);
+	let async_name = create_async_function_ident(
+		&create_function_ident_with_version(&method.sig.ident, version),
+	);
+	let args = get_function_arguments(&method.sig).map(FnArg::Typed);
+	let arg_names = get_function_argument_names(&method.sig);
+	let return_value = &method.sig.output;
+	let cfg_attrs = get_cfg_attributes(method);
+	let allow_lints = generate_allow_generated_lints();
+	let host_impl_cfg = generate_host_impl_cfg(options.registration_only);
+	let doc_string = format!(
+		" Returns the future of [`{}`], without driving it to completion.",
+		method.sig.ident,
+	);
+	let (externalities, call) = if takes_self_argument(&method.sig) {
+		(
+			quote!( mut __externalities__: &mut dyn #crate_::Externalities, ),
+			quote!( #trait_name::#async_name(&mut __externalities__, #( #arg_names, )*) ),
+		)
+	} else {
+		let impl_type = if options.no_externalities {
+			quote!( () )
+		} else {
+			quote!( &mut dyn #crate_::Externalities )
+		};
+
+		(quote!(), quote!( <#impl_type as #trait_name>::#async_name( #( #arg_names, )* ) ))
+	};
+
+	quote_spanned! { method.span() =>
+		#host_impl_cfg
+		#( #cfg_attrs )*
+		#[doc = #doc_string]
+		#allow_lints
+		pub async fn #function_name ( #externalities #( #args, )* ) #return_value {
+			#call.await
+		}
+	}
+}
+
+/// Generates `latest_version()` that returns the latest version of all functions of the interface
+/// and `{name}_latest_version()` for every function.
+///
+/// The latest version of a function is the version the bare function calls. As for
+/// `INTERFACE_FUNCTIONS`, the `#[native_only]` methods are left out.
+fn generate_latest_versions(
+	trait_name: &Ident,
+	runtime_interface: &RuntimeInterface,
+) -> TokenStream {
+	let latest_versions = runtime_interface.latest_versions()
+		.filter(|(_, method)| !is_native_only_method(method))
+		.collect::<Vec<_>>();
+	let latest_version = latest_versions.iter().map(|(version, _)| *version).max().unwrap_or(0);
+	let doc_string = format!(
+		" Returns the latest version of all functions of the `{}` runtime interface.",
+		trait_name,
+	);
+
+	let functions = latest_versions.iter().map(|(version, method)| {
+		let name = Ident::new(&format!("{}_latest_version", method.sig.ident), Span::call_site());
+		let doc_string = format!(
+			" Returns the latest version of [`{}`], the version it calls.",
+			method.sig.ident,
+		);
+		let cfg_attrs = get_cfg_attributes(method);
+
+		quote! {
+			#( #cfg_attrs )*
+			#[doc = #doc_string]
+			pub const fn #name() -> u32 {
+				#version
+			}
+		}
+	});
+
+	quote! {
+		#[doc = #doc_string]
+		pub const fn latest_version() -> u32 {
+			#latest_version
+		}
+
+		#( #functions )*
+	}
+}
+
+/// Generates the `{NAME}_VERSION_{X}_ABI_HASH` constant for every function version that has a host
+/// function.
+///
+/// The constant is compiled for the host and the wasm side, so the hashes of the runtime and the
+/// node can be compared to detect a changed signature of a host function.
+fn generate_abi_hashes(runtime_interface: &RuntimeInterface) -> TokenStream {
+	runtime_interface.all_versions()
+		.filter(|(_, method)| !is_native_only_method(method))
+		.map(|(version, method)| {
+			let function_name = create_function_name_const_ident(&method.sig.ident, version);
+			let const_name = Ident::new(&format!("{}_ABI_HASH", function_name), Span::call_site());
+			let abi_hash = abi_hash(method);
+			let cfg_attrs = get_cfg_attributes(method);
+			let doc_string = format!(
+				" The hash of the signature of the host function of version {} of `{}`.",
+				version,
+				method.sig.ident,
+			);
+
+			quote! {
+				#( #cfg_attrs )*
+				#[doc = #doc_string]
+				pub const #const_name: u64 = #abi_hash;
+			}
+		})
+		.collect()
+}
+
+/// Returns the hash of the argument types and the return type the host function of the given
+/// method uses.
+///
+/// The types are hashed as they are declared, after replacing the arguments that are passed
+/// differently, e.g. `#[pass_by(codec)]` arguments. So, a changed type changes the hash, even if
+/// the ffi type of the new type is the same. Zero sized arguments are not passed to the host and
+/// are not hashed. The hash is FNV-1a over the tokens of the types, which doesn't depend on the
+/// compiler version.
+fn abi_hash(method: &TraitItemMethod) -> u64 {
+	let host_method = replace_raw_ptr_arguments(
+		&replace_codec_arguments(&replace_out_arguments(&remove_zero_sized_arguments(method))),
+	);
+	let return_type = match &host_method.sig.output {
+		ReturnType::Default => quote!(),
+		ReturnType::Type(_, ty) => quote!( #ty ),
+	};
+	let arg_types = get_function_argument_types(&host_method.sig);
+	let signature = quote!( ( #( #arg_types ),* ) -> #return_type );
+
+	let mut tokens = String::new();
+	write_normalized_tokens(signature, &mut tokens);
+	tokens.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+		(hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+	})
+}
+
+/// Writes the given tokens separated by a single space, independent of their spans.
+fn write_normalized_tokens(tokens: TokenStream, out: &mut String) {
+	for token in tokens {
+		match token {
+			TokenTree::Group(group) => {
+				let (open, close) = match group.delimiter() {
+					Delimiter::Parenthesis => ("(", ")"),
+					Delimiter::Brace => ("{", "}"),
+					Delimiter::Bracket => ("[", "]"),
+					Delimiter::None => ("", ""),
+				};
+				out.push_str(open);
+				out.push(' ');
+				write_normalized_tokens(group.stream(), out);
+				out.push_str(close);
+			},
+			TokenTree::Ident(ident) => out.push_str(&ident.to_string()),
+			TokenTree::Punct(punct) => out.push(punct.as_char()),
+			TokenTree::Literal(literal) => out.push_str(&literal.to_string()),
+		}
+		out.push(' ');
+	}
+}
+
+/// Generates a `pub const` for every associated constant of the interface trait.
+///
+/// The constants are compiled for the host and the wasm side, so the runtime can read them without
+/// calling into the host.
+fn generate_constants(trait_def: &ItemTrait) -> Result<TokenStream> {
+	trait_def.items.iter()
+		.filter_map(|item| match item {
+			TraitItem::Const(constant) => Some(constant),
+			_ => None,
+		})
+		.map(|constant| {
+			let attrs = &constant.attrs;
+			let name = &constant.ident;
+			let ty = &constant.ty;
+			let value = match &constant.default {
+				Some((_, value)) => value,
+				None => return Err(Error::new(
+					constant.span(),
+					"Associated constants of a runtime interface require a value.",
+				)),
+			};
+
+			Ok(quote_spanned! { constant.span() =>
+				#( #attrs )*
+				pub const #name: #ty = #value;
+			})
+		})
+		.collect()
 }
 
 /// Generates the bare function implementation for the given method for the host and wasm side.
 fn function_for_method(
 	method: &TraitItemMethod,
 	latest_version: u32,
-	is_wasm_only: bool,
+	options: &Options,
 ) -> Result<TokenStream> {
-	let std_impl = if !is_wasm_only {
-		function_std_latest_impl(method, latest_version)?
+	// `#[native_only]` methods don't return across the FFI boundary.
+	if !is_native_only_method(method) {
+		check_return_type_is_ffi_passable(&method.sig)?;
+	}
+
+	let std_impl = if !is_wasm_only_method(method, options.wasm_only) {
+		function_std_latest_impl(method, latest_version, options)?
 	} else {
 		quote!()
 	};
 
-	let no_std_impl = function_no_std_impl(method)?;
+	// `#[native_only]` methods are not callable from wasm.
+	let no_std_impl = if !is_native_only_method(method) {
+		function_no_std_impl(method, options)?
+	} else {
+		quote!()
+	};
 
 	Ok(
 		quote! {
@@ -93,19 +503,27 @@ fn function_for_method(
 }
 
 /// Generates the bare function implementation for `cfg(not(feature = "std"))`.
-fn function_no_std_impl(method: &TraitItemMethod) -> Result<TokenStream> {
+fn function_no_std_impl(method: &TraitItemMethod, options: &Options) -> Result<TokenStream> {
 	let function_name = &method.sig.ident;
-	let host_function_name = create_exchangeable_host_function_ident(&method.sig.ident);
+	let host_function_name = create_exchangeable_host_function_ident(&get_exported_name(method)?);
 	let args = get_function_arguments(&method.sig);
 	let arg_names = get_function_argument_names(&method.sig);
+	let generics = &method.sig.generics;
 	let return_value = &method.sig.output;
-	let attrs = method.attrs.iter().filter(|a| !a.path.is_ident("version"));
+	let attrs = method.attrs.iter().filter(|a| !is_runtime_interface_attribute(a));
+	let track_caller = generate_track_caller(options.track_caller);
+	let allow_lints = generate_allow_generated_lints();
+	// The function only forwards to the host function, so it can always be inlined.
+	let inline = if options.inline_wasm { quote!( #[inline(always)] ) } else { quote!() };
 
 	Ok(
 		quote! {
 			#[cfg(not(feature = "std"))]
 			#( #attrs )*
-			pub fn #function_name( #( #args, )* ) #return_value {
+			#track_caller
+			#inline
+			#allow_lints
+			pub fn #function_name #generics ( #( #args, )* ) #return_value {
 				// Call the host function
 				#host_function_name.get()( #( #arg_names, )* )
 			}
@@ -116,21 +534,49 @@ fn function_no_std_impl(method: &TraitItemMethod) -> Result<TokenStream> {
 /// Generate call to latest function version for `cfg((feature = "std")`
 ///
 /// This should generate simple `fn func(..) { func_version_<latest_version>(..) }`.
+///
+/// If `mockable` is set, the function first checks if there is a mocked implementation for the
+/// current thread and calls this implementation instead.
 fn function_std_latest_impl(
 	method: &TraitItemMethod,
 	latest_version: u32,
+	options: &Options,
 ) -> Result<TokenStream> {
 	let function_name = &method.sig.ident;
 	let args = get_function_arguments(&method.sig).map(FnArg::Typed);
 	let arg_names = get_function_argument_names(&method.sig).collect::<Vec<_>>();
+	let generics = &method.sig.generics;
 	let return_value = &method.sig.output;
-	let attrs = method.attrs.iter().filter(|a| !a.path.is_ident("version"));
+	let attrs = method.attrs.iter().filter(|a| !is_runtime_interface_attribute(a));
 	let latest_function_name = create_function_ident_with_version(&method.sig.ident, latest_version);
+	let track_caller = generate_track_caller(options.track_caller);
+	let allow_lints = generate_allow_generated_lints();
+	let host_impl_cfg = generate_host_impl_cfg(options.registration_only);
+	let (mock_impl, call_mock) = if options.mockable {
+		let mock_static = create_mock_static_ident(&method.sig.ident);
+
+		(
+			generate_mock_implementation(method, &mock_static, &host_impl_cfg),
+			quote! {
+				if let Some(mock) = #mock_static.with(|m| m.get()) {
+					return mock( #( #arg_names, )* )
+				}
+			},
+		)
+	} else {
+		(quote!(), quote!())
+	};
 
 	Ok(quote_spanned! { method.span() =>
-		#[cfg(feature = "std")]
+		#mock_impl
+
+		#host_impl_cfg
 		#( #attrs )*
-		pub fn #function_name( #( #args, )* ) #return_value {
+		#track_caller
+		#allow_lints
+		pub fn #function_name #generics ( #( #args, )* ) #return_value {
+			#call_mock
+
 			#latest_function_name(
 				#( #arg_names, )*
 			)
@@ -138,61 +584,308 @@ fn function_std_latest_impl(
 	})
 }
 
+/// Create the identifier of the thread local that stores the mocked implementation.
+fn create_mock_static_ident(name: &Ident) -> Ident {
+	Ident::new(&format!("__mock_{}__", name), Span::call_site())
+}
+
+/// Generates the thread local that stores the mocked implementation of the given method and the
+/// `mock_*` function to set the mocked implementation.
+fn generate_mock_implementation(
+	method: &TraitItemMethod,
+	mock_static: &Ident,
+	host_impl_cfg: &TokenStream,
+) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let function_name = &method.sig.ident;
+	let mock_function_name = Ident::new(&format!("mock_{}", function_name), Span::call_site());
+	let arg_types = get_function_argument_types(&method.sig).collect::<Vec<_>>();
+	let return_value = &method.sig.output;
+	let for_lifetimes = generate_for_lifetimes(&method.sig);
+	let mock_type = quote!( dyn #for_lifetimes Fn( #( #arg_types ),* ) #return_value );
+	let cfg_attrs = get_cfg_attributes(method).collect::<Vec<_>>();
+	let allow_lints = generate_allow_generated_lints();
+	let doc_string = format!(
+		" Mock the implementation of [`{}`] for the current thread.",
+		function_name,
+	);
+
+	quote! {
+		#host_impl_cfg
+		#( #cfg_attrs )*
+		std::thread_local! {
+			#[allow(non_upper_case_globals)]
+			#allow_lints
+			static #mock_static: #crate_::host::MockedImplementation<#mock_type> =
+				Default::default();
+		}
+
+		#host_impl_cfg
+		#( #cfg_attrs )*
+		#[doc = #doc_string]
+		///
+		/// The mocked implementation is called instead of the real implementation, until the
+		/// returned guard is dropped.
+		#allow_lints
+		pub fn #mock_function_name(
+			mock: impl #for_lifetimes Fn( #( #arg_types ),* ) #return_value + 'static,
+		) -> #crate_::host::RestoreMock<#mock_type> {
+			let mock: std::rc::Rc<#mock_type> = std::rc::Rc::new(mock);
+			#crate_::host::set_mock(&#mock_static, mock)
+		}
+	}
+}
+
 /// Generates the bare function implementation for `cfg(feature = "std")`.
+///
+/// If `version` is deprecated by `deprecated_from`, the function is marked as `#[deprecated]`.
+/// So, anyone wiring up the host function of this version gets a compiler warning.
+///
+/// If `version` is an alias of another version, the function calls the function of this other
+/// version instead of the interface trait. `alias` holds the other version and its method.
+///
+/// If `reentrancy_guard` is set, the function is marked as entered while it is executed. So, a
+/// call of the function while it is still executed is detected and logged.
+///
+/// If `audit` is set, the call is reported to the installed audit sink before the implementation
+/// is called.
 fn function_std_impl(
 	trait_name: &Ident,
 	method: &TraitItemMethod,
 	version: u32,
-	is_wasm_only: bool,
+	deprecated_from: Option<u32>,
+	alias: Option<(u32, &TraitItemMethod)>,
+	options: &Options,
 ) -> Result<TokenStream> {
+	let is_wasm_only = is_wasm_only_method(method, options.wasm_only);
+	// `#[wasm_only]` methods are implemented in an extra trait for `dyn FunctionContext`.
+	let trait_name = &if is_wasm_only && !options.wasm_only {
+		create_wasm_only_trait_ident(trait_name)
+	} else {
+		trait_name.clone()
+	};
+	// The signature of the host function uses the ffi types of the host side arguments, without
+	// the zero sized arguments, which are not passed.
+	let ffi_method = replace_raw_ptr_arguments(
+		&replace_codec_arguments(&replace_out_arguments(&remove_zero_sized_arguments(method))),
+	);
+	let signature = generate_wasm_interface_signature_for_host_function(&ffi_method.sig)?;
+	// This function is called from the host side, so it takes the host side arguments.
+	let method = &replace_raw_ptr_arguments(method);
 	let function_name = create_function_ident_with_version(&method.sig.ident, version);
+	// The span name defaults to the versioned function name.
+	let span_name = get_item_span_name(method)?
+		.map(|name| name.value())
+		.unwrap_or_else(|| function_name.to_string());
+	let enter_span = generate_enter_span(method, &span_name, version);
+	let function_name_const = create_function_name_const_ident(&method.sig.ident, version);
 	let function_name_str = function_name.to_string();
+	let function_name_doc = format!(" The name of version {} of `{}`.", version, method.sig.ident);
+	let signature_function_name = Ident::new(
+		&format!("{}_signature", function_name),
+		Span::call_site(),
+	);
+	let signature_doc = format!(
+		" The signature of the host function of version {} of `{}`.",
+		version,
+		method.sig.ident,
+	);
+	let cfg_attrs = get_cfg_attributes(method).collect::<Vec<_>>();
+	let is_native_only = is_native_only_method(method);
+	let ffi_size = if options.ffi_size && !is_native_only {
+		generate_ffi_size_const(&ffi_method, version, &cfg_attrs)
+	} else {
+		quote!()
+	};
 
 	let crate_ = generate_crate_access();
+	// `#[native_only]` methods don't have a host function.
+	let signature_function = if !is_native_only {
+		quote! {
+			#[cfg(feature = "std")]
+			#( #cfg_attrs )*
+			#[doc = #signature_doc]
+			pub fn #signature_function_name() -> #crate_::sp_wasm_interface::Signature {
+				#signature
+			}
+		}
+	} else {
+		quote!()
+	};
+	// The function context is only borrowed mutable when calling into the interface trait.
+	let context_mut = if alias.is_none() { quote!( mut ) } else { quote!() };
+	let context_type = generate_function_context_type(options.context.as_ref());
 	let args = get_function_arguments(&method.sig).map(FnArg::Typed).chain(
-		// Add the function context as last parameter when this is a wasm only interface.
+		// Add the function context as last parameter when this method is only callable from wasm.
 		iter::from_fn(||
 			if is_wasm_only {
 				Some(
-					parse_quote!(
-						mut __function_context__: &mut dyn #crate_::sp_wasm_interface::FunctionContext
-					)
+					parse_quote!( #context_mut __function_context__: #context_type )
 				)
 			} else {
 				None
 			}
 		).take(1),
 	);
+	let generics = &method.sig.generics;
 	let return_value = &method.sig.output;
-	let attrs = method.attrs.iter().filter(|a| !a.path.is_ident("version"));
+	let attrs = method.attrs.iter().filter(|a| !is_runtime_interface_attribute(a));
+	let has_deprecated_attr = method.attrs.iter().any(|a| a.path.is_ident("deprecated"));
+	let deprecated = match deprecated_from {
+		Some(from) if version >= from && !has_deprecated_attr => {
+			let note = format!("Version {} of `{}` is deprecated.", version, method.sig.ident);
+			quote!( #[deprecated(note = #note)] )
+		},
+		_ => quote!(),
+	};
+	let track_caller = generate_track_caller(options.track_caller);
+	let allow_lints = generate_allow_generated_lints();
+	let host_impl_cfg = generate_host_impl_cfg(options.registration_only);
+	// The arguments of `#[native_only]` methods are not required to implement `Debug` or `Encode`.
+	let log_args = if options.debug_args && !is_native_only {
+		generate_log_arguments(method, version)
+	} else {
+		quote!()
+	};
+	// An alias calls the function of the other version, which records the call.
+	let record_call = if options.record && alias.is_none() && !is_native_only {
+		generate_record_call(method, &function_name_str)
+	} else {
+		quote!()
+	};
+	// An alias calls the function of the other version, which audits the call.
+	let audit_call = if options.audit && alias.is_none() && !is_native_only {
+		generate_audit_call(method, version)
+	} else {
+		quote!()
+	};
+	let reentrancy_guard = if options.reentrancy_guard {
+		quote!( let __entered_function__ = #crate_::host::enter_function(#function_name_str); )
+	} else {
+		quote!()
+	};
 	// Don't make the function public accessible when this is a wasm only interface.
-	let call_to_trait = generate_call_to_trait(trait_name, method, version, is_wasm_only);
+	let call_to_trait = match alias {
+		Some((alias, aliased_method)) =>
+			generate_call_to_alias(method, alias, aliased_method, is_wasm_only)?,
+		None => generate_call_to_trait(trait_name, method, version, is_wasm_only, options),
+	};
+	// An alias calls the function of the other version, which is wrapped.
+	let call_to_trait = match &options.wrap {
+		Some(wrap) if alias.is_none() => quote_spanned! { method.span() =>
+			#wrap(#function_name_str, move || { #call_to_trait })
+		},
+		_ => call_to_trait,
+	};
 
 	Ok(
 		quote_spanned! { method.span() =>
-			#[cfg(feature = "std")]
+			#host_impl_cfg
 			#( #attrs )*
-			fn #function_name( #( #args, )* ) #return_value {
-				#crate_::sp_tracing::enter_span!(#function_name_str);
+			#deprecated
+			#track_caller
+			#allow_lints
+			fn #function_name #generics ( #( #args, )* ) #return_value {
+				#reentrancy_guard
+				#enter_span
+				#log_args
+				#record_call
+				#audit_call
 				#call_to_trait
 			}
+
+			#[cfg(feature = "std")]
+			#( #cfg_attrs )*
+			#[doc = #function_name_doc]
+			pub const #function_name_const: &str = #function_name_str;
+
+			#signature_function
+
+			#ffi_size
 		}
 	)
 }
 
+/// Generates the `{NAME}_VERSION_{X}_FFI_SIZE` constant that holds the number of bytes of the
+/// ffi values of all arguments of the given `ffi_method`.
+fn generate_ffi_size_const(
+	ffi_method: &TraitItemMethod,
+	version: u32,
+	cfg_attrs: &[&Attribute],
+) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let const_name = Ident::new(
+		&format!("{}_FFI_SIZE", create_function_name_const_ident(&ffi_method.sig.ident, version)),
+		Span::call_site(),
+	);
+	let arg_types = get_function_argument_types_without_ref(&ffi_method.sig);
+	let doc_string = format!(
+		" The size in bytes of the ffi values of all arguments of version {} of `{}`.",
+		version,
+		ffi_method.sig.ident,
+	);
+
+	quote! {
+		#[cfg(feature = "std")]
+		#( #cfg_attrs )*
+		#[doc = #doc_string]
+		pub const #const_name: usize = 0 #(
+			+ #crate_::sp_std::mem::size_of::<<#arg_types as #crate_::RIType>::FFIType>()
+		)*;
+	}
+}
+
+/// Generate the call to the function of the `alias` version of the given method.
+///
+/// The arguments and the return value are converted with `Into`, so the aliased version can use
+/// different types. Arguments of the `aliased_method` with `#[default_when_missing(value)]` that
+/// the given method doesn't have are passed as `value`.
+fn generate_call_to_alias(
+	method: &TraitItemMethod,
+	alias: u32,
+	aliased_method: &TraitItemMethod,
+	is_wasm_only: bool,
+) -> Result<TokenStream> {
+	let alias_function_name = create_function_ident_with_version(&method.sig.ident, alias);
+	let mut arg_names = get_function_argument_names(&method.sig);
+	let mut args = Vec::new();
+	for arg in aliased_method.sig.inputs.iter() {
+		let arg = match arg {
+			FnArg::Typed(arg) => arg,
+			FnArg::Receiver(_) => continue,
+		};
+
+		match get_default_argument(arg)? {
+			Some(default) => args.push(quote!( #default )),
+			None => match arg_names.next() {
+				Some(name) => args.push(quote!( #name.into() )),
+				None => break,
+			},
+		}
+	}
+	let function_context = if is_wasm_only { quote!( __function_context__ ) } else { quote!() };
+
+	Ok(quote_spanned! { method.span() =>
+		#alias_function_name( #( #args, )* #function_context ).into()
+	})
+}
+
 /// Generate the call to the interface trait.
+///
+/// In a `no_externalities` interface, the trait is implemented for `()` and called directly. In a
+/// `no_panic` interface, a method taking `self` returns `Err(NoExternalities.into())` when it is
+/// called without `Externalities`. The `Externalities` are provided by `with_externalities` of
+/// this crate, unless another function is given as `with_externalities = path`.
 fn generate_call_to_trait(
 	trait_name: &Ident,
 	method: &TraitItemMethod,
 	version: u32,
 	is_wasm_only: bool,
+	options: &Options,
 ) -> TokenStream {
 	let crate_ = generate_crate_access();
 	let method_name = create_function_ident_with_version(&method.sig.ident, version);
-	let expect_msg = format!(
-		"`{}` called outside of an Externalities-provided environment.",
-		method_name,
-	);
+	let function_name = method_name.to_string();
 	let arg_names = get_function_argument_names(&method.sig);
 
 	if takes_self_argument(&method.sig) {
@@ -203,30 +896,118 @@ fn generate_call_to_trait(
 		};
 
 		let impl_ = quote!( #trait_name::#method_name(&mut #instance, #( #arg_names, )*) );
+		let with_externalities = match &options.with_externalities {
+			Some(path) => quote!( #path ),
+			None => quote!( #crate_::with_externalities ),
+		};
 
 		if is_wasm_only {
 			quote_spanned! { method.span() => #impl_ }
+		} else if options.no_panic {
+			quote_spanned! { method.span() =>
+				#with_externalities(|mut #instance| #impl_)
+					.unwrap_or_else(|| Err(#crate_::NoExternalities.into()))
+			}
 		} else {
+			// The panic is an unlikely path, so it is kept out of line in a `#[cold]` function.
 			quote_spanned! { method.span() =>
-				#crate_::with_externalities(|mut #instance| #impl_).expect(#expect_msg)
+				match #with_externalities(|mut #instance| #impl_) {
+					Some(res) => res,
+					None => #crate_::externalities_unavailable(#function_name),
+				}
 			}
 		}
 	} else {
-		// The name of the trait the interface trait is implemented for
-		let impl_trait_name = if is_wasm_only {
-			quote!( #crate_::sp_wasm_interface::FunctionContext )
+		// The type the interface trait is implemented for
+		let impl_type = if is_wasm_only {
+			generate_function_context_type(options.context.as_ref())
+		} else if options.no_externalities {
+			quote!( () )
 		} else {
-			quote!( #crate_::Externalities )
+			quote!( &mut dyn #crate_::Externalities )
 		};
 
 		quote_spanned! { method.span() =>
-			<&mut dyn #impl_trait_name as #trait_name>::#method_name(
+			<#impl_type as #trait_name>::#method_name(
 				#( #arg_names, )*
 			)
 		}
 	}
 }
 
+/// Generates the debug log of the arguments the given method is called with.
+///
+/// The log is only compiled in with `debug_assertions`.
+fn generate_log_arguments(method: &TraitItemMethod, version: u32) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let function_name = create_function_ident_with_version(&method.sig.ident, version).to_string();
+	let arg_names = get_function_argument_names(&method.sig).collect::<Vec<_>>();
+	let format_string = if arg_names.is_empty() {
+		format!("Calling `{}` without arguments", function_name)
+	} else {
+		format!(
+			"Calling `{}` with arguments: {}",
+			function_name,
+			arg_names.iter()
+				.map(|name| format!("{} = {{:?}}", name.to_token_stream()))
+				.collect::<Vec<_>>()
+				.join(", "),
+		)
+	};
+
+	quote! {
+		#[cfg(debug_assertions)]
+		#crate_::sp_tracing::tracing::debug!(
+			target: "runtime_interface",
+			#format_string,
+			#( #arg_names ),*
+		);
+	}
+}
+
+/// Generates the call that reports the call of the given function version to the recorder.
+///
+/// The arguments are encoded one after another, only when a recorder is set.
+fn generate_record_call(method: &TraitItemMethod, function_name: &str) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let arg_names = get_function_argument_names(&method.sig);
+
+	quote! {
+		#crate_::record::record_call(#function_name, || {
+			#[allow(unused_mut)]
+			let mut args = #crate_::sp_std::vec::Vec::new();
+			#( #crate_::codec::Encode::encode_to(&#arg_names, &mut args); )*
+			args
+		});
+	}
+}
+
+/// Generates the call that reports the call of the given method version to the installed audit
+/// sink, with the SCALE encoded arguments.
+fn generate_audit_call(method: &TraitItemMethod, version: u32) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let function_name = method.sig.ident.to_string();
+	let arg_names = get_function_argument_names(&method.sig);
+
+	quote! {
+		#crate_::audit::audit_call(#function_name, #version, || {
+			#[allow(unused_mut)]
+			let mut args = #crate_::sp_std::vec::Vec::new();
+			#( #crate_::codec::Encode::encode_to(&#arg_names, &mut args); )*
+			args
+		});
+	}
+}
+
+/// Generates the `#[track_caller]` attribute, if requested.
+fn generate_track_caller(track_caller: bool) -> TokenStream {
+	if track_caller {
+		quote!( #[track_caller] )
+	} else {
+		quote!()
+	}
+}
+
 /// Returns if the given `Signature` takes a `self` argument.
 fn takes_self_argument(sig: &Signature) -> bool {
 	match sig.inputs.first() {
@@ -234,3 +1015,296 @@ fn takes_self_argument(sig: &Signature) -> bool {
 		_ => false,
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::remove_old_versions;
+
+	#[test]
+	fn inline_wasm_only_inlines_the_wasm_side() {
+		let method: TraitItemMethod = parse_quote!( fn test(data: u32) {} );
+		let options = Options { inline_wasm: true, ..Default::default() };
+		let inline = quote!( #[inline(always)] ).to_string();
+
+		let no_std_impl = function_no_std_impl(&method, &options).unwrap().to_string();
+		assert!(no_std_impl.contains(&inline));
+
+		let std_impl = function_std_latest_impl(&method, 1, &options).unwrap().to_string();
+		assert!(!std_impl.contains(&inline));
+
+		let no_std_impl = function_no_std_impl(&method, &Options::default()).unwrap().to_string();
+		assert!(!no_std_impl.contains(&inline));
+	}
+
+	#[test]
+	fn generated_lints_are_allowed_on_the_bare_functions() {
+		let method: TraitItemMethod = parse_quote! {
+			fn test(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8, h: u8) {}
+		};
+		let options = Options::default();
+		let allow_lints = generate_allow_generated_lints().to_string();
+
+		let no_std_impl = function_no_std_impl(&method, &options).unwrap().to_string();
+		assert!(no_std_impl.contains(&allow_lints));
+
+		let std_impl = function_std_latest_impl(&method, 1, &options).unwrap().to_string();
+		assert!(std_impl.contains(&allow_lints));
+	}
+
+	#[test]
+	fn must_use_is_forwarded_to_the_bare_functions() {
+		let method: TraitItemMethod = parse_quote! {
+			#[must_use]
+			fn verify(data: &[u8]) -> bool {}
+		};
+		let options = Options::default();
+		let must_use = quote!( #[must_use] ).to_string();
+
+		let no_std_impl = function_no_std_impl(&method, &options).unwrap().to_string();
+		assert!(no_std_impl.contains(&must_use));
+
+		let std_impl = function_std_latest_impl(&method, 1, &options).unwrap().to_string();
+		assert!(std_impl.contains(&must_use));
+	}
+
+	#[test]
+	fn missing_externalities_panic_in_a_cold_function() {
+		let trait_name: Ident = parse_quote!(Interface);
+		let method: TraitItemMethod = parse_quote!( fn test(&mut self, data: u32) -> u32 {} );
+		let cold_function = quote!( externalities_unavailable("test_version_1") ).to_string();
+
+		let call = generate_call_to_trait(&trait_name, &method, 1, false, &Options::default())
+			.to_string();
+		assert!(call.contains(&cold_function));
+		assert!(!call.contains("expect"));
+	}
+
+	#[test]
+	fn wrap_wraps_the_call_to_the_trait() {
+		let trait_name: Ident = parse_quote!(Interface);
+		let method: TraitItemMethod = parse_quote!( fn test(data: u32) -> u32 {} );
+		let options = Options { wrap: Some(parse_quote!(my::wrap_call)), ..Default::default() };
+		let wrapped = "my :: wrap_call ( \"test_version_1\" , move || {";
+
+		let std_impl = function_std_impl(&trait_name, &method, 1, None, None, &options)
+			.unwrap()
+			.to_string();
+		assert!(std_impl.contains(wrapped));
+	}
+
+	#[test]
+	fn span_is_entered_with_interface_tracing() {
+		let trait_name: Ident = parse_quote!(Interface);
+		let method: TraitItemMethod = parse_quote!( fn test(data: u32) -> u32 {} );
+		let no_tracing: TraitItemMethod = parse_quote! {
+			#[no_tracing]
+			fn test(data: u32) -> u32 {}
+		};
+		let options = Options::default();
+		let crate_ = generate_crate_access();
+		let enter_span = quote! {
+			#crate_::sp_tracing::enter_span!("test_version_1", version = 1u32);
+		}.to_string();
+
+		let std_impl = function_std_impl(&trait_name, &method, 1, None, None, &options)
+			.unwrap()
+			.to_string();
+		assert_eq!(cfg!(feature = "interface-tracing"), std_impl.contains(&enter_span));
+
+		let std_impl = function_std_impl(&trait_name, &no_tracing, 1, None, None, &options)
+			.unwrap()
+			.to_string();
+		assert!(!std_impl.contains("enter_span"));
+	}
+
+	#[test]
+	fn abi_hash_changes_with_the_argument_types() {
+		let method: TraitItemMethod = parse_quote!( fn test(data: u32, other: &[u8]) -> u64 {} );
+		let same: TraitItemMethod = parse_quote!( fn test(data : u32 , other : & [ u8 ]) -> u64; );
+		let changed_arg: TraitItemMethod =
+			parse_quote!( fn test(data: u64, other: &[u8]) -> u64 {} );
+		let changed_return: TraitItemMethod = parse_quote!( fn test(data: u32, other: &[u8]) {} );
+
+		assert_eq!(abi_hash(&method), abi_hash(&same));
+		assert_ne!(abi_hash(&method), abi_hash(&changed_arg));
+		assert_ne!(abi_hash(&method), abi_hash(&changed_return));
+	}
+
+	#[test]
+	fn dispatch_function_requires_the_same_signature_in_all_versions() {
+		let dispatch = |trait_def: ItemTrait| {
+			let runtime_interface = get_runtime_interface(&trait_def).unwrap();
+			let (_, method) = runtime_interface.latest_versions().next().unwrap();
+			generate_dispatch_function(method, &runtime_interface, &Options::default()).to_string()
+		};
+
+		let same_signature = dispatch(parse_quote! {
+			trait Interface {
+				fn test(data: u32) -> u32 {}
+				#[version(2)]
+				fn test(input: u32) -> u32 {}
+			}
+		});
+		assert!(same_signature.contains("1u32 => Some ( test_version_1 ( input , ) ) ,"));
+		assert!(same_signature.contains("2u32 => Some ( test_version_2 ( input , ) ) ,"));
+
+		let changed_signature = dispatch(parse_quote! {
+			trait Interface {
+				fn test(data: u32) -> u32 {}
+				#[version(2)]
+				fn test(data: u64) -> u32 {}
+			}
+		});
+		assert!(changed_signature.is_empty());
+	}
+
+	#[test]
+	fn latest_only_skips_the_old_versions() {
+		let trait_def = remove_old_versions(parse_quote! {
+			trait Interface {
+				#[latest_only]
+				fn test(data: u32) {}
+				#[version(2)]
+				fn test(data: u32) {}
+				fn other(data: u32) {}
+			}
+		}, false, None).unwrap();
+
+		let bare_functions = generate(&trait_def, &Options::default()).unwrap().to_string();
+		assert!(!bare_functions.contains("fn test_version_1 ("));
+		assert!(bare_functions.contains("fn test_version_2 ("));
+		assert!(bare_functions.contains("fn other_version_1 ("));
+	}
+
+	#[test]
+	fn no_legacy_skips_the_old_versions_of_all_methods() {
+		let trait_def = remove_old_versions(parse_quote! {
+			trait Interface {
+				fn test(data: u32) {}
+				#[version(2)]
+				fn test(data: u32) {}
+				#[version(3)]
+				fn test(data: u32) {}
+				fn other(data: u32) {}
+			}
+		}, true, None).unwrap();
+
+		let bare_functions = generate(&trait_def, &Options::default()).unwrap().to_string();
+		assert!(!bare_functions.contains("fn test_version_1 ("));
+		assert!(!bare_functions.contains("fn test_version_2 ("));
+		assert!(bare_functions.contains("fn test_version_3 ("));
+		assert!(bare_functions.contains("fn other_version_1 ("));
+	}
+
+	#[test]
+	fn keep_versions_skips_the_versions_before_the_window() {
+		let trait_def = remove_old_versions(parse_quote! {
+			trait Interface {
+				fn test(data: u32) {}
+				#[version(2)]
+				fn test(data: u32) {}
+				#[version(3)]
+				fn test(data: u32) {}
+				#[version(4)]
+				fn test(data: u32) {}
+				#[version(5)]
+				fn test(data: u32) {}
+				fn other(data: u32) {}
+			}
+		}, false, Some(3)).unwrap();
+
+		let bare_functions = generate(&trait_def, &Options::default()).unwrap().to_string();
+		assert!(!bare_functions.contains("fn test_version_1 ("));
+		assert!(!bare_functions.contains("fn test_version_2 ("));
+		assert!(bare_functions.contains("fn test_version_3 ("));
+		assert!(bare_functions.contains("fn test_version_4 ("));
+		assert!(bare_functions.contains("fn test_version_5 ("));
+		assert!(bare_functions.contains("fn other_version_1 ("));
+	}
+
+	#[test]
+	fn keep_versions_rejects_an_alias_of_a_removed_version() {
+		let res = remove_old_versions(parse_quote! {
+			trait Interface {
+				fn test(data: u32) {}
+				#[version(2)]
+				fn test(data: u32) {}
+				#[version(3, alias = 1)]
+				fn test(data: u32);
+			}
+		}, false, Some(2));
+
+		assert!(res.is_err());
+	}
+
+	#[test]
+	fn keep_versions_does_not_overflow() {
+		let trait_def = remove_old_versions(parse_quote! {
+			trait Interface {
+				fn test(data: u32) {}
+				#[version(2)]
+				fn test(data: u32) {}
+			}
+		}, false, Some(u32::max_value())).unwrap();
+
+		let bare_functions = generate(&trait_def, &Options::default()).unwrap().to_string();
+		assert!(bare_functions.contains("fn test_version_1 ("));
+		assert!(bare_functions.contains("fn test_version_2 ("));
+	}
+
+	#[test]
+	fn latest_version_is_the_highest_version() {
+		let trait_def: ItemTrait = parse_quote! {
+			trait Interface {
+				fn test(data: u32) {}
+				#[version(3)]
+				fn test(data: u32) {}
+				#[version(2)]
+				fn test(data: u32) {}
+				fn other(data: u32) {}
+			}
+		};
+		let interface = get_runtime_interface(&trait_def).unwrap();
+		let latest_versions = generate_latest_versions(&trait_def.ident, &interface).to_string();
+
+		assert!(latest_versions.contains("pub const fn latest_version ( ) -> u32 { 3u32 }"));
+		assert!(latest_versions.contains("pub const fn test_latest_version ( ) -> u32 { 3u32 }"));
+		assert!(latest_versions.contains("pub const fn other_latest_version ( ) -> u32 { 1u32 }"));
+	}
+
+	#[test]
+	fn previous_version_passes_the_default_argument() {
+		let method: TraitItemMethod = parse_quote!( fn test(data: u32); );
+		let aliased_method: TraitItemMethod = parse_quote! {
+			fn test(#[default_when_missing(7)] factor: u32, data: u32) {}
+		};
+
+		let call = generate_call_to_alias(&method, 2, &aliased_method, false).unwrap().to_string();
+		assert_eq!(quote!( test_version_2(7, data.into(),).into() ).to_string(), call);
+	}
+
+	#[test]
+	fn latest_version_does_not_depend_on_the_declaration_order() {
+		let trait_def: ItemTrait = parse_quote! {
+			trait Interface {
+				#[version(3)]
+				fn func(data: u32) {}
+				fn func(data: u32) {}
+				#[version(2)]
+				fn func(data: u32) {}
+			}
+		};
+		let interface = get_runtime_interface(&trait_def).unwrap();
+
+		assert_eq!(vec![1, 2, 3], interface.all_versions().map(|(v, _)| v).collect::<Vec<_>>());
+
+		let (version, method) = interface.latest_versions().next().unwrap();
+		assert_eq!(3, version);
+
+		let std_impl = function_std_latest_impl(method, version, &Options::default())
+			.unwrap()
+			.to_string();
+		assert!(std_impl.contains("func_version_3 ("));
+	}
+}
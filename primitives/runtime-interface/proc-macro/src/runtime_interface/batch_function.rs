@@ -0,0 +1,293 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Adds the batch functions for all methods annotated with `#[batch]`.
+//!
+//! The batch function of a method `name` is added as method `name_batch` to the trait. It takes
+//! a `Vec` of all arguments of the method and calls the method on the host side for each element.
+//! So, calling the batch function crosses the wasm/host boundary only once.
+
+use crate::utils::{
+	create_function_ident_with_version, get_function_arguments, get_item_version,
+	get_cfg_attributes, generate_crate_access, is_wasm_only_method, is_callback_argument,
+	get_borrowed_return_value,
+};
+
+use syn::{
+	ItemTrait, TraitItem, TraitItemMethod, Result, Error, FnArg, Type, ReturnType, Ident,
+	spanned::Spanned, parse_quote,
+};
+
+use proc_macro2::{Span, Literal, TokenStream};
+
+use quote::quote;
+
+use std::collections::BTreeMap;
+
+/// Adds the batch function for every method annotated with `#[batch]` to the given trait.
+pub fn add_batch_functions(mut trait_def: ItemTrait) -> Result<ItemTrait> {
+	// The batch function has the versions of the annotated versions of the method, so annotating
+	// another version later doesn't change the existing versions of the batch function.
+	let mut first_versions = BTreeMap::<Ident, u32>::new();
+	let mut batched_methods = BTreeMap::<Ident, Vec<(u32, &TraitItemMethod)>>::new();
+	for item in &trait_def.items {
+		let method = match item {
+			TraitItem::Method(method) => method,
+			_ => continue,
+		};
+
+		let version = get_item_version(method)?.map_or(1, |version| version.version);
+		let first_version = first_versions.entry(method.sig.ident.clone()).or_insert(version);
+		*first_version = version.min(*first_version);
+
+		if method.attrs.iter().any(|a| a.path.is_ident("batch")) {
+			batched_methods.entry(method.sig.ident.clone()).or_default().push((version, method));
+		}
+	}
+
+	let mut batch_functions = Vec::new();
+	for (name, mut methods) in batched_methods {
+		methods.sort_by_key(|(version, _)| *version);
+
+		let first_version = first_versions[&name];
+		let (first_batched, first_method) = methods[0];
+		if first_batched != first_version {
+			return Err(Error::new(
+				first_method.sig.span(),
+				format!(
+					"`#[batch]` is required on version {} of `{}`, as the versions of `{}_batch` \
+					are the versions of `{}`",
+					first_version,
+					name,
+					name,
+					name,
+				),
+			))
+		}
+
+		// The versions between two annotated versions are skipped by the batch function.
+		for (i, (version, method)) in methods.iter().enumerate() {
+			let next_version = methods.get(i + 1).map_or(version + 1, |(next, _)| *next);
+			let skipped = (version + 1..next_version).collect::<Vec<_>>();
+
+			batch_functions.push(
+				TraitItem::Method(generate_batch_function(&trait_def.ident, method, &skipped)?)
+			);
+		}
+	}
+
+	trait_def.items.extend(batch_functions);
+	Ok(trait_def)
+}
+
+/// Generates the batch function for the given method, with the same version as the method.
+///
+/// `skipped` are the versions of the batch function that follow this version and that have no
+/// annotated method.
+fn generate_batch_function(
+	trait_name: &Ident,
+	method: &TraitItemMethod,
+	skipped: &[u32],
+) -> Result<TraitItemMethod> {
+	let version = match get_item_version(method)? {
+		Some(version) if version.alias.is_some() => return Err(Error::new(
+			method.sig.span(),
+			"`#[batch]` is not supported on versions with `alias`",
+		)),
+		Some(version) => version.version,
+		None => 1,
+	};
+	if is_wasm_only_method(method, false) {
+		return Err(
+			Error::new(method.sig.span(), "`#[batch]` is not supported on `#[wasm_only]` methods")
+		)
+	}
+	if get_borrowed_return_value(&method.sig).is_some() {
+		return Err(Error::new(
+			method.sig.output.span(),
+			"`#[batch]` does not support methods that return a slice of an argument",
+		))
+	}
+	let function_name = create_function_ident_with_version(&method.sig.ident, version);
+	let batch_name = Ident::new(&format!("{}_batch", method.sig.ident), Span::call_site());
+	let receiver = method.sig.receiver();
+	let self_arg = match receiver {
+		Some(FnArg::Receiver(receiver)) if receiver.mutability.is_some() => quote!( &mut *self, ),
+		Some(_) => quote!( &*self, ),
+		None => quote!(),
+	};
+
+	let pass_by_arg = method.sig.inputs.iter().find(|arg| match arg {
+		FnArg::Typed(arg) => arg.attrs.iter().any(|a| a.path.is_ident("pass_by")),
+		FnArg::Receiver(_) => false,
+	});
+	if let Some(arg) = pass_by_arg {
+		return Err(Error::new(arg.span(), "`#[batch]` does not support `#[pass_by]` arguments"))
+	}
+	let callback_arg = method.sig.inputs.iter().find(|arg| match arg {
+		FnArg::Typed(arg) => is_callback_argument(arg),
+		FnArg::Receiver(_) => false,
+	});
+	if let Some(arg) = callback_arg {
+		return Err(Error::new(arg.span(), "`#[batch]` does not support `#[callback]` arguments"))
+	}
+
+	let crate_ = generate_crate_access();
+	let mut arg_names = Vec::new();
+	let mut arg_types = Vec::new();
+	let mut call_args = Vec::new();
+	for arg in get_function_arguments(&method.sig) {
+		let name = &arg.pat;
+		let (ty, is_ref) = element_type(&arg.ty, &crate_)?;
+		call_args.push(if is_ref { quote!( &#name ) } else { quote!( #name ) });
+		arg_names.push(name.clone());
+		arg_types.push(ty);
+	}
+
+	if arg_names.is_empty() {
+		return Err(Error::new(method.sig.span(), "`#[batch]` requires at least one argument"))
+	}
+
+	let call = quote! {
+		<Self as #trait_name>::#function_name( #self_arg #( #call_args ),* )
+	};
+	let (output, body) = match &method.sig.output {
+		ReturnType::Default => (
+			quote!(),
+			quote! {
+				for ( #( #arg_names, )* ) in items {
+					#call;
+				}
+			},
+		),
+		ReturnType::Type(_, ty) => (
+			quote!( -> #crate_::sp_std::vec::Vec<#ty> ),
+			quote! {
+				let mut results = #crate_::sp_std::vec::Vec::with_capacity(items.len());
+				for ( #( #arg_names, )* ) in items {
+					results.push(#call);
+				}
+				results
+			},
+		),
+	};
+	let receiver = receiver.map(|r| quote!( #r, ));
+	let cfg_attrs = get_cfg_attributes(method);
+	let latest_only = method.attrs.iter().filter(|a| a.path.is_ident("latest_only"));
+	let batch_version = Literal::u32_unsuffixed(version);
+	let skipped = skipped.iter().map(|version| Literal::u32_unsuffixed(*version));
+	let doc_string = format!(
+		" Calls [`{}`] for every element of `items`, with one call into the host.",
+		method.sig.ident,
+	);
+
+	Ok(parse_quote! {
+		#( #cfg_attrs )*
+		#( #latest_only )*
+		#[doc = #doc_string]
+		#[version(#batch_version)]
+		#( #[skip_version(#skipped)] )*
+		fn #batch_name(
+			#receiver
+			items: #crate_::sp_std::vec::Vec<( #( #arg_types, )* )>,
+		) #output {
+			#body
+		}
+	})
+}
+
+/// Returns the owned type that is used to store an argument of the given type in the batch and if
+/// the argument needs to be passed as reference.
+fn element_type(ty: &Type, crate_: &TokenStream) -> Result<(Type, bool)> {
+	match ty {
+		Type::Reference(type_ref) if type_ref.mutability.is_some() => Err(Error::new(
+			ty.span(),
+			"`#[batch]` does not support `&mut` arguments",
+		)),
+		Type::Reference(type_ref) => {
+			let owned = match &*type_ref.elem {
+				Type::Slice(slice) => {
+					let elem = &slice.elem;
+					parse_quote!( #crate_::sp_std::vec::Vec<#elem> )
+				},
+				Type::Path(path) if path.qself.is_none() && path.path.is_ident("str") => return Err(
+					Error::new(ty.span(), "`#[batch]` does not support `&str` arguments")
+				),
+				elem => elem.clone(),
+			};
+
+			Ok((owned, true))
+		},
+		ty => Ok((ty.clone(), false)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn batch_versions_follow_the_method_versions() {
+		let trait_def: ItemTrait = parse_quote! {
+			trait Interface {
+				#[batch]
+				#[version(2)]
+				fn test(data: u32) {}
+				#[batch]
+				fn test(data: u32) {}
+			}
+		};
+
+		let trait_def = add_batch_functions(trait_def).unwrap();
+		let batch_functions = trait_def.items[2..].iter().map(|item| match item {
+			TraitItem::Method(method) => quote!( #method ).to_string(),
+			_ => panic!("Only methods are added"),
+		}).collect::<Vec<_>>();
+
+		assert!(batch_functions[0].contains("version ( 1 )"));
+		assert!(batch_functions[0].contains("test_version_1 ("));
+		assert!(batch_functions[1].contains("version ( 2 )"));
+		assert!(batch_functions[1].contains("test_version_2 ("));
+	}
+
+	#[test]
+	fn batch_skips_the_versions_without_batch() {
+		let trait_def: ItemTrait = parse_quote! {
+			trait Interface {
+				#[batch]
+				fn test(data: u32) {}
+				#[version(2)]
+				fn test(data: u32) {}
+				#[batch]
+				#[version(3)]
+				fn test(data: u32) {}
+			}
+		};
+
+		let trait_def = add_batch_functions(trait_def).unwrap();
+		let batch_functions = trait_def.items[3..].iter().map(|item| match item {
+			TraitItem::Method(method) => quote!( #method ).to_string(),
+			_ => panic!("Only methods are added"),
+		}).collect::<Vec<_>>();
+
+		assert_eq!(2, batch_functions.len());
+		assert!(batch_functions[0].contains("version ( 1 )"));
+		assert!(batch_functions[0].contains("skip_version ( 2 )"));
+		assert!(batch_functions[1].contains("version ( 3 )"));
+		assert!(batch_functions[1].contains("test_version_3 ("));
+		assert!(!batch_functions[1].contains("skip_version"));
+	}
+}
@@ -0,0 +1,146 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates the handle types of the methods annotated with `#[handle]`.
+//!
+//! A method `create_hasher` returning `u32` that is annotated with `#[handle(Hasher)]` returns a
+//! `HasherHandle` instead, a newtype that is passed as `u32`. The methods that use the handle take
+//! a `HasherHandle` argument, so the handle can not be mixed up with any other integer. Without a
+//! name, `#[handle]` names the type after the method, `CreateHasherHandle`.
+
+use crate::utils::{generate_crate_access, get_cfg_attributes};
+
+use syn::{
+	ItemTrait, TraitItem, TraitItemMethod, Result, Error, Type, ReturnType, Ident, Attribute,
+	spanned::Spanned, parse_quote,
+};
+
+use proc_macro2::{Span, TokenStream};
+
+use quote::quote;
+
+use inflector::Inflector;
+
+use std::collections::BTreeMap;
+
+/// Replaces the `u32` return type of every method annotated with `#[handle]` in the given trait
+/// by its handle type.
+///
+/// Returns the trait and the declarations of the handle types.
+pub fn add_handle_types(mut trait_def: ItemTrait) -> Result<(ItemTrait, TokenStream)> {
+	// Every handle type is declared once, even if several versions return it.
+	let mut handle_types = BTreeMap::<Ident, TraitItemMethod>::new();
+	for item in trait_def.items.iter_mut() {
+		let method = match item {
+			TraitItem::Method(method) => method,
+			_ => continue,
+		};
+		let attr = match method.attrs.iter().position(|a| a.path.is_ident("handle")) {
+			Some(pos) => method.attrs.remove(pos),
+			None => continue,
+		};
+
+		let handle_type = parse_handle_attribute(&attr, method)?;
+		replace_return_type(method, &handle_type)?;
+		handle_types.entry(handle_type).or_insert_with(|| method.clone());
+	}
+
+	let declarations = handle_types.iter()
+		.map(|(handle_type, method)| generate_handle_type(handle_type, method))
+		.collect();
+
+	Ok((trait_def, declarations))
+}
+
+/// Returns the name of the handle type that is given by the `#[handle(Name)]` attribute of the
+/// given method.
+fn parse_handle_attribute(attr: &Attribute, method: &TraitItemMethod) -> Result<Ident> {
+	let name = if attr.tokens.is_empty() {
+		method.sig.ident.to_string().to_pascal_case()
+	} else {
+		attr.parse_args::<Ident>()
+			.map_err(|_| Error::new(attr.span(), "Expected `#[handle]` or `#[handle(Name)]`"))?
+			.to_string()
+	};
+
+	Ok(Ident::new(&format!("{}Handle", name), Span::call_site()))
+}
+
+/// Replaces the `u32` return type of the given method by the given handle type.
+///
+/// The default implementation still returns the `u32`, which is wrapped into the handle.
+fn replace_return_type(method: &mut TraitItemMethod, handle_type: &Ident) -> Result<()> {
+	let returns_u32 = match &method.sig.output {
+		ReturnType::Type(_, ty) => match &**ty {
+			Type::Path(path) => path.qself.is_none() && path.path.is_ident("u32"),
+			_ => false,
+		},
+		ReturnType::Default => false,
+	};
+	if !returns_u32 {
+		return Err(
+			Error::new(method.sig.output.span(), "`#[handle]` requires a `u32` return value")
+		)
+	}
+
+	method.sig.output = parse_quote!( -> #handle_type );
+	if let Some(block) = method.default.take() {
+		// The closure keeps `return` in the implementation working.
+		method.default = Some(parse_quote!({
+			#[allow(clippy::redundant_closure_call)]
+			let handle = (|| -> u32 #block)();
+			#handle_type(handle)
+		}));
+	}
+
+	Ok(())
+}
+
+/// Generates the declaration of the given handle type, which is returned by the given method.
+fn generate_handle_type(handle_type: &Ident, method: &TraitItemMethod) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let cfg_attrs = get_cfg_attributes(method).collect::<Vec<_>>();
+	let doc_string = format!(" The handle that is returned by [`{}`].", method.sig.ident);
+
+	quote! {
+		#( #cfg_attrs )*
+		#[doc = #doc_string]
+		#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+		pub struct #handle_type(u32);
+
+		#( #cfg_attrs )*
+		impl #crate_::pass_by::PassBy for #handle_type {
+			type PassBy = #crate_::pass_by::Inner<#handle_type, u32>;
+		}
+
+		#( #cfg_attrs )*
+		impl #crate_::pass_by::PassByInner for #handle_type {
+			type Inner = u32;
+
+			fn into_inner(self) -> u32 {
+				self.0
+			}
+
+			fn inner(&self) -> &u32 {
+				&self.0
+			}
+
+			fn from_inner(inner: u32) -> Self {
+				Self(inner)
+			}
+		}
+	}
+}
@@ -25,24 +25,46 @@ use crate::utils::{
 	get_function_argument_types_without_ref, get_function_argument_types_ref_and_mut,
 	get_function_argument_names_and_types_without_ref, get_function_arguments,
 	get_function_argument_types, create_exchangeable_host_function_ident, get_runtime_interface,
-	create_function_ident_with_version,
+	create_function_ident_with_version, generate_enter_span, get_exported_name, get_cfg_attributes,
+	create_host_function_feature_name,
+	replace_raw_ptr_arguments, replace_out_arguments, is_out_argument, is_wasm_only_method,
+	replace_codec_arguments, get_argument_pass_by, get_return_value_pass_by, PassByStrategy,
+	is_ffi_argument_attribute, is_native_only_method, is_zero_sized_argument,
+	remove_zero_sized_arguments, generate_allow_generated_lints, is_compressed_argument,
+	get_no_alloc_max, is_byte_slices_argument, get_borrowed_return_value, generate_for_lifetimes,
+	BorrowedReturnValue,
 };
 
+use super::Options;
+
 use syn::{
-	ItemTrait, TraitItemMethod, Result, ReturnType, Ident, Pat, Error, Signature, spanned::Spanned,
+	ItemTrait, TraitItemMethod, Result, ReturnType, Ident, Pat, Error, Signature, FnArg, Type,
+	TraitItem, spanned::Spanned,
 };
 
 use proc_macro2::{TokenStream, Span};
 
-use quote::{quote, ToTokens};
+use quote::{quote, quote_spanned, ToTokens};
 
 use inflector::Inflector;
 
 use std::iter::{Iterator, self};
 
+/// Returns the given trait definition without the `#[native_only]` methods, as these methods don't
+/// have a host function.
+fn remove_native_only_methods(trait_def: &ItemTrait) -> ItemTrait {
+	let mut trait_def = trait_def.clone();
+	trait_def.items.retain(|item| match item {
+		TraitItem::Method(method) => !is_native_only_method(method),
+		_ => true,
+	});
+	trait_def
+}
+
 /// Generate the extern host functions for wasm and the `HostFunctions` struct that provides the
 /// implementations for the host functions on the host.
-pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
+pub fn generate(trait_def: &ItemTrait, options: &Options) -> Result<TokenStream> {
+	let trait_def = &remove_native_only_methods(trait_def);
 	let trait_name = &trait_def.ident;
 	let extern_host_function_impls = get_runtime_interface(trait_def)?
 		.latest_versions()
@@ -56,7 +78,11 @@ pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream
 			t.extend(generate_exchangeable_host_function(m)?);
 			Ok::<_, Error>(t)
 		})?;
-	let host_functions_struct = generate_host_functions_struct(trait_def, is_wasm_only)?;
+	let host_functions_struct = generate_host_functions_struct(trait_def, options)?;
+	let conversion_trait_assertions = get_runtime_interface(trait_def)?
+		.all_versions()
+		.map(|(_, method)| generate_conversion_trait_assertions(method))
+		.collect::<Vec<_>>();
 
 	Ok(
 		quote! {
@@ -73,6 +99,8 @@ pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream
 			#exchangeable_host_functions
 
 			#host_functions_struct
+
+			#( #conversion_trait_assertions )*
 		}
 	)
 }
@@ -81,28 +109,119 @@ pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream
 fn generate_extern_host_function(method: &TraitItemMethod, version: u32, trait_name: &Ident) -> Result<TokenStream> {
 	let crate_ = generate_crate_access();
 	let args = get_function_arguments(&method.sig);
-	let arg_types = get_function_argument_types_without_ref(&method.sig);
-	let arg_types2 = get_function_argument_types_without_ref(&method.sig);
-	let arg_names = get_function_argument_names(&method.sig);
-	let arg_names2 = get_function_argument_names(&method.sig);
-	let arg_names3 = get_function_argument_names(&method.sig);
+	// Zero sized arguments are not passed to the host.
+	let zero_sized_args = get_function_arguments(&method.sig)
+		.filter(is_zero_sized_argument)
+		.map(|arg| arg.pat);
+	let passed_method = remove_zero_sized_arguments(method);
+	// `#[out]` arguments are passed as `OutVec`, `#[pass_by(..)]` arguments as the type of their
+	// strategy, `#[compressed]` arguments as `Compressed` and `&[&[u8]]` arguments as `ByteSlices`.
+	let ffi_method = replace_codec_arguments(&replace_out_arguments(&passed_method));
+	let arg_types = get_function_argument_types_without_ref(&ffi_method.sig);
+	let arg_names = get_function_argument_names(&passed_method.sig);
+	let arg_names3 = get_function_argument_names(&passed_method.sig);
+	let out_args = get_function_argument_names(&passed_method.sig)
+		.zip(passed_method.sig.inputs.iter().filter_map(|arg| match arg {
+			FnArg::Typed(arg) => Some((
+				is_out_argument(arg),
+				get_argument_pass_by(arg),
+				is_compressed_argument(arg),
+				is_byte_slices_argument(arg),
+			)),
+			FnArg::Receiver(_) => None,
+		}))
+		.collect::<Vec<_>>();
+	let into_ffi_values = out_args.iter()
+		.zip(get_function_argument_types_without_ref(&passed_method.sig))
+		.map(|((name, (is_out, pass_by, is_compressed, is_byte_slices)), ty)| if *is_out {
+			quote! {
+				let #name = #crate_::wasm::WrappedOutVec::new(#name);
+			}
+		} else if *is_compressed {
+			quote! {
+				let #name = #crate_::compression::Compressed::into_ffi_value(#name);
+			}
+		} else if *is_byte_slices {
+			quote! {
+				let #name = #crate_::ByteSlices::into_ffi_value(#name);
+			}
+		} else if let Some(PassByStrategy::Width) = pass_by {
+			let pass_by_type = PassByStrategy::Width.pass_by_type(&ty);
+
+			// `Width` takes the slice of a `&[T]` or a `Vec<T>`, which is passed without a copy.
+			quote! {
+				let #name = <#pass_by_type>::into_ffi_value(&#name[..]);
+			}
+		} else if let Some(strategy) = pass_by {
+			let pass_by_type = strategy.pass_by_type(&ty);
+
+			quote! {
+				let #name = <
+					#pass_by_type as #crate_::pass_by::PassByImpl<#ty>
+				>::into_ffi_value(&#name);
+			}
+		} else {
+			quote! {
+				let #name = <#ty as #crate_::wasm::IntoFFIValue>::into_ffi_value(&#name);
+			}
+		});
+	let write_back_out_args = out_args.iter()
+		.filter(|(_, (is_out, _, _, _))| *is_out)
+		.map(|(name, _)| quote!( #name.write_back(); ));
 	let function = &method.sig.ident;
-	let ext_function = create_host_function_ident(&method.sig.ident, version, trait_name);
+	let generics = &method.sig.generics;
+	// The encoded arguments of `#[no_alloc(max = N)]` methods are stored in an array on the stack.
+	let enter_scratch = get_no_alloc_max(method)?.map(|max| {
+		let name = function.to_string();
+
+		quote! {
+			let mut scratch = [0u8; #max];
+			let _scratch = #crate_::wasm::ScratchArguments::enter(#name, &mut scratch);
+		}
+	});
+	let ext_function = create_host_function_ident(&get_exported_name(method)?, version, trait_name);
 	let doc_string = format!(
 		" Default extern host function implementation for [`super::{}`].",
 		method.sig.ident,
 	);
 	let return_value = &method.sig.output;
+	let cfg_attrs = get_cfg_attributes(method);
+	let allow_lints = generate_allow_generated_lints();
 
-	let ffi_return_value = match method.sig.output {
+	let ffi_return_value = match ffi_method.sig.output {
 		ReturnType::Default => quote!(),
 		ReturnType::Type(_, ref ty) => quote! {
 			-> <#ty as #crate_::RIType>::FFIType
 		},
 	};
 
+	// The host returns the offset and the length of the slice in the argument it borrows from.
+	let borrowed_return_value = get_borrowed_return_value(&method.sig);
+	let keep_source = borrowed_return_value.as_ref().map(|borrowed| {
+		let source = &borrowed.source;
+		quote!( let __source__: &[u8] = #source; )
+	});
+
 	let convert_return_value = match return_value {
 		ReturnType::Default => quote!(),
+		ReturnType::Type(..) if borrowed_return_value.is_some() => match borrowed_return_value {
+			Some(BorrowedReturnValue { optional: true, .. }) => quote! {
+				#crate_::BorrowedSlice::from_ffi_value(__source__, result)
+			},
+			_ => quote! {
+				#crate_::BorrowedSlice::from_ffi_value(__source__, result)
+					.expect("The host only returns `None` for `Option` return values; qed")
+			},
+		},
+		ReturnType::Type(_, ref ty) if get_return_value_pass_by(method).is_some() => {
+			let pass_by_type = get_return_value_pass_by(method)
+				.expect("Checked by the match guard; qed")
+				.pass_by_type(ty);
+
+			quote! {
+				<#pass_by_type as #crate_::pass_by::PassByImpl<#ty>>::from_ffi_value(result)
+			}
+		},
 		ReturnType::Type(_, ref ty) => quote! {
 			<#ty as #crate_::wasm::FromFFIValue>::from_ffi_value(result)
 		}
@@ -110,8 +229,10 @@ fn generate_extern_host_function(method: &TraitItemMethod, version: u32, trait_n
 
 	Ok(
 		quote! {
+			#( #cfg_attrs )*
 			#[doc = #doc_string]
-			pub fn #function ( #( #args ),* ) #return_value {
+			#allow_lints
+			pub fn #function #generics ( #( #args ),* ) #return_value {
 				extern "C" {
 					/// The extern function.
 					pub fn #ext_function (
@@ -119,15 +240,19 @@ fn generate_extern_host_function(method: &TraitItemMethod, version: u32, trait_n
 					) #ffi_return_value;
 				}
 
+				#( let _ = #zero_sized_args; )*
+
+				#enter_scratch
+
+				#keep_source
+
 				// Generate all wrapped ffi values.
-				#(
-					let #arg_names2 = <#arg_types2 as #crate_::wasm::IntoFFIValue>::into_ffi_value(
-						&#arg_names2,
-					);
-				)*
+				#( #into_ffi_values )*
 
 				let result = unsafe { #ext_function( #( #arg_names3.get() ),* ) };
 
+				#( #write_back_out_args )*
+
 				#convert_return_value
 			}
 		}
@@ -139,17 +264,23 @@ fn generate_exchangeable_host_function(method: &TraitItemMethod) -> Result<Token
 	let crate_ = generate_crate_access();
 	let arg_types = get_function_argument_types(&method.sig);
 	let function = &method.sig.ident;
-	let exchangeable_function = create_exchangeable_host_function_ident(&method.sig.ident);
+	let exchangeable_function =
+		create_exchangeable_host_function_ident(&get_exported_name(method)?);
 	let doc_string = format!(" Exchangeable host function used by [`{}`].", method.sig.ident);
 	let output = &method.sig.output;
+	let for_lifetimes = generate_for_lifetimes(&method.sig);
+	let cfg_attrs = get_cfg_attributes(method);
+	let allow_lints = generate_allow_generated_lints();
 
 	Ok(
 		quote! {
 			#[cfg(not(feature = "std"))]
+			#( #cfg_attrs )*
 			#[allow(non_upper_case_globals)]
+			#allow_lints
 			#[doc = #doc_string]
 			pub static #exchangeable_function : #crate_::wasm::ExchangeableFunction<
-				fn ( #( #arg_types ),* ) #output
+				#for_lifetimes fn ( #( #arg_types ),* ) #output
 			> = #crate_::wasm::ExchangeableFunction::new(extern_host_function_impls::#function);
 		}
 	)
@@ -157,16 +288,67 @@ fn generate_exchangeable_host_function(method: &TraitItemMethod) -> Result<Token
 
 /// Generate the `HostFunctions` struct that implements `wasm-interface::HostFunctions` to provide
 /// implementations for the extern host functions.
-fn generate_host_functions_struct(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
+fn generate_host_functions_struct(trait_def: &ItemTrait, options: &Options) -> Result<TokenStream> {
 	let crate_ = generate_crate_access();
+	let runtime_interface = get_runtime_interface(trait_def)?;
 
-	let host_functions = get_runtime_interface(trait_def)?
+	// With `host_function_features`, every host function is registered unless its feature disables
+	// it.
+	let feature_gate = |method: &TraitItemMethod| -> Result<TokenStream> {
+		if options.host_function_features {
+			let feature = create_host_function_feature_name(
+				&get_exported_name(method)?,
+				&trait_def.ident,
+			);
+			Ok(quote!( #[cfg(not(feature = #feature))] ))
+		} else {
+			Ok(quote!())
+		}
+	};
+
+	let host_functions = runtime_interface
 		.all_versions()
-		.map(|(version, method)|
-			generate_host_function_implementation(&trait_def.ident, method, version, is_wasm_only)
+		.map(|(version, method)| {
+			let feature_gate = feature_gate(method)?;
+			let host_function = generate_host_function_implementation(
+				&trait_def.ident,
+				method,
+				version,
+				is_wasm_only_method(method, options.wasm_only),
+				options.registration_only,
+				options.context.is_some(),
+			)?;
+
+			Ok(quote!( #feature_gate #host_function ))
+		})
+		.chain(
+			runtime_interface.skipped_versions()
+				.map(|(version, method)| {
+					let feature_gate = feature_gate(method)?;
+					let host_function = generate_skipped_host_function_implementation(
+						&trait_def.ident,
+						method,
+						version,
+					)?;
+
+					Ok(quote!( #feature_gate #host_function ))
+				})
 		)
 		.collect::<Result<Vec<_>>>()?;
 
+	let install_host_simulator = if options.host_simulator {
+		quote! {
+			/// Registers all versions of all host functions of the interface in the given
+			/// simulator.
+			#[cfg(feature = "std")]
+			pub fn install_host_simulator(simulator: &mut #crate_::simulator::HostSimulator) {
+				simulator.register(host_functions())
+			}
+		}
+	} else {
+		quote!()
+	};
+
 	Ok(
 		quote! {
 			/// Provides implementations for the extern host functions.
@@ -179,6 +361,190 @@ fn generate_host_functions_struct(trait_def: &ItemTrait, is_wasm_only: bool) ->
 					vec![ #( #host_functions ),* ]
 				}
 			}
+
+			/// Returns all versions of all host functions of the interface.
+			///
+			/// The same as `HostFunctions::host_functions()`, usable to register the host functions
+			/// in a custom executor.
+			#[cfg(feature = "std")]
+			pub fn host_functions() -> Vec<&'static dyn #crate_::sp_wasm_interface::Function> {
+				<HostFunctions as #crate_::sp_wasm_interface::HostFunctions>::host_functions()
+			}
+
+			#install_host_simulator
+		}
+	)
+}
+
+/// Generate the `benches` module with the conversions the host functions of all function versions
+/// do, without calling the implementation.
+///
+/// For every function version `name_version_X`, the module contains `name_version_X_arguments` that
+/// converts the wasm values into the host values of the arguments. If the function returns a
+/// value, it also contains `name_version_X_return_value` that converts this value into the wasm
+/// value. The module is only compiled with the `bench` feature of the crate declaring the
+/// interface.
+pub fn generate_conversion_benches(trait_def: &ItemTrait) -> Result<TokenStream> {
+	let trait_def = &remove_native_only_methods(trait_def);
+	let crate_ = generate_crate_access();
+	let benches = get_runtime_interface(trait_def)?
+		.all_versions()
+		.map(|(version, method)| generate_conversion_bench(&trait_def.ident, method, version))
+		.collect::<Result<Vec<_>>>()?;
+
+	Ok(
+		quote! {
+			/// The conversions between the wasm values and the host values, the host functions of
+			/// the interface do. Each function only does the conversion, so the time spent in the
+			/// conversion can be measured with dummy data.
+			#[cfg(all(feature = "std", feature = "bench"))]
+			pub mod benches {
+				use super::*;
+				use #crate_::sp_wasm_interface::{FunctionContext, Value};
+
+				#( #benches )*
+			}
+		}
+	)
+}
+
+/// Generate the `roundtrip_tests` module with a test for every argument of every function version.
+///
+/// Each test checks with random values that the argument is the same after converting it into its
+/// ffi value and back on the host side, if the type of the argument supports this. The module is
+/// only compiled for the tests of the crate declaring the interface and needs the `gen-tests`
+/// feature of `sp-runtime-interface`.
+pub fn generate_roundtrip_tests(trait_def: &ItemTrait) -> Result<TokenStream> {
+	let trait_def = &remove_native_only_methods(trait_def);
+	let crate_ = generate_crate_access();
+	let mut tests = Vec::new();
+	for (version, method) in get_runtime_interface(trait_def)?.all_versions() {
+		let host_method = replace_raw_ptr_arguments(
+			&replace_codec_arguments(&replace_out_arguments(&remove_zero_sized_arguments(method))),
+		);
+		let function_name = create_function_ident_with_version(&method.sig.ident, version);
+		let cfg_attrs = get_cfg_attributes(method).collect::<Vec<_>>();
+
+		for (i, (name, ty)) in get_function_argument_names_and_types_without_ref(&host_method.sig)
+			.enumerate()
+		{
+			let arg_name = match &*name {
+				Pat::Ident(pat) => pat.ident.to_string(),
+				_ => format!("argument_{}", i),
+			};
+			let test_name = Ident::new(
+				&format!("{}_{}_roundtrip", function_name, arg_name),
+				Span::call_site(),
+			);
+
+			tests.push(quote! {
+				#( #cfg_attrs )*
+				#[test]
+				fn #test_name() {
+					(&#crate_::testing::Roundtrip::<#ty>::new()).check();
+				}
+			});
+		}
+	}
+
+	Ok(
+		quote! {
+			#[cfg(all(test, feature = "std"))]
+			mod roundtrip_tests {
+				use super::*;
+				#[allow(unused_imports)]
+				use #crate_::testing::{CheckRoundtrip, SkipRoundtrip};
+
+				#( #tests )*
+			}
+		}
+	)
+}
+
+/// Generate the conversion functions of the given function version for the `benches` module.
+fn generate_conversion_bench(
+	trait_name: &Ident,
+	method: &TraitItemMethod,
+	version: u32,
+) -> Result<TokenStream> {
+	let crate_ = generate_crate_access();
+	let host_method = replace_raw_ptr_arguments(
+		&replace_codec_arguments(&replace_out_arguments(&remove_zero_sized_arguments(method))),
+	);
+	let function_name = create_function_ident_with_version(&method.sig.ident, version);
+	let arguments_name = Ident::new(&format!("{}_arguments", function_name), Span::call_site());
+	let arguments_doc = format!(
+		" Converts the wasm values `args` into the arguments of version {} of `{}`.",
+		version,
+		method.sig.ident,
+	);
+	let wasm_to_ffi_values = generate_wasm_to_ffi_values(&host_method.sig, trait_name)
+		.collect::<Result<Vec<_>>>()?;
+	let arg_types = get_function_argument_types_without_ref(&host_method.sig).collect::<Vec<_>>();
+	let ffi_value_var_names = get_function_argument_names(&host_method.sig)
+		.map(|name| generate_ffi_value_var_name(&name))
+		.collect::<Result<Vec<_>>>()?;
+	let cfg_attrs = get_cfg_attributes(method).collect::<Vec<_>>();
+	let allow_lints = generate_allow_generated_lints();
+
+	// A return value that borrows from an argument can't be converted without this argument.
+	let return_value = match &method.sig.output {
+		ReturnType::Type(..) if get_borrowed_return_value(&method.sig).is_some() => quote!(),
+		ReturnType::Default => quote!(),
+		ReturnType::Type(_, ty) => {
+			let return_value_name = Ident::new(
+				&format!("{}_return_value", function_name),
+				Span::call_site(),
+			);
+			let return_value_doc = format!(
+				" Converts the return value of version {} of `{}` into the wasm value.",
+				version,
+				method.sig.ident,
+			);
+			let result_var_name = generate_host_function_result_var_name(&host_method.sig.ident);
+			let convert_return_value = generate_return_value_into_wasm_value(
+				&host_method.sig,
+				get_return_value_pass_by(method).is_some(),
+			);
+
+			quote! {
+				#( #cfg_attrs )*
+				#[doc = #return_value_doc]
+				pub fn #return_value_name(
+					__function_context__: &mut dyn FunctionContext,
+					#result_var_name: #ty,
+				) -> std::result::Result<Option<Value>, String> {
+					#convert_return_value
+				}
+			}
+		},
+	};
+
+	Ok(
+		quote! {
+			#( #cfg_attrs )*
+			#[doc = #arguments_doc]
+			#allow_lints
+			pub fn #arguments_name(
+				__function_context__: &mut dyn FunctionContext,
+				args: &mut dyn Iterator<Item = Value>,
+			) -> std::result::Result<
+				( #( <#arg_types as #crate_::host::FromFFIValue>::SelfInstance, )* ),
+				String,
+			> {
+				#( #wasm_to_ffi_values )*
+
+				Ok((
+					#(
+						<#arg_types as #crate_::host::FromFFIValue>::from_ffi_value(
+							__function_context__,
+							#ffi_value_var_names,
+						)?,
+					)*
+				))
+			}
+
+			#return_value
 		}
 	)
 }
@@ -188,27 +554,91 @@ fn generate_host_functions_struct(trait_def: &ItemTrait, is_wasm_only: bool) ->
 ///
 /// When calling from wasm into the host, we will call the `execute` function that calls the native
 /// implementation of the function.
+///
+/// If `registration_only` is set, the native implementation is not compiled with the
+/// `registration-only` feature. The `execute` function returns an error then, which traps the wasm
+/// execution.
 fn generate_host_function_implementation(
 	trait_name: &Ident,
 	method: &TraitItemMethod,
 	version: u32,
 	is_wasm_only: bool,
+	registration_only: bool,
+	custom_context: bool,
 ) -> Result<TokenStream> {
-	let name = create_host_function_ident(&method.sig.ident, version, trait_name).to_string();
+	// The host function is called with the host side arguments.
+	let wasm_method = replace_codec_arguments(&replace_out_arguments(method));
+	let host_method = replace_raw_ptr_arguments(&wasm_method);
+	// Only the arguments that are not zero sized are passed from wasm.
+	let passed_wasm_method = remove_zero_sized_arguments(&wasm_method);
+	let passed_method = remove_zero_sized_arguments(&host_method);
+	let ffi_type_assertions = generate_ffi_type_assertions(
+		&passed_wasm_method.sig,
+		&passed_method.sig,
+	);
+	// The host implementation borrows the slices of the `&[&[u8]]` arguments from `ByteSlices`.
+	let byte_slices_args = method.sig.inputs.iter()
+		.filter_map(|arg| match arg {
+			FnArg::Typed(arg) => Some(is_byte_slices_argument(arg)),
+			FnArg::Receiver(_) => None,
+		})
+		.collect::<Vec<_>>();
+	let borrowed_return_value = get_borrowed_return_value(&method.sig);
+	let method = &host_method;
+	let name = create_host_function_ident(&get_exported_name(method)?, version, trait_name)
+		.to_string();
 	let struct_name = Ident::new(&name.to_pascal_case(), Span::call_site());
 	let crate_ = generate_crate_access();
-	let signature = generate_wasm_interface_signature_for_host_function(&method.sig)?;
+	let signature = generate_wasm_interface_signature_for_host_function(&passed_method.sig)?;
 	let wasm_to_ffi_values = generate_wasm_to_ffi_values(
-		&method.sig,
+		&passed_method.sig,
 		trait_name,
 	).collect::<Result<Vec<_>>>()?;
-	let ffi_to_host_values = generate_ffi_to_host_value(&method.sig).collect::<Result<Vec<_>>>()?;
-	let host_function_call = generate_host_function_call(&method.sig, version, is_wasm_only);
-	let into_preallocated_ffi_value = generate_into_preallocated_ffi_value(&method.sig)?;
-	let convert_return_value = generate_return_value_into_wasm_value(&method.sig);
+	let ffi_to_host_values = generate_ffi_to_host_value(&passed_method.sig)
+		.collect::<Result<Vec<_>>>()?;
+	let host_function_call = generate_host_function_call(
+		&method.sig,
+		&byte_slices_args,
+		version,
+		is_wasm_only,
+		custom_context,
+	);
+	let into_preallocated_ffi_value = generate_into_preallocated_ffi_value(&passed_method.sig)?;
+	let convert_return_value = match borrowed_return_value {
+		Some(borrowed) => generate_borrowed_return_value_into_wasm_value(&method.sig, &borrowed),
+		None => generate_return_value_into_wasm_value(
+			&method.sig,
+			get_return_value_pass_by(method).is_some(),
+		),
+	};
+	let enter_span = generate_enter_span(method, &name, version);
+	let cfg_attrs = get_cfg_attributes(method);
+	let (host_impl_cfg, execute_stub) = if registration_only {
+		let error_message = format!(
+			"`{}` is compiled with the `registration-only` feature and can not be called",
+			name,
+		);
+
+		(
+			quote!( #[cfg(not(feature = "registration-only"))] ),
+			quote! {
+				#[cfg(feature = "registration-only")]
+				fn execute(
+					&self,
+					_: &mut dyn #crate_::sp_wasm_interface::FunctionContext,
+					_: &mut dyn Iterator<Item = #crate_::sp_wasm_interface::Value>,
+				) -> std::result::Result<Option<#crate_::sp_wasm_interface::Value>, String> {
+					Err(#error_message.into())
+				}
+			},
+		)
+	} else {
+		(quote!(), quote!())
+	};
 
 	Ok(
 		quote! {
+			#( #cfg_attrs )*
 			{
 				struct #struct_name;
 
@@ -221,18 +651,22 @@ fn generate_host_function_implementation(
 						#signature
 					}
 
+					#host_impl_cfg
 					fn execute(
 						&self,
 						__function_context__: &mut dyn #crate_::sp_wasm_interface::FunctionContext,
 						args: &mut dyn Iterator<Item = #crate_::sp_wasm_interface::Value>,
 					) -> std::result::Result<Option<#crate_::sp_wasm_interface::Value>, String> {
-						#crate_::sp_tracing::enter_span!(#name);
+						#ffi_type_assertions
+						#enter_span
 						#( #wasm_to_ffi_values )*
 						#( #ffi_to_host_values )*
 						#host_function_call
 						#into_preallocated_ffi_value
 						#convert_return_value
 					}
+
+					#execute_stub
 				}
 
 				&#struct_name as &dyn #crate_::sp_wasm_interface::Function
@@ -241,8 +675,132 @@ fn generate_host_function_implementation(
 	)
 }
 
+/// Generates the host function struct for the skipped `version` of the given method and returns a
+/// static reference to this struct.
+///
+/// The host function uses the signature of the given method, the version before the skipped
+/// version. It is only registered to keep the version numbering and returns an error when it is
+/// called, which traps the wasm execution.
+fn generate_skipped_host_function_implementation(
+	trait_name: &Ident,
+	method: &TraitItemMethod,
+	version: u32,
+) -> Result<TokenStream> {
+	let host_method = replace_raw_ptr_arguments(
+		&replace_codec_arguments(&replace_out_arguments(&remove_zero_sized_arguments(method))),
+	);
+	let name = create_host_function_ident(&get_exported_name(method)?, version, trait_name)
+		.to_string();
+	let struct_name = Ident::new(&name.to_pascal_case(), Span::call_site());
+	let crate_ = generate_crate_access();
+	let signature = generate_wasm_interface_signature_for_host_function(&host_method.sig)?;
+	let error_message = format!(
+		"Version {} of `{}` is skipped and must not be called",
+		version,
+		method.sig.ident,
+	);
+	let cfg_attrs = get_cfg_attributes(method);
+
+	Ok(
+		quote! {
+			#( #cfg_attrs )*
+			{
+				struct #struct_name;
+
+				impl #crate_::sp_wasm_interface::Function for #struct_name {
+					fn name(&self) -> &str {
+						#name
+					}
+
+					fn signature(&self) -> #crate_::sp_wasm_interface::Signature {
+						#signature
+					}
+
+					fn execute(
+						&self,
+						_: &mut dyn #crate_::sp_wasm_interface::FunctionContext,
+						_: &mut dyn Iterator<Item = #crate_::sp_wasm_interface::Value>,
+					) -> std::result::Result<Option<#crate_::sp_wasm_interface::Value>, String> {
+						Err(#error_message.into())
+					}
+				}
+
+				&#struct_name as &dyn #crate_::sp_wasm_interface::Function
+			}
+		}
+	)
+}
+
+/// Generate the compile time assertions that the argument types and the return type of the given
+/// method implement the traits to convert them from and into their ffi values.
+///
+/// Without the assertions, a missing implementation is reported somewhere in the generated code.
+/// The assertions are spanned to the types, so the error points to the type that lacks the
+/// implementation and names the missing trait. Arguments with an attribute that changes how they
+/// are passed, e.g. `#[pass_by(codec)]`, and `&[&[u8]]` arguments are not converted with their own
+/// type and are skipped, as well as the zero sized arguments, which are not passed. The same goes
+/// for a return value that borrows from an argument.
+fn generate_conversion_trait_assertions(method: &TraitItemMethod) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let arg_types = method.sig.inputs.iter()
+		.filter_map(|arg| match arg {
+			FnArg::Typed(arg) if !arg.attrs.iter().any(is_ffi_argument_attribute) &&
+				!is_zero_sized_argument(arg) && !is_byte_slices_argument(arg) => Some(&arg.ty),
+			_ => None,
+		})
+		.map(|ty| match &**ty {
+			Type::Reference(type_ref) => &type_ref.elem,
+			_ => ty,
+		});
+	let return_type = match &method.sig.output {
+		ReturnType::Type(_, ty) if get_return_value_pass_by(method).is_none() &&
+			get_borrowed_return_value(&method.sig).is_none() => Some(ty),
+		_ => None,
+	};
+	let assertions = arg_types
+		.map(|ty| quote_spanned! { ty.span() =>
+			#[cfg(feature = "std")]
+			#crate_::host::assert_argument_type::<#ty>();
+			#[cfg(not(feature = "std"))]
+			#crate_::wasm::assert_argument_type::<#ty>();
+		})
+		.chain(return_type.map(|ty| quote_spanned! { ty.span() =>
+			#[cfg(feature = "std")]
+			#crate_::host::assert_return_type::<#ty>();
+			#[cfg(not(feature = "std"))]
+			#crate_::wasm::assert_return_type::<#ty>();
+		}));
+	let cfg_attrs = get_cfg_attributes(method);
+
+	quote! {
+		#( #cfg_attrs )*
+		const _: fn() = || {
+			#( #assertions )*
+		};
+	}
+}
+
+/// Generate the compile time assertions that the host function with `host_sig` uses the same ffi
+/// types for the arguments and the return value as the wasm side with `wasm_sig`.
+fn generate_ffi_type_assertions(wasm_sig: &Signature, host_sig: &Signature) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let return_types = match (&wasm_sig.output, &host_sig.output) {
+		(ReturnType::Type(_, wasm_ty), ReturnType::Type(_, host_ty)) =>
+			Some((wasm_ty.clone(), host_ty.clone())),
+		_ => None,
+	};
+
+	get_function_argument_types_without_ref(wasm_sig)
+		.zip(get_function_argument_types_without_ref(host_sig))
+		.chain(return_types)
+		.map(|(wasm_ty, host_ty)| quote_spanned! { wasm_ty.span() =>
+			#crate_::host::assert_same_ffi_type::<#wasm_ty, #host_ty>();
+		})
+		.collect()
+}
+
 /// Generate the `wasm_interface::Signature` for the given host function `sig`.
-fn generate_wasm_interface_signature_for_host_function(sig: &Signature) -> Result<TokenStream> {
+pub fn generate_wasm_interface_signature_for_host_function(sig: &Signature) -> Result<TokenStream> {
 	let crate_ = generate_crate_access();
 	let return_value = match &sig.output {
 		ReturnType::Type(_, ty) =>
@@ -323,21 +881,48 @@ fn generate_ffi_to_host_value<'a>(
 }
 
 /// Generate the code to call the host function and the ident that stores the result.
-fn generate_host_function_call(sig: &Signature, version: u32, is_wasm_only: bool) -> TokenStream {
+///
+/// A custom function context is implemented for `&mut dyn FunctionContext`, so a reference to the
+/// `FunctionContext` is passed. The zero sized arguments are not passed from wasm and are created
+/// with `Default::default()`. The arguments that are marked in `byte_slices_args` are passed as
+/// the slices of their `ByteSlices`.
+fn generate_host_function_call(
+	sig: &Signature,
+	byte_slices_args: &[bool],
+	version: u32,
+	is_wasm_only: bool,
+	custom_context: bool,
+) -> TokenStream {
 	let host_function_name = create_function_ident_with_version(&sig.ident, version);
 	let result_var_name = generate_host_function_result_var_name(&sig.ident);
 	let ref_and_mut = get_function_argument_types_ref_and_mut(sig).map(|ram|
 		ram.map(|(vr, vm)| quote!(#vr #vm))
 	);
 	let names = get_function_argument_names(sig);
+	// The cast shortens the lifetime of the trait object to the lifetime of the reborrow, which is
+	// the lifetime of `&mut dyn FunctionContext` the custom context is implemented for.
+	let function_context = if custom_context {
+		let crate_ = generate_crate_access();
+		quote!(
+			&mut (
+				&mut *__function_context__ as &mut dyn #crate_::sp_wasm_interface::FunctionContext
+			)
+		)
+	} else {
+		quote!( __function_context__ )
+	};
 
-	let var_access = names.zip(ref_and_mut)
-		.map(|(n, ref_and_mut)| {
+	let var_access = names.zip(ref_and_mut).zip(get_function_arguments(sig)).zip(byte_slices_args)
+		.map(|(((n, ref_and_mut), arg), is_byte_slices)| if is_zero_sized_argument(&arg) {
+			quote!( #ref_and_mut Default::default() )
+		} else if *is_byte_slices {
+			quote!( &#n.slices()[..] )
+		} else {
 			quote!( #ref_and_mut #n )
 		})
-		// If this is a wasm only interface, we add the function context as last parameter.
+		// If this method is only callable from wasm, we add the function context as last parameter.
 		.chain(
-			iter::from_fn(|| if is_wasm_only { Some(quote!(__function_context__)) } else { None })
+			iter::from_fn(|| if is_wasm_only { Some(function_context.clone()) } else { None })
 				.take(1)
 		);
 
@@ -397,11 +982,24 @@ fn generate_into_preallocated_ffi_value(sig: &Signature) -> Result<TokenStream>
 }
 
 /// Generate the code that converts the return value into the appropriate wasm value.
-fn generate_return_value_into_wasm_value(sig: &Signature) -> TokenStream {
+///
+/// If `by_strategy` is set, the return type is the type of a `#[pass_by(..)]` strategy, e.g.
+/// `Codec<T>`, and the return value of type `T` is passed with this strategy.
+fn generate_return_value_into_wasm_value(sig: &Signature, by_strategy: bool) -> TokenStream {
 	let crate_ = generate_crate_access();
 
 	match &sig.output {
 		ReturnType::Default => quote!( Ok(None) ),
+		ReturnType::Type(_, ty) if by_strategy => {
+			let result_var_name = generate_host_function_result_var_name(&sig.ident);
+
+			quote! {
+				<#ty as #crate_::pass_by::PassByImpl<_>>::into_ffi_value(
+					#result_var_name,
+					__function_context__,
+				).map(#crate_::sp_wasm_interface::IntoValue::into_value).map(Some)
+			}
+		},
 		ReturnType::Type(_, ty) => {
 			let result_var_name = generate_host_function_result_var_name(&sig.ident);
 
@@ -414,3 +1012,26 @@ fn generate_return_value_into_wasm_value(sig: &Signature) -> TokenStream {
 		}
 	}
 }
+
+/// Generate the code that converts the return value, which borrows from the argument given by
+/// `borrowed`, into the wasm value.
+///
+/// The slice is passed as its offset and length in this argument, which the wasm side still has.
+fn generate_borrowed_return_value_into_wasm_value(
+	sig: &Signature,
+	borrowed: &BorrowedReturnValue,
+) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let result_var_name = generate_host_function_result_var_name(&sig.ident);
+	let source = &borrowed.source;
+	let result = if borrowed.optional {
+		quote!( #result_var_name )
+	} else {
+		quote!( Some(#result_var_name) )
+	};
+
+	quote! {
+		#crate_::BorrowedSlice::into_ffi_value(&#source, #result)
+			.map(#crate_::sp_wasm_interface::IntoValue::into_value).map(Some)
+	}
+}
@@ -14,16 +14,27 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::utils::generate_runtime_interface_include;
+use crate::utils::{
+	generate_runtime_interface_include, check_pass_by_arguments, check_out_arguments,
+	check_callback_arguments, check_native_only_methods, check_return_types_of_versions,
+	check_reserved_method_names, remove_old_versions, add_export_name_prefix,
+	generate_wide_signature_warnings, check_compressed_arguments, check_no_alloc_methods,
+	check_trait_items, generate_crate_access, generate_host_impl_cfg,
+};
 
 use proc_macro2::{Span, TokenStream};
 
-use syn::{Ident, ItemTrait, Result};
+use syn::{
+	Ident, ItemTrait, Result, Token, Error, Path, LitStr, LitInt, parse::{Parse, ParseStream},
+};
 
 use inflector::Inflector;
 
 use quote::quote;
 
+mod batch_function;
+mod handle_type;
+mod stream_function;
 mod bare_function_interface;
 mod host_function_interface;
 mod trait_decl_impl;
@@ -32,18 +43,332 @@ mod trait_decl_impl;
 pub mod keywords {
 	// Custom keyword `wasm_only` that can be given as attribute to [`runtime_interface`].
 	syn::custom_keyword!(wasm_only);
+	// Custom keyword `track_caller` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(track_caller);
+	// Custom keyword `mockable` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(mockable);
+	// Custom keyword `debug_args` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(debug_args);
+	// Custom keyword `inline_wasm` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(inline_wasm);
+	// Custom keyword `record` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(record);
+	// Custom keyword `deny_unused_self` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(deny_unused_self);
+	// Custom keyword `audit` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(audit);
+	// Custom keyword `error_enum` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(error_enum);
+	// Custom keyword `ffi_size` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(ffi_size);
+	// Custom keyword `no_externalities` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(no_externalities);
+	// Custom keyword `reentrancy_guard` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(reentrancy_guard);
+	// Custom keyword `benches` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(benches);
+	// Custom keyword `no_panic` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(no_panic);
+	// Custom keyword `registration_only` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(registration_only);
+	// Custom keyword `prefix` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(prefix);
+	// Custom keyword `context` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(context);
+	// Custom keyword `with_externalities` that can be given as attribute to
+	// [`runtime_interface`].
+	syn::custom_keyword!(with_externalities);
+	// Custom keyword `no_legacy` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(no_legacy);
+	// Custom keyword `wrap` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(wrap);
+	// Custom keyword `keep_versions` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(keep_versions);
+	// Custom keyword `gen_tests` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(gen_tests);
+	// Custom keyword `strict_versioning` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(strict_versioning);
+	// Custom keyword `host_simulator` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(host_simulator);
+	// Custom keyword `warn_wide` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(warn_wide);
+	// Custom keyword `host_function_features` that can be given as attribute to
+	// [`runtime_interface`].
+	syn::custom_keyword!(host_function_features);
+}
+
+/// The options that can be given as attribute to [`runtime_interface`].
+#[derive(Default)]
+pub struct Options {
+	/// Is this a wasm only interface?
+	wasm_only: bool,
+	/// Should the bare functions be annotated with `#[track_caller]`?
+	track_caller: bool,
+	/// Should the bare functions be mockable on the native side?
+	mockable: bool,
+	/// Should the arguments of every call be logged on the host side in debug builds?
+	debug_args: bool,
+	/// Should the bare functions on the wasm side be annotated with `#[inline(always)]`?
+	inline_wasm: bool,
+	/// Are the implementations of the methods `async` blocks?
+	is_async: bool,
+	/// Should every call on the host side be reported to the installed recorder?
+	record: bool,
+	/// Is a `self` receiver that is not used by the implementation of the method rejected?
+	deny_unused_self: bool,
+	/// Should every call on the host side be reported to the installed audit sink?
+	audit: bool,
+	/// Should an enum with one variant per function of the interface be generated?
+	error_enum: bool,
+	/// Should the size of the ffi values of the arguments be exposed for every function version?
+	ffi_size: bool,
+	/// Are the functions of the interface implemented without any `Externalities`?
+	no_externalities: bool,
+	/// Should re-entrant calls of the functions be detected on the host side?
+	reentrancy_guard: bool,
+	/// Should the conversions of the host functions be exposed for benchmarking?
+	benches: bool,
+	/// Should the functions return an error instead of panicking without `Externalities`?
+	no_panic: bool,
+	/// Should the host side implementation be left out with the `registration-only` feature?
+	registration_only: bool,
+	/// Is only the latest version of every method generated?
+	no_legacy: bool,
+	/// Should a roundtrip test of the conversion of every argument be generated?
+	gen_tests: bool,
+	/// Do all versions of a method need to return the same type?
+	strict_versioning: bool,
+	/// Should a function that installs the host functions into a `HostSimulator` be generated?
+	host_simulator: bool,
+	/// Can the registration of every host function be disabled by a `disable_hostfn_<name>`
+	/// feature?
+	host_function_features: bool,
+	/// The prefix of the exported names of the host functions given as `prefix = "name"`.
+	prefix: Option<LitStr>,
+	/// The number of ffi values a method can take without a warning given as `warn_wide = N`.
+	warn_wide: Option<usize>,
+	/// The trait of the function context of `#[wasm_only]` methods given as `context = path`.
+	context: Option<Path>,
+	/// The function that provides the `Externalities` on the native side given as
+	/// `with_externalities = path`.
+	with_externalities: Option<Path>,
+	/// The number of the latest versions of every method that are generated given as
+	/// `keep_versions = N`.
+	keep_versions: Option<u32>,
+	/// The function every call on the host side is wrapped in given as `wrap = path`.
+	wrap: Option<Path>,
+	/// The path to the `sp-runtime-interface` crate given as `crate = path`.
+	crate_path: Option<Path>,
+}
+
+impl Parse for Options {
+	fn parse(input: ParseStream) -> Result<Self> {
+		let mut res = Self::default();
+
+		while !input.is_empty() {
+			let lookahead = input.lookahead1();
+			if lookahead.peek(keywords::wasm_only) {
+				input.parse::<keywords::wasm_only>()?;
+				res.wasm_only = true;
+			} else if lookahead.peek(keywords::track_caller) {
+				input.parse::<keywords::track_caller>()?;
+				res.track_caller = true;
+			} else if lookahead.peek(keywords::mockable) {
+				input.parse::<keywords::mockable>()?;
+				res.mockable = true;
+			} else if lookahead.peek(keywords::debug_args) {
+				input.parse::<keywords::debug_args>()?;
+				res.debug_args = true;
+			} else if lookahead.peek(keywords::inline_wasm) {
+				input.parse::<keywords::inline_wasm>()?;
+				res.inline_wasm = true;
+			} else if lookahead.peek(Token![async]) {
+				input.parse::<Token![async]>()?;
+				res.is_async = true;
+			} else if lookahead.peek(keywords::record) {
+				input.parse::<keywords::record>()?;
+				res.record = true;
+			} else if lookahead.peek(keywords::deny_unused_self) {
+				input.parse::<keywords::deny_unused_self>()?;
+				res.deny_unused_self = true;
+			} else if lookahead.peek(keywords::audit) {
+				input.parse::<keywords::audit>()?;
+				res.audit = true;
+			} else if lookahead.peek(keywords::error_enum) {
+				input.parse::<keywords::error_enum>()?;
+				res.error_enum = true;
+			} else if lookahead.peek(keywords::ffi_size) {
+				input.parse::<keywords::ffi_size>()?;
+				res.ffi_size = true;
+			} else if lookahead.peek(keywords::no_externalities) {
+				input.parse::<keywords::no_externalities>()?;
+				res.no_externalities = true;
+			} else if lookahead.peek(keywords::reentrancy_guard) {
+				input.parse::<keywords::reentrancy_guard>()?;
+				res.reentrancy_guard = true;
+			} else if lookahead.peek(keywords::benches) {
+				input.parse::<keywords::benches>()?;
+				res.benches = true;
+			} else if lookahead.peek(keywords::no_panic) {
+				input.parse::<keywords::no_panic>()?;
+				res.no_panic = true;
+			} else if lookahead.peek(keywords::registration_only) {
+				input.parse::<keywords::registration_only>()?;
+				res.registration_only = true;
+			} else if lookahead.peek(keywords::strict_versioning) {
+				input.parse::<keywords::strict_versioning>()?;
+				res.strict_versioning = true;
+			} else if lookahead.peek(keywords::no_legacy) {
+				input.parse::<keywords::no_legacy>()?;
+				res.no_legacy = true;
+			} else if lookahead.peek(keywords::gen_tests) {
+				input.parse::<keywords::gen_tests>()?;
+				res.gen_tests = true;
+			} else if lookahead.peek(keywords::host_simulator) {
+				input.parse::<keywords::host_simulator>()?;
+				res.host_simulator = true;
+			} else if lookahead.peek(keywords::host_function_features) {
+				input.parse::<keywords::host_function_features>()?;
+				res.host_function_features = true;
+			} else if lookahead.peek(keywords::context) {
+				input.parse::<keywords::context>()?;
+				input.parse::<Token![=]>()?;
+				res.context = Some(input.parse()?);
+			} else if lookahead.peek(keywords::with_externalities) {
+				input.parse::<keywords::with_externalities>()?;
+				input.parse::<Token![=]>()?;
+				res.with_externalities = Some(input.parse()?);
+			} else if lookahead.peek(keywords::wrap) {
+				input.parse::<keywords::wrap>()?;
+				input.parse::<Token![=]>()?;
+				res.wrap = Some(input.parse()?);
+			} else if lookahead.peek(keywords::prefix) {
+				input.parse::<keywords::prefix>()?;
+				input.parse::<Token![=]>()?;
+				let prefix = input.parse::<LitStr>()?;
+				if prefix.parse::<Ident>().is_err() {
+					return Err(Error::new(
+						prefix.span(),
+						format!("`{}` is not a valid identifier", prefix.value()),
+					))
+				}
+				res.prefix = Some(prefix);
+			} else if lookahead.peek(keywords::warn_wide) {
+				input.parse::<keywords::warn_wide>()?;
+				input.parse::<Token![=]>()?;
+				res.warn_wide = Some(input.parse::<LitInt>()?.base10_parse()?);
+			} else if lookahead.peek(keywords::keep_versions) {
+				input.parse::<keywords::keep_versions>()?;
+				input.parse::<Token![=]>()?;
+				let keep_versions = input.parse::<LitInt>()?;
+				if keep_versions.base10_parse::<u32>()? == 0 {
+					return Err(
+						Error::new(keep_versions.span(), "`keep_versions` needs to be at least 1")
+					)
+				}
+				res.keep_versions = Some(keep_versions.base10_parse()?);
+			} else if lookahead.peek(Token![crate]) {
+				input.parse::<Token![crate]>()?;
+				input.parse::<Token![=]>()?;
+				res.crate_path = Some(input.parse()?);
+			} else {
+				return Err(lookahead.error())
+			}
+
+			if !input.is_empty() {
+				input.parse::<Token![,]>()?;
+			}
+		}
+
+		if res.wasm_only && res.mockable {
+			return Err(
+				Error::new(input.span(), "`mockable` can not be combined with `wasm_only`")
+			)
+		}
+
+		if res.wasm_only && res.no_externalities {
+			return Err(
+				Error::new(input.span(), "`no_externalities` can not be combined with `wasm_only`")
+			)
+		}
+
+		// The `_async` functions call the implementation directly, so they would bypass these.
+		if res.is_async && (res.mockable || res.record || res.audit || res.wrap.is_some()) {
+			return Err(
+				Error::new(
+					input.span(),
+					"`async` can not be combined with `mockable`, `record`, `audit` or `wrap`",
+				)
+			)
+		}
+
+		if res.no_legacy && res.keep_versions.is_some() {
+			return Err(
+				Error::new(input.span(), "`keep_versions` can not be combined with `no_legacy`")
+			)
+		}
+
+		if res.with_externalities.is_some() && (res.wasm_only || res.no_externalities) {
+			return Err(
+				Error::new(
+					input.span(),
+					"`with_externalities` can not be combined with `wasm_only` or \
+					`no_externalities`",
+				)
+			)
+		}
+
+		Ok(res)
+	}
 }
 
 /// Implementation of the `runtime_interface` attribute.
 ///
-/// It expects the trait definition the attribute was put above and if this should be an wasm only
-/// interface.
-pub fn runtime_interface_impl(trait_def: ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
-	let bare_functions = bare_function_interface::generate(&trait_def, is_wasm_only)?;
-	let crate_include = generate_runtime_interface_include();
+/// It expects the trait definition the attribute was put above and the options given to the
+/// attribute.
+pub fn runtime_interface_impl(trait_def: ItemTrait, options: Options) -> Result<TokenStream> {
+	check_trait_items(&trait_def)?;
+	check_reserved_method_names(&trait_def, options.mockable, options.is_async)?;
+	let trait_def = remove_old_versions(trait_def, options.no_legacy, options.keep_versions)?;
+	let trait_def = match &options.prefix {
+		Some(prefix) => add_export_name_prefix(trait_def, prefix)?,
+		None => trait_def,
+	};
+	let (trait_def, handle_types) = handle_type::add_handle_types(trait_def)?;
+	let (trait_def, stream_functions) =
+		stream_function::add_stream_functions(trait_def, &options)?;
+	let trait_def = batch_function::add_batch_functions(trait_def)?;
+	let is_wasm_only = options.wasm_only;
+	check_pass_by_arguments(&trait_def, is_wasm_only)?;
+	check_out_arguments(&trait_def)?;
+	check_callback_arguments(&trait_def, is_wasm_only)?;
+	check_compressed_arguments(&trait_def)?;
+	check_no_alloc_methods(&trait_def)?;
+	check_native_only_methods(&trait_def, is_wasm_only)?;
+	if options.strict_versioning {
+		check_return_types_of_versions(&trait_def)?;
+	}
+
+	let bare_functions = bare_function_interface::generate(&trait_def, &options)?;
+	let crate_include = generate_runtime_interface_include(options.crate_path.as_ref());
 	let mod_name = Ident::new(&trait_def.ident.to_string().to_snake_case(), Span::call_site());
-	let trait_decl_impl = trait_decl_impl::process(&trait_def, is_wasm_only)?;
-	let host_functions = host_function_interface::generate(&trait_def, is_wasm_only)?;
+	let trait_decl_impl = trait_decl_impl::process(&trait_def, &options)?;
+	let host_functions = host_function_interface::generate(&trait_def, &options)?;
+	let benches = if options.benches {
+		host_function_interface::generate_conversion_benches(&trait_def)?
+	} else {
+		quote!()
+	};
+	let wide_signature_warnings = match options.warn_wide {
+		Some(limit) => generate_wide_signature_warnings(&trait_def, limit),
+		None => quote!(),
+	};
+	let roundtrip_tests = if options.gen_tests {
+		host_function_interface::generate_roundtrip_tests(&trait_def)?
+	} else {
+		quote!()
+	};
+	let feature_checks = generate_feature_checks(&options);
 	let vis = trait_def.vis;
 	let attrs = &trait_def.attrs;
 
@@ -53,13 +378,53 @@ pub fn runtime_interface_impl(trait_def: ItemTrait, is_wasm_only: bool) -> Resul
 			use super::*;
 			#crate_include
 
+			#handle_types
+
 			#bare_functions
 
+			#stream_functions
+
 			#trait_decl_impl
 
 			#host_functions
+
+			#benches
+
+			#roundtrip_tests
+
+			#wide_signature_warnings
+
+			#feature_checks
 		}
 	};
 
 	Ok(res)
 }
+
+/// Generates a check for every given option that requires a feature of `sp-runtime-interface` on
+/// the native side, so that a missing feature fails with a clear error.
+fn generate_feature_checks(options: &Options) -> TokenStream {
+	let crate_ = generate_crate_access();
+	let host_impl_cfg = generate_host_impl_cfg(options.registration_only);
+	let mut checks = Vec::new();
+
+	if options.is_async {
+		let message = "`#[runtime_interface(async)]` requires the `async` feature of \
+			`sp-runtime-interface`";
+		checks.push(quote!( #crate_::features::require_async!(#message); ));
+	}
+
+	if options.record {
+		let message = "`#[runtime_interface(record)]` requires the `record` feature of \
+			`sp-runtime-interface`";
+		checks.push(quote!( #crate_::features::require_record!(#message); ));
+	}
+
+	if options.audit {
+		let message = "`#[runtime_interface(audit)]` requires the `audit` feature of \
+			`sp-runtime-interface`";
+		checks.push(quote!( #crate_::features::require_audit!(#message); ));
+	}
+
+	quote!( #( #host_impl_cfg #checks )* )
+}
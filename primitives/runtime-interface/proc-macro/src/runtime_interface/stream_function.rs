@@ -0,0 +1,278 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Replaces the methods annotated with `#[stream]` by the methods that give out their items.
+//!
+//! A method `name` returning `impl Iterator<Item = T>` is replaced by the methods
+//! `name_stream_open`, `name_stream_next` and `name_stream_drop`. The first one creates the
+//! iterator on the host and returns the handle to it, the others pull the next item and drop the
+//! iterator. The bare function `name` returns a `Stream<T>` that calls them, so only the items
+//! that are read cross the wasm/host boundary.
+
+use crate::utils::{
+	get_function_arguments, get_function_argument_names, get_item_version, get_cfg_attributes,
+	generate_crate_access, is_wasm_only_method,
+};
+
+use super::Options;
+
+use syn::{
+	ItemTrait, TraitItem, TraitItemMethod, Result, Error, Type, ReturnType, Ident, PathArguments,
+	GenericArgument, TypeParamBound, FnArg, spanned::Spanned, parse_quote,
+};
+
+use proc_macro2::{Span, TokenStream};
+
+use quote::quote;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Replaces every method annotated with `#[stream]` in the given trait.
+///
+/// Returns the trait and the bare functions that return the streams.
+pub fn add_stream_functions(
+	mut trait_def: ItemTrait,
+	options: &Options,
+) -> Result<(ItemTrait, TokenStream)> {
+	let streamed = trait_def.items.iter()
+		.filter_map(|item| match item {
+			TraitItem::Method(method) if is_stream_method(method) => Some(method.sig.ident.clone()),
+			_ => None,
+		})
+		.collect::<BTreeSet<_>>();
+
+	// The latest version of every streamed method, to generate the bare function for it.
+	let mut latest_versions = BTreeMap::<Ident, (u32, TraitItemMethod)>::new();
+	let mut items = Vec::with_capacity(trait_def.items.len());
+	for item in trait_def.items {
+		let method = match item {
+			TraitItem::Method(method) if streamed.contains(&method.sig.ident) => method,
+			item => {
+				items.push(item);
+				continue
+			},
+		};
+
+		if !is_stream_method(&method) {
+			return Err(Error::new(
+				method.sig.span(),
+				"All versions of a method need to be `#[stream]` if one of them is",
+			))
+		}
+
+		if options.no_externalities {
+			return Err(Error::new(
+				method.sig.span(),
+				"`#[stream]` keeps the iterators in the externalities and can not be used in a \
+				`no_externalities` interface",
+			))
+		}
+
+		let version = get_item_version(&method)?.map_or(1, |version| version.version);
+		items.extend(generate_stream_methods(&method)?);
+
+		let latest = latest_versions.entry(method.sig.ident.clone())
+			.or_insert_with(|| (version, method.clone()));
+		if latest.0 < version {
+			*latest = (version, method);
+		}
+	}
+	trait_def.items = items;
+
+	let bare_functions = latest_versions.values()
+		.map(|(_, method)| generate_stream_bare_function(method, options))
+		.collect::<Result<_>>()?;
+
+	Ok((trait_def, bare_functions))
+}
+
+/// Returns if the given method is annotated with `#[stream]`.
+fn is_stream_method(method: &TraitItemMethod) -> bool {
+	method.attrs.iter().any(|a| a.path.is_ident("stream"))
+}
+
+/// Generates the ident of the given stream method, e.g. `name_stream_open`.
+fn create_stream_method_ident(name: &Ident, method: &str) -> Ident {
+	Ident::new(&format!("{}_stream_{}", name, method), Span::call_site())
+}
+
+/// Generates the methods that open the stream, pull the next item and drop the stream for the
+/// given method.
+fn generate_stream_methods(method: &TraitItemMethod) -> Result<Vec<TraitItem>> {
+	if is_wasm_only_method(method, false) {
+		return Err(
+			Error::new(method.sig.span(), "`#[stream]` is not supported on `#[wasm_only]` methods")
+		)
+	}
+	if method.attrs.iter().any(|a| a.path.is_ident("batch")) {
+		return Err(Error::new(method.sig.span(), "`#[stream]` can not be combined with `#[batch]`"))
+	}
+	let item = iterator_item_type(&method.sig.output)?;
+	let body = match &method.default {
+		Some(body) => body,
+		None => return Err(Error::new(method.sig.span(), "`#[stream]` methods need a body")),
+	};
+
+	let crate_ = generate_crate_access();
+	let name = &method.sig.ident;
+	let open_name = create_stream_method_ident(name, "open");
+	let next_name = create_stream_method_ident(name, "next");
+	let drop_name = create_stream_method_ident(name, "drop");
+	// The receiver of the method is replaced by the `&mut self` that is needed to keep the
+	// iterator, the body can still use `self`.
+	let inputs = method.sig.inputs.iter().filter(|arg| matches!(arg, FnArg::Typed(_)));
+	let attrs = method.attrs.iter()
+		.filter(|a| !a.path.is_ident("stream") && !a.path.is_ident("doc"));
+	// Every version of the method gets its own methods to pull the items, as the item type may
+	// change between the versions.
+	let version = method.attrs.iter().filter(|a| a.path.is_ident("version"));
+	let shared_attrs = get_cfg_attributes(method).chain(version).collect::<Vec<_>>();
+	let open_doc = format!(" Opens the stream of [`{}`] and returns the handle to it.", name);
+	let next_doc = format!(" Returns the next item of the stream of [`{}`].", name);
+	let drop_doc = format!(" Drops the stream of [`{}`].", name);
+
+	let open: TraitItemMethod = parse_quote! {
+		#( #attrs )*
+		#[doc = #open_doc]
+		#[doc(hidden)]
+		fn #open_name(&mut self, #( #inputs ),* ) -> u32 {
+			let iter = #body;
+			#crate_::stream::open(&mut **self, iter)
+		}
+	};
+	let next: TraitItemMethod = parse_quote! {
+		#( #shared_attrs )*
+		#[doc = #next_doc]
+		#[doc(hidden)]
+		fn #next_name(&mut self, handle: u32) -> Option<#item> {
+			#crate_::stream::next(&mut **self, handle)
+		}
+	};
+	let drop: TraitItemMethod = parse_quote! {
+		#( #shared_attrs )*
+		#[doc = #drop_doc]
+		#[doc(hidden)]
+		fn #drop_name(handle: u32) {
+			#crate_::stream::close(handle)
+		}
+	};
+
+	Ok(vec![TraitItem::Method(open), TraitItem::Method(next), TraitItem::Method(drop)])
+}
+
+/// Returns the item type `T` of the `impl Iterator<Item = T>` the given method returns.
+fn iterator_item_type(output: &ReturnType) -> Result<&Type> {
+	let error = || Error::new(
+		output.span(),
+		"`#[stream]` methods need to return `impl Iterator<Item = T>`",
+	);
+	let bounds = match output {
+		ReturnType::Type(_, ty) => match &**ty {
+			Type::ImplTrait(impl_trait) => &impl_trait.bounds,
+			_ => return Err(error()),
+		},
+		ReturnType::Default => return Err(error()),
+	};
+
+	bounds.iter()
+		.filter_map(|bound| match bound {
+			TypeParamBound::Trait(bound) => bound.path.segments.last(),
+			TypeParamBound::Lifetime(_) => None,
+		})
+		.filter(|segment| segment.ident == "Iterator")
+		.find_map(|segment| match &segment.arguments {
+			PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+				GenericArgument::Binding(binding) if binding.ident == "Item" => Some(&binding.ty),
+				_ => None,
+			}),
+			_ => None,
+		})
+		.ok_or_else(error)
+}
+
+/// Generates the bare function that returns the stream of the given method.
+fn generate_stream_bare_function(
+	method: &TraitItemMethod,
+	options: &Options,
+) -> Result<TokenStream> {
+	let crate_ = generate_crate_access();
+	let name = &method.sig.ident;
+	let open_name = create_stream_method_ident(name, "open");
+	let next_name = create_stream_method_ident(name, "next");
+	let drop_name = create_stream_method_ident(name, "drop");
+	let item = iterator_item_type(&method.sig.output)?;
+	let args = get_function_arguments(&method.sig);
+	let arg_names = get_function_argument_names(&method.sig);
+	let attrs = method.attrs.iter().filter(|a| a.path.is_ident("cfg") || a.path.is_ident("doc"));
+	// The bare functions on the host are not compiled with `registration-only`.
+	let registration_only_cfg = if options.registration_only {
+		quote!( #[cfg(not(feature = "registration-only"))] )
+	} else {
+		quote!()
+	};
+
+	Ok(
+		quote! {
+			#( #attrs )*
+			#registration_only_cfg
+			pub fn #name( #( #args, )* ) -> #crate_::stream::Stream<#item> {
+				let handle = #open_name( #( #arg_names, )* );
+				#crate_::stream::Stream::new(handle, #next_name, #drop_name)
+			}
+		}
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn stream_methods_are_replaced() {
+		let trait_def: ItemTrait = parse_quote! {
+			trait Interface {
+				#[stream]
+				fn numbers(count: u32) -> impl Iterator<Item = u32> { 0..count }
+				fn other() {}
+			}
+		};
+
+		let (trait_def, bare_functions) =
+			add_stream_functions(trait_def, &Options::default()).unwrap();
+		let names = trait_def.items.iter().map(|item| match item {
+			TraitItem::Method(method) => method.sig.ident.to_string(),
+			_ => panic!("Only methods are declared"),
+		}).collect::<Vec<_>>();
+
+		assert_eq!(
+			vec!["numbers_stream_open", "numbers_stream_next", "numbers_stream_drop", "other"],
+			names,
+		);
+		assert!(bare_functions.to_string().contains("pub fn numbers ( count : u32 , )"));
+	}
+
+	#[test]
+	fn stream_methods_need_to_return_an_iterator() {
+		let trait_def: ItemTrait = parse_quote! {
+			trait Interface {
+				#[stream]
+				fn numbers(count: u32) -> Vec<u32> { vec![count] }
+			}
+		};
+
+		assert!(add_stream_functions(trait_def, &Options::default()).is_err());
+	}
+}
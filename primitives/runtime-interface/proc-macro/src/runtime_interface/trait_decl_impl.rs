@@ -15,29 +15,120 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Checks the trait declaration, makes the trait declaration module local, removes all method
-//! default implementations and implements the trait for `&mut dyn Externalities`.
+//! default implementations and implements the trait for `&mut dyn Externalities`. The methods of
+//! a `wasm_only` interface and `#[wasm_only]` methods are implemented for
+//! `&mut dyn FunctionContext` instead.
 
 use crate::utils::{
 	generate_crate_access,
 	get_function_argument_types_without_ref,
 	get_runtime_interface,
 	create_function_ident_with_version,
+	create_async_function_ident,
+	is_runtime_interface_attribute,
+	replace_raw_ptr_arguments,
+	generate_allow_generated_lints,
+	is_wasm_only_method,
+	create_wasm_only_trait_ident,
+	generate_function_context_type,
+	get_result_error_type,
+	generate_host_impl_cfg,
+	is_native_only_method,
+	get_borrowed_return_value,
+	RuntimeInterface,
 };
 
+use super::Options;
+
 use syn::{
-	ItemTrait, TraitItemMethod, Result, Error, fold::{self, Fold}, spanned::Spanned,
-	Visibility, Receiver, Type, Generics,
+	ItemTrait, TraitItemMethod, Result, Error, Ident, fold::{self, Fold}, spanned::Spanned,
+	Visibility, Receiver, Type, Generics, GenericParam, ReturnType, TypeReference, TypeImplTrait,
+	ExprPath, Macro, TypePath, visit::{self, Visit}, parse_quote, Signature, FnArg, Pat,
 };
 
-use proc_macro2::TokenStream;
+use proc_macro2::{TokenStream, TokenTree};
 
 use quote::quote;
 
 /// Process the given trait definition, by checking that the definition is valid, fold it to the
 /// essential definition and implement this essential definition for `dyn Externalities`.
-pub fn process(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
-	let impl_trait = impl_trait_for_externalities(trait_def, is_wasm_only)?;
-	let essential_trait_def = declare_essential_trait(trait_def)?;
+///
+/// In a `no_externalities` interface, the essential definition is implemented for `()` instead,
+/// so the methods don't have access to any `Externalities`.
+///
+/// The `#[wasm_only]` methods of an interface that is not `wasm_only` are put into an extra
+/// essential trait that is implemented for `dyn FunctionContext`.
+pub fn process(trait_def: &ItemTrait, options: &Options) -> Result<TokenStream> {
+	check_trait_def(trait_def)?;
+
+	let is_wasm_only = options.wasm_only;
+	let trait_ = &trait_def.ident;
+	let crate_ = generate_crate_access();
+	let interface = get_runtime_interface(trait_def)?;
+	let (wasm_only_methods, methods): (Vec<_>, Vec<_>) = interface.all_versions()
+		.partition(|(_, method)| is_wasm_only_method(method, is_wasm_only));
+
+	let mut res = TokenStream::new();
+	if options.no_externalities {
+		methods.iter().try_for_each(|(_, method)| error_on_self_receiver(method))?;
+		res.extend(declare_and_impl_essential_trait(
+			trait_,
+			&interface,
+			&methods,
+			quote!( () ),
+			options,
+		)?);
+	} else if !is_wasm_only {
+		res.extend(declare_and_impl_essential_trait(
+			trait_,
+			&interface,
+			&methods,
+			quote!( &mut dyn #crate_::Externalities ),
+			options,
+		)?);
+		if options.deny_unused_self {
+			methods.iter().try_for_each(|(_, method)| error_on_unused_self_receiver(method))?;
+		}
+
+		if options.no_panic {
+			methods.iter().try_for_each(|(_, method)| error_on_panicking_method(method))?;
+		}
+	}
+
+	if is_wasm_only || !wasm_only_methods.is_empty() {
+		let wasm_only_trait = if is_wasm_only {
+			trait_.clone()
+		} else {
+			create_wasm_only_trait_ident(trait_)
+		};
+
+		res.extend(declare_and_impl_essential_trait(
+			&wasm_only_trait,
+			&interface,
+			&wasm_only_methods,
+			generate_function_context_type(options.context.as_ref()),
+			options,
+		)?);
+	}
+
+	Ok(res)
+}
+
+/// Declares the essential trait with the given methods and implements it for `impl_type`.
+fn declare_and_impl_essential_trait(
+	trait_: &Ident,
+	interface: &RuntimeInterface,
+	methods: &[(u32, &TraitItemMethod)],
+	impl_type: TokenStream,
+	options: &Options,
+) -> Result<TokenStream> {
+	let impl_trait = impl_essential_trait(trait_, interface, methods, impl_type, options);
+	let essential_trait_def = declare_essential_trait(
+		trait_,
+		interface,
+		methods,
+		options.is_async,
+	)?;
 
 	Ok(
 		quote! {
@@ -76,10 +167,24 @@ impl ToEssentialTraitDef {
 		}
 	}
 
-	fn process(&mut self, method: &TraitItemMethod, version: u32) {
+	/// Process the given version of the method.
+	///
+	/// A version that is an alias of another version is only checked, as it is implemented by
+	/// calling the other version.
+	fn process(&mut self, method: &TraitItemMethod, version: u32, is_alias: bool) {
+		match (&method.default, is_alias) {
+			(None, false) => self.push_error(method, "Methods need to have an implementation."),
+			(Some(block), true) =>
+				self.push_error(block, "Versions with `alias` can not have an implementation."),
+			_ => {},
+		}
+
 		let mut folded = self.fold_trait_item_method(method.clone());
-		folded.sig.ident = create_function_ident_with_version(&folded.sig.ident, version);
-		self.methods.push(folded);
+
+		if !is_alias {
+			folded.sig.ident = create_function_ident_with_version(&folded.sig.ident, version);
+			self.methods.push(folded);
+		}
 	}
 
 	fn push_error<S: Spanned>(&mut self, span: &S, msg: &str) {
@@ -88,28 +193,231 @@ impl ToEssentialTraitDef {
 
 	fn error_on_generic_parameters(&mut self, generics: &Generics) {
 		if let Some(param) = generics.params.first() {
-			self.push_error(param, "Generic parameters not supported.");
+			if let GenericParam::Lifetime(_) = param {
+				self.push_error(
+					param,
+					"Lifetime parameters not supported. Arguments are only borrowed for the \
+					duration of the call.",
+				);
+			} else {
+				self.push_error(param, "Generic parameters not supported.");
+			}
+		}
+	}
+
+	fn error_on_unsupported_argument_type(&mut self, ty: &Type, is_native_only: bool) {
+		match ty {
+			Type::TraitObject(trait_object) if !is_native_only => self.push_error(
+				trait_object,
+				"`dyn Trait` arguments are only supported in `#[native_only]` methods.",
+			),
+			_ => {},
+		}
+
+		let mut finder = FindImplTrait(None);
+		finder.visit_type(ty);
+
+		if let Some(impl_trait) = finder.0 {
+			self.push_error(impl_trait, "`impl Trait` syntax not supported.");
+		}
+
+		if !is_native_only {
+			self.error_on_pointer_sized_integer(ty);
+		}
+	}
+
+	fn error_on_pointer_sized_integer(&mut self, ty: &Type) {
+		let mut finder = FindPointerSizedInteger(None);
+		finder.visit_type(ty);
+
+		if let Some(ty) = finder.0 {
+			let msg = format!(
+				"`{}` has a different width in wasm and on the host. Use a fixed-width integer \
+				type like `u32` or `u64` instead.",
+				quote!( #ty ),
+			);
+			self.push_error(ty, &msg);
+		}
+	}
+
+	fn error_on_reference_return_value(&mut self, output: &ReturnType) {
+		if let ReturnType::Type(_, ty) = output {
+			let mut finder = FindReference(None);
+			finder.visit_type(ty);
+
+			if let Some(reference) = finder.0 {
+				self.push_error(
+					reference,
+					"Returning references not supported. The return value needs to be owned.",
+				);
+			}
+		}
+	}
+}
+
+/// Returns an error if the given method takes `self`.
+///
+/// The methods of a `no_externalities` interface are called without any `Externalities`, so there
+/// is nothing that could be passed as `self`.
+fn error_on_self_receiver(method: &TraitItemMethod) -> Result<()> {
+	match method.sig.receiver() {
+		Some(receiver) => Err(Error::new(
+			receiver.span(),
+			"Methods of a `no_externalities` interface can not take `self`.",
+		)),
+		None => Ok(()),
+	}
+}
+
+/// Returns an error if the given method takes `self`, but doesn't return a `Result`.
+///
+/// In a `no_panic` interface, a method taking `self` returns an error when it is called without
+/// `Externalities`, so it needs to return a `Result`.
+fn error_on_panicking_method(method: &TraitItemMethod) -> Result<()> {
+	if method.sig.receiver().is_none() || get_result_error_type(&method.sig).is_some() {
+		return Ok(())
+	}
+
+	let span = match &method.sig.output {
+		ReturnType::Type(_, ty) => ty.span(),
+		ReturnType::Default => method.sig.span(),
+	};
+
+	Err(Error::new(
+		span,
+		"Methods taking `self` need to return a `Result` in a `no_panic` interface, to return an \
+		error when they are called without `Externalities` set.",
+	))
+}
+
+/// Returns an error if the given method takes `self`, but its implementation doesn't use it.
+///
+/// This is only checked in a `deny_unused_self` interface.
+///
+/// A method that takes `self` can only be called with `Externalities` set. Without using `self`,
+/// the receiver is most likely a mistake that makes the function unusable without `Externalities`.
+/// Methods that intentionally require `Externalities` are annotated with
+/// `#[requires_externalities]`.
+fn error_on_unused_self_receiver(method: &TraitItemMethod) -> Result<()> {
+	let receiver = match method.sig.receiver() {
+		Some(_) if method.attrs.iter().any(|a| a.path.is_ident("requires_externalities")) =>
+			return Ok(()),
+		Some(receiver) => receiver,
+		None => return Ok(()),
+	};
+
+	match &method.default {
+		Some(block) => {
+			let mut finder = FindSelf(false);
+			finder.visit_block(block);
+
+			if finder.0 {
+				Ok(())
+			} else {
+				Err(Error::new(
+					receiver.span(),
+					"The implementation doesn't use `self`. Methods taking `self` can only be \
+					called with `Externalities` set. Remove `self` or annotate the method with \
+					`#[requires_externalities]`, if this is intended.",
+				))
+			}
+		},
+		// Versions with `alias` are implemented by the version they refer to.
+		None => Ok(()),
+	}
+}
+
+/// Finds a usage of `self` in an expression or in the tokens of a macro call.
+struct FindSelf(bool);
+
+impl FindSelf {
+	fn find_in_tokens(&mut self, tokens: TokenStream) {
+		for token in tokens {
+			match token {
+				TokenTree::Ident(ident) if ident == "self" => self.0 = true,
+				TokenTree::Group(group) => self.find_in_tokens(group.stream()),
+				_ => {},
+			}
+		}
+	}
+}
+
+impl<'a> Visit<'a> for FindSelf {
+	fn visit_expr_path(&mut self, path: &'a ExprPath) {
+		if path.qself.is_none() && path.path.is_ident("self") {
+			self.0 = true;
+		}
+
+		visit::visit_expr_path(self, path);
+	}
+
+	fn visit_macro(&mut self, mac: &'a Macro) {
+		self.find_in_tokens(mac.tokens.clone());
+	}
+}
+
+/// Finds the first `impl Trait` in a type.
+struct FindImplTrait<'a>(Option<&'a TypeImplTrait>);
+
+impl<'a> Visit<'a> for FindImplTrait<'a> {
+	fn visit_type_impl_trait(&mut self, impl_trait: &'a TypeImplTrait) {
+		if self.0.is_none() {
+			self.0 = Some(impl_trait);
+		}
+	}
+}
+
+/// Finds the first `usize` or `isize` in a type.
+struct FindPointerSizedInteger<'a>(Option<&'a TypePath>);
+
+impl<'a> Visit<'a> for FindPointerSizedInteger<'a> {
+	fn visit_type_path(&mut self, path: &'a TypePath) {
+		let is_pointer_sized = path.qself.is_none() &&
+			(path.path.is_ident("usize") || path.path.is_ident("isize"));
+
+		if is_pointer_sized && self.0.is_none() {
+			self.0 = Some(path);
+		}
+
+		visit::visit_type_path(self, path);
+	}
+}
+
+/// Finds the first reference in a type.
+struct FindReference<'a>(Option<&'a TypeReference>);
+
+impl<'a> Visit<'a> for FindReference<'a> {
+	fn visit_type_reference(&mut self, reference: &'a TypeReference) {
+		if self.0.is_none() {
+			self.0 = Some(reference);
 		}
 	}
 }
 
 impl Fold for ToEssentialTraitDef {
 	fn fold_trait_item_method(&mut self, mut method: TraitItemMethod) -> TraitItemMethod {
-		if method.default.take().is_none() {
-			self.push_error(&method, "Methods need to have an implementation.");
-		}
+		method.default = None;
 
+		// `#[native_only]` methods don't cross the wasm boundary and can take `dyn Trait`
+		// arguments.
+		let is_native_only = is_native_only_method(&method);
 		let arg_types = get_function_argument_types_without_ref(&method.sig);
-		arg_types.filter_map(|ty|
-			match *ty {
-				Type::ImplTrait(impl_trait) => Some(impl_trait),
-				_ => None
-			}
-		).for_each(|invalid| self.push_error(&invalid, "`impl Trait` syntax not supported."));
+		arg_types.for_each(|ty| self.error_on_unsupported_argument_type(&ty, is_native_only));
 
-		self.error_on_generic_parameters(&method.sig.generics);
+		// The only supported lifetime parameter is the one of a slice of an argument that is
+		// returned.
+		if get_borrowed_return_value(&method.sig).is_none() {
+			self.error_on_generic_parameters(&method.sig.generics);
+			self.error_on_reference_return_value(&method.sig.output);
+		}
+		match &method.sig.output {
+			ReturnType::Type(_, ty) if !is_native_only => self.error_on_pointer_sized_integer(ty),
+			_ => {},
+		}
 
-		method.attrs.retain(|a| !a.path.is_ident("version"));
+		method.attrs.retain(|a| {
+			!is_runtime_interface_attribute(a) && !a.path.is_ident("deprecated")
+		});
 
 		fold::fold_trait_item_method(self, method)
 	}
@@ -130,22 +438,56 @@ impl Fold for ToEssentialTraitDef {
 	}
 }
 
-fn declare_essential_trait(trait_def: &ItemTrait) -> Result<TokenStream> {
-	let trait_ = &trait_def.ident;
-
+/// Checks that the given trait definition is supported.
+fn check_trait_def(trait_def: &ItemTrait) -> Result<()> {
 	if let Some(param) = trait_def.generics.params.first() {
 		return Err(Error::new(param.span(), "Generic parameters not supported."))
 	}
 
-	let interface = get_runtime_interface(trait_def)?;
+	if let Some(supertrait) = trait_def.supertraits.first() {
+		return Err(Error::new(
+			supertrait.span(),
+			"Supertraits not supported. Every function of the interface needs to be declared in \
+			the interface trait itself.",
+		))
+	}
+
+	Ok(())
+}
+
+/// Declares the essential trait with the given methods.
+///
+/// In an `async` interface, every method has a `name_version_X_async` twin that returns the
+/// future of the implementation. The twins only exist on the native side, as they are only
+/// implemented there.
+fn declare_essential_trait(
+	trait_: &Ident,
+	interface: &RuntimeInterface,
+	methods: &[(u32, &TraitItemMethod)],
+	is_async: bool,
+) -> Result<TokenStream> {
 	let mut folder = ToEssentialTraitDef::new();
-	for (version, interface_method) in interface.all_versions() {
-		folder.process(interface_method, version);
+	for (version, interface_method) in methods {
+		let is_alias = interface.alias_of(&interface_method.sig.ident, *version).is_some();
+		folder.process(&replace_raw_ptr_arguments(interface_method), *version, is_alias);
 	}
-	let methods = folder.into_methods()?;
+	let mut methods = folder.into_methods()?;
+	if is_async {
+		let async_methods = methods.iter()
+			.map(|method| {
+				let mut method = method.clone();
+				method.sig = generate_async_signature(&method.sig);
+				method.attrs.push(parse_quote!( #[cfg(feature = "std")] ));
+				method
+			})
+			.collect::<Vec<_>>();
+		methods.extend(async_methods);
+	}
+	let allow_lints = generate_allow_generated_lints();
 
 	Ok(
 		quote! {
+			#allow_lints
 			trait #trait_ {
 				#( #methods )*
 			}
@@ -153,30 +495,111 @@ fn declare_essential_trait(trait_def: &ItemTrait) -> Result<TokenStream> {
 	)
 }
 
-/// Implements the given trait definition for `dyn Externalities`.
-fn impl_trait_for_externalities(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
-	let trait_ = &trait_def.ident;
+/// Implements the given essential trait for `impl_type`.
+///
+/// If the interface is `async`, the implementation of every method is put into an `async` block
+/// that the `_async` twin of the method returns. The method itself drives this future to
+/// completion, because it is called by the host function of a synchronous call from wasm.
+fn impl_essential_trait(
+	trait_: &Ident,
+	interface: &RuntimeInterface,
+	methods: &[(u32, &TraitItemMethod)],
+	impl_type: TokenStream,
+	options: &Options,
+) -> TokenStream {
+	let methods = methods.iter()
+		.filter(|(version, method)| interface.alias_of(&method.sig.ident, *version).is_none())
+		.flat_map(|(version, method)| {
+			let mut cloned = replace_raw_ptr_arguments(method);
+			cloned.attrs.retain(|a| {
+				!is_runtime_interface_attribute(a) && !a.path.is_ident("deprecated")
+			});
+			cloned.sig.ident = create_function_ident_with_version(&cloned.sig.ident, *version);
+			if options.is_async {
+				let async_method = TraitItemMethod {
+					sig: generate_async_signature(&cloned.sig),
+					default: cloned.default.as_ref().map(|block| parse_quote!({
+						Box::pin(async move #block)
+					})),
+					..cloned.clone()
+				};
+
+				vec![block_on_async_method(cloned, &async_method.sig.ident), async_method]
+			} else {
+				vec![cloned]
+			}
+		});
+	let host_impl_cfg = generate_host_impl_cfg(options.registration_only);
+
+	quote! {
+		#host_impl_cfg
+		impl #trait_ for #impl_type {
+			#( #methods )*
+		}
+	}
+}
+
+/// Returns the signature of the `_async` twin of the given method of an `async` interface.
+///
+/// The twin takes the same arguments, but borrows them for the lifetime `'__async` of the future
+/// it returns.
+fn generate_async_signature(sig: &Signature) -> Signature {
 	let crate_ = generate_crate_access();
-	let interface = get_runtime_interface(trait_def)?;
-	let methods = interface.all_versions().map(|(version, method)| {
-		let mut cloned = method.clone();
-		cloned.attrs.retain(|a| !a.path.is_ident("version"));
-		cloned.sig.ident = create_function_ident_with_version(&cloned.sig.ident, version);
-		cloned
-	});
-
-	let impl_type = if is_wasm_only {
-		quote!( &mut dyn #crate_::sp_wasm_interface::FunctionContext )
-	} else {
-		quote!( &mut dyn #crate_::Externalities )
+	let mut sig = sig.clone();
+	let output = match &sig.output {
+		ReturnType::Default => quote!( () ),
+		ReturnType::Type(_, ty) => quote!( #ty ),
 	};
 
-	Ok(
-		quote! {
-			#[cfg(feature = "std")]
-			impl #trait_ for #impl_type {
-				#( #methods )*
-			}
+	sig.ident = create_async_function_ident(&sig.ident);
+	sig.generics.params.push(parse_quote!( '__async ));
+	sig.inputs = sig.inputs.into_iter().map(|arg| BorrowForFuture.fold_fn_arg(arg)).collect();
+	sig.output = parse_quote!( -> #crate_::futures::future::LocalBoxFuture<'__async, #output> );
+	sig
+}
+
+/// Gives all references without a lifetime the lifetime `'__async` of the future of an `async`
+/// method.
+struct BorrowForFuture;
+
+impl Fold for BorrowForFuture {
+	fn fold_type_reference(&mut self, mut reference: TypeReference) -> TypeReference {
+		reference.lifetime = reference.lifetime.or_else(|| Some(parse_quote!( '__async )));
+		fold::fold_type_reference(self, reference)
+	}
+
+	fn fold_receiver(&mut self, mut receiver: Receiver) -> Receiver {
+		if let Some((_, lifetime @ None)) = &mut receiver.reference {
+			*lifetime = Some(parse_quote!( '__async ));
 		}
-	)
+
+		receiver
+	}
+}
+
+/// Returns the given method of an `async` interface with an implementation that drives the future
+/// of its `_async` twin to completion.
+fn block_on_async_method(mut method: TraitItemMethod, async_name: &Ident) -> TraitItemMethod {
+	let crate_ = generate_crate_access();
+	let receiver = method.sig.receiver().map(|_| quote!( self, ));
+	// The arguments are only forwarded, so they are bound to plain identifiers.
+	let arg_names = method.sig.inputs.iter_mut()
+		.enumerate()
+		.filter_map(|(i, arg)| match arg {
+			FnArg::Typed(arg) => {
+				let name = match &*arg.pat {
+					Pat::Ident(pat) => pat.ident.clone(),
+					pat => Ident::new(&format!("__runtime_interface_generated_{}_", i), pat.span()),
+				};
+				arg.pat = parse_quote!( #name );
+				Some(name)
+			},
+			FnArg::Receiver(_) => None,
+		})
+		.collect::<Vec<_>>();
+
+	method.default = method.default.map(|_| parse_quote!({
+		#crate_::futures::executor::block_on(Self::#async_name( #receiver #( #arg_names ),* ))
+	}));
+	method
 }
@@ -20,40 +20,75 @@ use proc_macro2::{TokenStream, Span};
 
 use syn::{
 	Ident, Error, Signature, Pat, PatType, FnArg, Type, token, TraitItemMethod, ItemTrait,
-	TraitItem, parse_quote, spanned::Spanned, Result, Meta, NestedMeta, Lit, Attribute,
+	TraitItem, parse_quote, spanned::Spanned, Result, Meta, NestedMeta, Lit, Attribute, ReturnType,
+	PathArguments, GenericArgument, LitStr, MetaNameValue, Expr, GenericParam, Lifetime, TypePath,
+	visit::{self, Visit},
 };
 
 use proc_macro_crate::crate_name;
 
 use std::env;
-use std::collections::{BTreeMap, btree_map::Entry};
+use std::collections::{BTreeMap, BTreeSet, btree_map::Entry};
 
-use quote::quote;
+use quote::{quote, quote_spanned};
 
 use inflector::Inflector;
 
+/// Attributes on trait methods that are consumed by the macro and must not be emitted.
+const RUNTIME_INTERFACE_ATTRIBUTES: &[&str] = &[
+	"version", "deprecated_from", "span_name", "export_name", "batch", "no_tracing", "wasm_only",
+	"requires_externalities", "pass_by", "skip_version", "native_only", "latest_only", "no_alloc",
+];
+
+/// The parsed `#[version(..)]` attribute of a method.
+pub struct ItemVersion {
+	/// The version of the method.
+	pub version: u32,
+	/// Is the version only registered on the host (`register_only`)?
+	pub register_only: bool,
+	/// The version that implements this version (`alias = X`).
+	pub alias: Option<u32>,
+	/// Is this the minimum version runtimes need to call (`min = true`)?
+	pub min: bool,
+}
+
 /// Runtime interface function with all associated versions of this function.
 pub struct RuntimeInterfaceFunction<'a> {
-	latest_version: u32,
+	/// The latest version that is not `register_only`.
+	latest_version: Option<u32>,
 	versions: BTreeMap<u32, &'a TraitItemMethod>,
+	/// All versions starting from this version are deprecated (`#[deprecated_from(X)]`).
+	deprecated_from: Option<u32>,
+	/// The versions that are implemented by another version, mapped to this other version.
+	aliases: BTreeMap<u32, u32>,
+	/// The minimum version runtimes need to call (`#[version(X, min = true)]`).
+	min_version: Option<u32>,
+	/// The versions that are intentionally absent (`#[skip_version(X)]`).
+	skipped_versions: BTreeSet<u32>,
 }
 
 impl<'a> RuntimeInterfaceFunction<'a> {
-	fn new(version: u32, trait_item: &'a TraitItemMethod) -> Self {
+	fn new(version: u32, register_only: bool, trait_item: &'a TraitItemMethod) -> Self {
 		Self {
-			latest_version: version,
+			latest_version: if register_only { None } else { Some(version) },
 			versions: {
 				let mut res = BTreeMap::new();
 				res.insert(version, trait_item);
 				res
 			},
+			deprecated_from: None,
+			aliases: BTreeMap::new(),
+			min_version: None,
+			skipped_versions: BTreeSet::new(),
 		}
 	}
 
 	pub fn latest_version(&self) -> (u32, &TraitItemMethod) {
+		let latest_version = self.latest_version
+			.expect("Every function has at least one version that is not `register_only`; qed");
 		(
-			self.latest_version,
-			self.versions.get(&self.latest_version)
+			latest_version,
+			self.versions.get(&latest_version)
 				.expect("If latest_version has a value, the key with this value is in the versions; qed")
 		)
 	}
@@ -72,12 +107,84 @@ impl<'a> RuntimeInterface<'a> {
 	pub fn all_versions(&self) -> impl Iterator<Item = (u32, &TraitItemMethod)> {
 		self.items.iter().flat_map(|(_, item)| item.versions.iter()).map(|(v, i)| (*v, *i))
 	}
+
+	/// Returns the version starting from which the given function is deprecated, if any.
+	pub fn deprecated_from(&self, name: &Ident) -> Option<u32> {
+		self.items.get(name).and_then(|item| item.deprecated_from)
+	}
+
+	/// Returns the version that implements the given version of the function, if it is an alias.
+	pub fn alias_of(&self, name: &Ident, version: u32) -> Option<u32> {
+		self.items.get(name).and_then(|item| item.aliases.get(&version).copied())
+	}
+
+	/// Returns all versions of the given function.
+	pub fn versions(&self, name: &Ident) -> impl Iterator<Item = (u32, &TraitItemMethod)> {
+		self.items.get(name)
+			.into_iter()
+			.flat_map(|item| item.versions.iter())
+			.map(|(v, i)| (*v, *i))
+	}
+
+	/// Returns the given version of the function.
+	pub fn method(&self, name: &Ident, version: u32) -> Option<&TraitItemMethod> {
+		self.items.get(name).and_then(|item| item.versions.get(&version).copied())
+	}
+
+	/// Returns all skipped versions of all functions together with the version before the skipped
+	/// version, which provides the signature of the skipped version.
+	pub fn skipped_versions(&self) -> impl Iterator<Item = (u32, &TraitItemMethod)> {
+		self.items.values().flat_map(|item| item.skipped_versions.iter().map(move |version| {
+			let (_, method) = item.versions.range(..version).next_back()
+				.expect("Only versions above the first version can be skipped; qed");
+			(*version, *method)
+		}))
+	}
  }
 
+/// Returns if the given attribute is consumed by the runtime interface macro.
+pub fn is_runtime_interface_attribute(attr: &Attribute) -> bool {
+	RUNTIME_INTERFACE_ATTRIBUTES.iter().any(|name| attr.path.is_ident(name))
+}
+
+/// Returns if the given method is only callable from wasm.
+///
+/// This is the case when the interface is `wasm_only` or the method is annotated with
+/// `#[wasm_only]`.
+pub fn is_wasm_only_method(method: &TraitItemMethod, is_wasm_only_interface: bool) -> bool {
+	is_wasm_only_interface || method.attrs.iter().any(|a| a.path.is_ident("wasm_only"))
+}
+
+/// Returns if the given method is annotated with `#[native_only]` and is only callable natively.
+pub fn is_native_only_method(method: &TraitItemMethod) -> bool {
+	method.attrs.iter().any(|a| a.path.is_ident("native_only"))
+}
+
+/// Returns if the given method is annotated with `#[latest_only]`.
+fn is_latest_only_method(method: &TraitItemMethod) -> bool {
+	method.attrs.iter().any(|a| a.path.is_ident("latest_only"))
+}
+
+/// Returns the `#[cfg(..)]` attributes of the given method.
+///
+/// These attributes need to be put on every item that is generated for the method, as otherwise
+/// the generated items would reference items that are not compiled.
+pub fn get_cfg_attributes(method: &TraitItemMethod) -> impl Iterator<Item = &Attribute> {
+	method.attrs.iter().filter(|a| a.path.is_ident("cfg"))
+}
+
 /// Generates the include for the runtime-interface crate.
-pub fn generate_runtime_interface_include() -> TokenStream {
+///
+/// If `crate_path` is given, the crate is included from this path instead of the dependency, e.g.
+/// when it is re-exported by another crate.
+pub fn generate_runtime_interface_include(crate_path: Option<&syn::Path>) -> TokenStream {
 	if env::var("CARGO_PKG_NAME").unwrap() == "sp-runtime-interface" {
 		TokenStream::new()
+	} else if let Some(crate_path) = crate_path {
+		quote!(
+			#[doc(hidden)]
+			use #crate_path as proc_macro_runtime_interface;
+		)
 	} else {
 		match crate_name("sp-runtime-interface") {
 			Ok(crate_name) => {
@@ -104,11 +211,70 @@ pub fn generate_crate_access() -> TokenStream {
 	}
 }
 
+/// Generates the `cfg` attribute for the host side implementation of the interface.
+///
+/// If `registration_only` is set, the implementation is not compiled when the `registration-only`
+/// feature of the crate declaring the interface is enabled. Only the host functions are compiled
+/// then, to register them in an executor.
+pub fn generate_host_impl_cfg(registration_only: bool) -> TokenStream {
+	if registration_only {
+		quote!( #[cfg(all(feature = "std", not(feature = "registration-only")))] )
+	} else {
+		quote!( #[cfg(feature = "std")] )
+	}
+}
+
+/// Generates the `allow` attribute for the lints the generated functions trigger because of the
+/// declared signature, e.g. `clippy::too_many_arguments` for a method with many arguments.
+///
+/// The users don't write the generated functions and can't allow the lints on them.
+pub fn generate_allow_generated_lints() -> TokenStream {
+	quote!( #[allow(clippy::too_many_arguments, clippy::type_complexity)] )
+}
+
+/// Generates the type of the function context that is passed to the `#[wasm_only]` methods.
+///
+/// By default this is `&mut dyn FunctionContext`. If `context` is given, it is `&mut dyn context`
+/// and the host functions pass their `FunctionContext` as `&mut &mut dyn FunctionContext`.
+pub fn generate_function_context_type(context: Option<&syn::Path>) -> TokenStream {
+	match context {
+		Some(context) => quote!( &mut dyn #context ),
+		None => {
+			let crate_ = generate_crate_access();
+			quote!( &mut dyn #crate_::sp_wasm_interface::FunctionContext )
+		},
+	}
+}
+
+/// Generates the call to `enter_span!` for the given span name.
+///
+/// The span records the `version` of the called function as field, so the calls can be grouped by
+/// the version in a tracing backend.
+///
+/// If the `interface-tracing` feature is disabled or the method is annotated with `#[no_tracing]`,
+/// no code is generated.
+pub fn generate_enter_span(method: &TraitItemMethod, name: &str, version: u32) -> TokenStream {
+	let no_tracing = method.attrs.iter().any(|a| a.path.is_ident("no_tracing"));
+
+	if !cfg!(feature = "interface-tracing") || no_tracing {
+		TokenStream::new()
+	} else {
+		let crate_ = generate_crate_access();
+		quote!( #crate_::sp_tracing::enter_span!(#name, version = #version); )
+	}
+}
+
 /// Create the exchangeable host function identifier for the given function name.
 pub fn create_exchangeable_host_function_ident(name: &Ident) -> Ident {
 	Ident::new(&format!("host_{}", name), Span::call_site())
 }
 
+/// Create the identifier of the trait that implements the `#[wasm_only]` methods of the given
+/// interface trait.
+pub fn create_wasm_only_trait_ident(trait_name: &Ident) -> Ident {
+	Ident::new(&format!("{}WasmOnly", trait_name), Span::call_site())
+}
+
 /// Create the host function identifier for the given function name.
 pub fn create_host_function_ident(name: &Ident, version: u32, trait_name: &Ident) -> Ident {
 	Ident::new(
@@ -122,6 +288,20 @@ pub fn create_host_function_ident(name: &Ident, version: u32, trait_name: &Ident
 	)
 }
 
+/// Create the name of the feature that disables the registration of all versions of the host
+/// function with the given name.
+pub fn create_host_function_feature_name(name: &Ident, trait_name: &Ident) -> String {
+	format!("disable_hostfn_{}_{}", trait_name.to_string().to_snake_case(), name)
+}
+
+/// Create the identifier of the constant that holds the versioned name of the given function.
+pub fn create_function_name_const_ident(name: &Ident, version: u32) -> Ident {
+	Ident::new(
+		&create_function_ident_with_version(name, version).to_string().to_screaming_snake_case(),
+		Span::call_site(),
+	)
+}
+
 /// Create the host function identifier for the given function name.
 pub fn create_function_ident_with_version(name: &Ident, version: u32) -> Ident {
 	Ident::new(
@@ -134,6 +314,12 @@ pub fn create_function_ident_with_version(name: &Ident, version: u32) -> Ident {
 	)
 }
 
+/// Create the identifier of the function that returns the future of the given function of an
+/// `async` interface.
+pub fn create_async_function_ident(name: &Ident) -> Ident {
+	Ident::new(&format!("{}_async", name), Span::call_site())
+}
+
 /// Returns the function arguments of the given `Signature`, minus any `self` arguments.
 pub fn get_function_arguments<'a>(sig: &'a Signature) -> impl Iterator<Item = PatType> + 'a {
 	sig.inputs
@@ -145,6 +331,12 @@ pub fn get_function_arguments<'a>(sig: &'a Signature) -> impl Iterator<Item = Pa
 		.enumerate()
 		.map(|(i, arg)| {
 			let mut res = arg.clone();
+			// `#[pass_by(..)]`, `#[out]` and `#[callback]` are only used to generate the ffi
+			// signature and `#[default_when_missing(..)]` only to call a newer version.
+			res.attrs.retain(|a|
+				!is_ffi_argument_attribute(a) && !is_default_argument_attribute(a)
+			);
+
 			if let Pat::Wild(wild) = &*arg.pat {
 				let ident = Ident::new(
 					&format!("__runtime_interface_generated_{}_", i),
@@ -158,6 +350,36 @@ pub fn get_function_arguments<'a>(sig: &'a Signature) -> impl Iterator<Item = Pa
 		})
 }
 
+/// Returns if the given attribute of a function argument is consumed by the macro.
+pub fn is_ffi_argument_attribute(attr: &Attribute) -> bool {
+	attr.path.is_ident("pass_by") || attr.path.is_ident("out") || attr.path.is_ident("callback") ||
+		attr.path.is_ident("compressed")
+}
+
+/// Returns if the given attribute is `#[default_when_missing(..)]`.
+fn is_default_argument_attribute(attr: &Attribute) -> bool {
+	attr.path.is_ident("default_when_missing")
+}
+
+/// Returns the value of the `#[default_when_missing(value)]` attribute of the given argument.
+///
+/// The value is passed for the argument when the previous version, which doesn't have this
+/// argument, is called.
+pub fn get_default_argument(arg: &PatType) -> Result<Option<Expr>> {
+	arg.attrs.iter()
+		.find(|a| is_default_argument_attribute(a))
+		.map(|attr| attr.parse_args())
+		.transpose()
+}
+
+/// Returns if any argument of the given method has a `#[default_when_missing(..)]` attribute.
+fn has_default_arguments(method: &TraitItemMethod) -> bool {
+	method.sig.inputs.iter().any(|arg| match arg {
+		FnArg::Typed(arg) => arg.attrs.iter().any(is_default_argument_attribute),
+		FnArg::Receiver(_) => false,
+	})
+}
+
 /// Returns the function argument names of the given `Signature`, minus any `self`.
 pub fn get_function_argument_names<'a>(sig: &'a Signature) -> impl Iterator<Item = Box<Pat>> + 'a {
 	get_function_arguments(sig).map(|pt| pt.pat)
@@ -206,6 +428,118 @@ pub fn get_function_argument_types_ref_and_mut<'a>(
 		})
 }
 
+/// Returns the error type `E`, if the given `Signature` returns a `Result<T, E>`.
+pub fn get_result_error_type(sig: &Signature) -> Option<&Type> {
+	let ty = match &sig.output {
+		ReturnType::Type(_, ty) => ty,
+		ReturnType::Default => return None,
+	};
+
+	let segment = match &**ty {
+		Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+		_ => return None,
+	};
+
+	if segment.ident != "Result" {
+		return None
+	}
+
+	match &segment.arguments {
+		PathArguments::AngleBracketed(args) if args.args.len() == 2 => match args.args.last() {
+			Some(GenericArgument::Type(err)) => Some(err),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+/// Removes the versions of the methods annotated with `#[latest_only]` that are older than the
+/// latest version and, with `keep_versions`, the versions of all other methods that are older than
+/// the latest `keep_versions` versions.
+///
+/// Nothing is generated for the removed versions, so the host functions for these versions are
+/// not registered either. Newer `register_only` versions are kept. With `no_legacy`, every method
+/// is treated as `#[latest_only]`.
+pub fn remove_old_versions(
+	mut trait_def: ItemTrait,
+	no_legacy: bool,
+	keep_versions: Option<u32>,
+) -> Result<ItemTrait> {
+	let mut latest_versions = BTreeMap::<Ident, u32>::new();
+	for method in get_trait_methods(&trait_def) {
+		let version = get_item_version(method)?;
+		if matches!(version, Some(ItemVersion { register_only: true, .. })) {
+			continue
+		}
+		let version = version.map_or(1, |version| version.version);
+
+		let latest = latest_versions.entry(method.sig.ident.clone()).or_insert(version);
+		*latest = (*latest).max(version);
+	}
+
+	let mut latest_only = BTreeSet::new();
+	for method in get_trait_methods(&trait_def) {
+		if !no_legacy && !is_latest_only_method(method) {
+			continue
+		}
+
+		let alias = get_trait_methods(&trait_def)
+			.filter(|other| other.sig.ident == method.sig.ident)
+			.find(|other| matches!(
+				get_item_version(other),
+				Ok(Some(ItemVersion { alias: Some(_), .. })),
+			));
+		if let Some(alias) = alias {
+			let msg = if no_legacy {
+				"Methods of a `no_legacy` interface can not have versions with `alias`"
+			} else {
+				"`#[latest_only]` methods can not have versions with `alias`"
+			};
+			return Err(Error::new(alias.sig.span(), msg))
+		}
+
+		latest_only.insert(method.sig.ident.clone());
+	}
+
+	let mut items = Vec::with_capacity(trait_def.items.len());
+	for mut item in trait_def.items {
+		if let TraitItem::Method(method) = &mut item {
+			let name = &method.sig.ident;
+			let version = get_item_version(method)?.map_or(1, |version| version.version);
+
+			let keep = if latest_only.contains(name) { Some(1) } else { keep_versions };
+			if let (Some(keep), Some(latest)) = (keep, latest_versions.get(name)) {
+				if latest.saturating_sub(version) >= keep {
+					continue
+				}
+
+				let alias = get_item_version(method)?.and_then(|version| version.alias);
+				if let Some(alias) = alias.filter(|alias| latest.saturating_sub(*alias) >= keep) {
+					return Err(Error::new(
+						method.sig.span(),
+						format!(
+							"`alias = {}` refers to a version that is removed by \
+							`keep_versions = {}`",
+							alias,
+							keep,
+						),
+					))
+				}
+
+				// The remaining versions need to be annotated, as their versions don't start at 1.
+				if !is_latest_only_method(method) {
+					method.attrs.push(parse_quote!( #[latest_only] ));
+				}
+			}
+		}
+
+		items.push(item);
+	}
+	trait_def.items = items;
+
+	Ok(trait_def)
+}
+
 /// Returns an iterator over all trait methods for the given trait definition.
 fn get_trait_methods<'a>(trait_def: &'a ItemTrait) -> impl Iterator<Item = &'a TraitItemMethod> {
 	trait_def
@@ -220,12 +554,73 @@ fn get_trait_methods<'a>(trait_def: &'a ItemTrait) -> impl Iterator<Item = &'a T
 /// Parse version attribute.
 ///
 /// Returns error if it is in incorrent format. Correct format is only `#[version(X)]`.
-fn parse_version_attribute(version: &Attribute) -> Result<u32> {
+///
+/// Besides the version, the attribute can contain the `register_only` flag and the version this
+/// version is an alias of: `#[version(X, register_only, alias = Y)]`.
+fn parse_version_attribute(version: &Attribute) -> Result<ItemVersion> {
 	let meta = version.parse_meta()?;
 
 	let err = Err(Error::new(
 			meta.span(),
-			"Unexpected `version` attribute. The supported format is `#[version(1)]`",
+			"Unexpected `version` attribute. The supported formats are `#[version(1)]`, \
+			`#[version(1, register_only)]`, `#[version(2, alias = 1)]` and \
+			`#[version(2, min = true)]`",
+		)
+	);
+
+	match meta {
+		Meta::List(list) => {
+			let mut nested = list.nested.iter();
+			let mut res = match nested.next() {
+				Some(NestedMeta::Lit(Lit::Int(i))) =>
+					ItemVersion {
+						version: i.base10_parse()?,
+						register_only: false,
+						alias: None,
+						min: false,
+					},
+				_ => return err,
+			};
+
+			for item in nested {
+				match item {
+					NestedMeta::Meta(Meta::Path(flag))
+						if flag.is_ident("register_only") && !res.register_only =>
+						res.register_only = true,
+					NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit: Lit::Int(i), .. }))
+						if path.is_ident("alias") && res.alias.is_none() =>
+					{
+						let alias = i.base10_parse()?;
+
+						if alias >= res.version {
+							return Err(Error::new(
+								i.span(),
+								"`alias` needs to refer to a previous version",
+							))
+						}
+
+						res.alias = Some(alias);
+					},
+					NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit: Lit::Bool(b), .. }))
+						if path.is_ident("min") && !res.min =>
+						res.min = b.value,
+					_ => return err,
+				}
+			}
+
+			Ok(res)
+		},
+		_ => err,
+	}
+}
+
+/// Parse an attribute in the format `#[name(X)]`, where `X` is an `u32`.
+fn parse_u32_attribute(attr: &Attribute, name: &str) -> Result<u32> {
+	let meta = attr.parse_meta()?;
+
+	let err = Err(Error::new(
+			meta.span(),
+			format!("Unexpected `{0}` attribute. The supported format is `#[{0}(1)]`", name),
 		)
 	);
 
@@ -244,55 +639,1392 @@ fn parse_version_attribute(version: &Attribute) -> Result<u32> {
 }
 
 /// Return item version (`#[version(X)]`) attribute, if present.
-fn get_item_version(item: &TraitItemMethod) -> Result<Option<u32>> {
+pub fn get_item_version(item: &TraitItemMethod) -> Result<Option<ItemVersion>> {
 	item.attrs.iter().find(|attr| attr.path.is_ident("version"))
 		.map(|attr| parse_version_attribute(attr))
 		.transpose()
 }
 
-/// Returns all runtime interface members, with versions.
-pub fn get_runtime_interface<'a>(trait_def: &'a ItemTrait)
-	-> Result<RuntimeInterface<'a>>
-{
-	let mut functions: BTreeMap<syn::Ident, RuntimeInterfaceFunction<'a>> = BTreeMap::new();
+/// Return item deprecated from (`#[deprecated_from(X)]`) attribute, if present.
+fn get_item_deprecated_from(item: &TraitItemMethod) -> Result<Option<u32>> {
+	item.attrs.iter().find(|attr| attr.path.is_ident("deprecated_from"))
+		.map(|attr| parse_u32_attribute(attr, "deprecated_from"))
+		.transpose()
+}
 
-	for item in get_trait_methods(trait_def) {
-		let name = item.sig.ident.clone();
-		let version = get_item_version(item)?.unwrap_or(1);
+/// Return the versions given by the item skip version (`#[skip_version(X)]`) attributes.
+fn get_item_skipped_versions(item: &TraitItemMethod) -> Result<Vec<u32>> {
+	item.attrs.iter().filter(|attr| attr.path.is_ident("skip_version"))
+		.map(|attr| parse_u32_attribute(attr, "skip_version"))
+		.collect()
+}
 
-		match functions.entry(name.clone()) {
-			Entry::Vacant(entry) => { entry.insert(RuntimeInterfaceFunction::new(version, item)); },
-			Entry::Occupied(mut entry) => {
-				if let Some(existing_item) = entry.get().versions.get(&version) {
-					let mut err = Error::new(
-						item.span(),
-						"Duplicated version attribute",
-					);
-					err.combine(Error::new(
-						existing_item.span(),
-						"Previous version with the same number defined here",
-					));
+/// Return item span name (`#[span_name = "name"]`) attribute, if present.
+///
+/// Returns error if it is in incorrect format. Correct format is only `#[span_name = "name"]`.
+pub fn get_item_span_name(item: &TraitItemMethod) -> Result<Option<LitStr>> {
+	item.attrs.iter().find(|attr| attr.path.is_ident("span_name"))
+		.map(|attr| {
+			let meta = attr.parse_meta()?;
 
-					return Err(err);
-				}
+			match meta {
+				Meta::NameValue(name_value) => match name_value.lit {
+					Lit::Str(name) => Ok(name),
+					lit => Err(Error::new(lit.span(), "Expected a string literal as span name")),
+				},
+				_ => Err(Error::new(
+					meta.span(),
+					"Unexpected `span_name` attribute. The supported format is \
+					`#[span_name = \"name\"]`",
+				)),
+			}
+		})
+		.transpose()
+}
 
-				let interface_item = entry.get_mut();
-				if interface_item.latest_version < version { interface_item.latest_version = version; }
-				interface_item.versions.insert(version, item);
+/// Return item export name (`#[export_name = "name"]`) attribute, if present.
+///
+/// Returns error if it is in incorrect format or the name is not a valid identifier.
+fn get_item_export_name(item: &TraitItemMethod) -> Result<Option<Ident>> {
+	item.attrs.iter().find(|attr| attr.path.is_ident("export_name"))
+		.map(|attr| {
+			let meta = attr.parse_meta()?;
+
+			match meta {
+				Meta::NameValue(MetaNameValue { lit: Lit::Str(name), .. }) => {
+					name.parse::<Ident>().map_err(|_| Error::new(
+						name.span(),
+						format!("`{}` is not a valid identifier", name.value()),
+					))
+				},
+				_ => Err(Error::new(
+					meta.span(),
+					"Unexpected `export_name` attribute. The supported format is \
+					`#[export_name = \"name\"]`",
+				)),
 			}
+		})
+		.transpose()
+}
+
+/// Returns the name that should be used for the exported host function of the given item.
+///
+/// This is the name given by `#[export_name = "name"]` or the name of the method.
+pub fn get_exported_name(item: &TraitItemMethod) -> Result<Ident> {
+	Ok(get_item_export_name(item)?.unwrap_or_else(|| item.sig.ident.clone()))
+}
+
+/// Prefixes the exported names of all methods of the given trait with `prefix`.
+///
+/// The prefixed name is set as `#[export_name = "prefix_name"]`, so the extern host function, the
+/// exchangeable host function and the registered host function all use it.
+pub fn add_export_name_prefix(mut trait_def: ItemTrait, prefix: &LitStr) -> Result<ItemTrait> {
+	for item in trait_def.items.iter_mut() {
+		if let TraitItem::Method(method) = item {
+			let export_name = format!("{}_{}", prefix.value(), get_exported_name(method)?);
+
+			method.attrs.retain(|a| !a.path.is_ident("export_name"));
+			method.attrs.push(parse_quote!( #[export_name = #export_name] ));
 		}
 	}
 
-	for function in functions.values() {
-		let mut next_expected = 1;
-		for (version, item) in function.versions.iter() {
-			if next_expected != *version {
+	Ok(trait_def)
+}
+
+/// Returns if the given attribute is `#[pass_by(strategy)]`.
+fn is_pass_by_attribute(attr: &Attribute, strategy: &str) -> bool {
+	if !attr.path.is_ident("pass_by") {
+		return false
+	}
+
+	match attr.parse_meta() {
+		Ok(Meta::List(list)) => list.nested.len() == 1 && match list.nested.first() {
+			Some(NestedMeta::Meta(Meta::Path(path))) => path.is_ident(strategy),
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+/// Returns if the given function argument is annotated with `#[pass_by(raw_ptr)]`.
+fn is_raw_ptr_argument(arg: &PatType) -> bool {
+	arg.attrs.iter().any(|a| is_pass_by_attribute(a, "raw_ptr"))
+}
+
+/// A `#[pass_by(..)]` strategy that passes an argument or the return value as another type.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PassByStrategy {
+	/// `#[pass_by(codec)]`, the value is passed SCALE encoded as `Codec<T>`.
+	Codec,
+	/// `#[pass_by(chunks)]`, a `Vec<Vec<u8>>` is passed as one buffer with a table of the offsets
+	/// of its chunks as `Chunks`.
+	Chunks,
+	/// `#[pass_by(width)]`, a `&[T]` or `Vec<T>` argument of `u16`, `u32` or `u64` is passed as
+	/// the little endian bytes of its elements as `Width<T>`.
+	Width,
+	/// `#[pass_by(bitmap)]`, a `Vec<bool>` is passed with one bit per `bool` as `Bitmap`.
+	Bitmap,
+}
+
+impl PassByStrategy {
+	/// All strategies with the name that is used in the attribute.
+	const ALL: &'static [(&'static str, PassByStrategy)] = &[
+		("codec", PassByStrategy::Codec),
+		("chunks", PassByStrategy::Chunks),
+		("width", PassByStrategy::Width),
+		("bitmap", PassByStrategy::Bitmap),
+	];
+
+	/// Returns the strategy of the given `#[pass_by(..)]` attribute.
+	///
+	/// `#[pass_by(raw_ptr)]` is not a strategy, as the argument is still passed as `&[u8]`.
+	fn from_attribute(attr: &Attribute) -> Option<Self> {
+		Self::ALL.iter()
+			.find(|(name, _)| is_pass_by_attribute(attr, name))
+			.map(|(_, strategy)| *strategy)
+	}
+
+	/// Returns the name of the strategy that is used in the attribute.
+	fn name(self) -> &'static str {
+		Self::ALL.iter()
+			.find(|(_, strategy)| *strategy == self)
+			.map(|(name, _)| *name)
+			.expect("All strategies are in `ALL`; qed")
+	}
+
+	/// Returns the type that passes a value of the given type with this strategy.
+	pub fn pass_by_type(self, ty: &Type) -> Type {
+		let crate_ = generate_crate_access();
+
+		match self {
+			PassByStrategy::Codec => parse_quote!( #crate_::pass_by::Codec<#ty> ),
+			PassByStrategy::Chunks => parse_quote!( #crate_::pass_by::Chunks ),
+			PassByStrategy::Width => {
+				let elem = get_width_element(ty).unwrap_or(ty);
+				parse_quote!( #crate_::pass_by::Width<#elem> )
+			},
+			PassByStrategy::Bitmap => parse_quote!( #crate_::pass_by::Bitmap ),
+		}
+	}
+
+	/// Checks that a value of the given type can be passed with this strategy.
+	fn check_type(self, ty: &Type) -> Result<()> {
+		let (is_supported, expected) = match self {
+			PassByStrategy::Codec => return Ok(()),
+			PassByStrategy::Chunks =>
+				(get_vec_element(ty).filter(|elem| is_byte_vec(elem)).is_some(), "`Vec<Vec<u8>>`"),
+			PassByStrategy::Width => {
+				let ty = match ty {
+					Type::Reference(type_ref) => &type_ref.elem,
+					ty => ty,
+				};
+
+				(get_width_element(ty).is_some(), "`&[T]` and `Vec<T>` of `u16`, `u32` or `u64`")
+			},
+			PassByStrategy::Bitmap => (
+				matches!(get_vec_element(ty), Some(Type::Path(p)) if p.path.is_ident("bool")),
+				"`Vec<bool>`",
+			),
+		};
+
+		if is_supported {
+			Ok(())
+		} else {
+			Err(Error::new(
+				ty.span(),
+				format!("`#[pass_by({})]` is only supported for {}", self.name(), expected),
+			))
+		}
+	}
+}
+
+/// Returns the element type `T` of the given `[T]` or `Vec<T>`, if `T` is `u16`, `u32` or `u64`.
+fn get_width_element(ty: &Type) -> Option<&Type> {
+	let elem = match ty {
+		Type::Slice(slice) => &slice.elem,
+		ty => get_vec_element(ty)?,
+	};
+
+	match elem {
+		Type::Path(path) if path.qself.is_none() &&
+			["u16", "u32", "u64"].iter().any(|int| path.path.is_ident(int)) => Some(elem),
+		_ => None,
+	}
+}
+
+/// Returns the strategy of the `#[pass_by(..)]` attribute of the given function argument.
+pub fn get_argument_pass_by(arg: &PatType) -> Option<PassByStrategy> {
+	arg.attrs.iter().find_map(PassByStrategy::from_attribute)
+}
+
+/// Returns the strategy of the `#[pass_by(..)]` attribute of the given method, which is used to
+/// pass its return value.
+pub fn get_return_value_pass_by(method: &TraitItemMethod) -> Option<PassByStrategy> {
+	method.attrs.iter().find_map(PassByStrategy::from_attribute)
+}
+
+/// Returns the error for a `#[pass_by(..)]` attribute with an unknown strategy.
+fn unknown_pass_by_strategy(attr: &Attribute, with_raw_ptr: bool) -> Error {
+	let expected = PassByStrategy::ALL.iter()
+		.map(|(name, _)| format!("`#[pass_by({})]`", name))
+		.chain(if with_raw_ptr { Some("`#[pass_by(raw_ptr)]`".into()) } else { None })
+		.collect::<Vec<_>>();
+
+	Error::new(attr.span(), format!("Expected one of {}", expected.join(", ")))
+}
+
+/// Checks the `#[pass_by(..)]` attributes on the methods and their arguments of the given trait.
+///
+/// `#[pass_by(raw_ptr)]` is only supported for `&[u8]` arguments of methods that are only
+/// callable from wasm. `#[pass_by(codec)]` is supported for all arguments that are not given as
+/// `&mut` and on methods with a return value. `#[pass_by(chunks)]` is only supported for
+/// `Vec<Vec<u8>>`, `#[pass_by(width)]` only for `&[T]` and `Vec<T>` arguments of `u16`, `u32` or
+/// `u64` and `#[pass_by(bitmap)]` only for `Vec<bool>`.
+pub fn check_pass_by_arguments(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<()> {
+	for method in get_trait_methods(trait_def) {
+		for attr in method.attrs.iter().filter(|a| a.path.is_ident("pass_by")) {
+			let strategy = PassByStrategy::from_attribute(attr)
+				.ok_or_else(|| unknown_pass_by_strategy(attr, false))?;
+
+			if strategy == PassByStrategy::Width {
 				return Err(Error::new(
-					item.span(),
-					format!("Unexpected version attribute: missing version '{}' for this function", next_expected),
-				));
+					attr.span(),
+					"`#[pass_by(width)]` is only supported for arguments",
+				))
+			}
+
+			let ty = match &method.sig.output {
+				ReturnType::Default => return Err(Error::new(
+					attr.span(),
+					format!(
+						"`#[pass_by({})]` on a method requires a return value",
+						strategy.name(),
+					),
+				)),
+				ReturnType::Type(_, ty) => ty,
+			};
+
+			if get_borrowed_return_value(&method.sig).is_some() {
+				return Err(Error::new(
+					attr.span(),
+					format!(
+						"`#[pass_by({})]` is not supported on methods that return a slice of an \
+						argument",
+						strategy.name(),
+					),
+				))
+			}
+
+			strategy.check_type(ty)?;
+		}
+	}
+
+	let args = get_trait_methods(trait_def)
+		.flat_map(|method| {
+			let is_wasm_only = is_wasm_only_method(method, is_wasm_only);
+			method.sig.inputs.iter().map(move |arg| (arg, is_wasm_only))
+		})
+		.filter_map(|(arg, is_wasm_only)| match arg {
+			FnArg::Typed(arg) if arg.attrs.iter().any(|a| a.path.is_ident("pass_by")) =>
+				Some((arg, is_wasm_only)),
+			_ => None,
+		});
+
+	for (arg, is_wasm_only) in args {
+		let mut attrs = arg.attrs.iter().filter(|a| a.path.is_ident("pass_by"));
+
+		if let Some(attr) = attrs.nth(1) {
+			return Err(Error::new(attr.span(), "Only one `#[pass_by(..)]` attribute is supported"))
+		}
+
+		for attr in arg.attrs.iter().filter(|a| a.path.is_ident("pass_by")) {
+			if PassByStrategy::from_attribute(attr).is_some() {
+				continue
+			}
+
+			if !is_pass_by_attribute(attr, "raw_ptr") {
+				return Err(unknown_pass_by_strategy(attr, true))
+			}
+
+			if !is_wasm_only {
+				return Err(Error::new(
+					attr.span(),
+					"`#[pass_by(raw_ptr)]` is only supported in `wasm_only` interfaces and \
+					`#[wasm_only]` methods",
+				))
+			}
+		}
+
+		if let Some(strategy) = get_argument_pass_by(arg) {
+			if let Type::Reference(type_ref) = &*arg.ty {
+				if type_ref.mutability.is_some() {
+					return Err(Error::new(
+						arg.ty.span(),
+						format!(
+							"`#[pass_by({})]` is not supported for `&mut` arguments",
+							strategy.name(),
+						),
+					))
+				}
+			}
+
+			strategy.check_type(&arg.ty)?;
+
+			continue
+		}
+
+		if !is_byte_slice(&arg.ty) {
+			return Err(Error::new(
+				arg.ty.span(),
+				"`#[pass_by(raw_ptr)]` is only supported for `&[u8]` arguments",
+			))
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns if the given type is `&[u8]`.
+fn is_byte_slice(ty: &Type) -> bool {
+	match ty {
+		Type::Reference(type_ref) if type_ref.mutability.is_none() => match &*type_ref.elem {
+			Type::Slice(slice) => match &*slice.elem {
+				Type::Path(path) => path.qself.is_none() && path.path.is_ident("u8"),
+				_ => false,
+			},
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+/// Returns if the given function argument is a `&[&[u8]]` without any argument attribute.
+///
+/// The slices are written into one buffer and are passed as `ByteSlices`.
+pub fn is_byte_slices_argument(arg: &PatType) -> bool {
+	!arg.attrs.iter().any(is_ffi_argument_attribute) && match &*arg.ty {
+		Type::Reference(type_ref) if type_ref.mutability.is_none() => match &*type_ref.elem {
+			Type::Slice(slice) => is_byte_slice(&slice.elem),
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+/// The return value of a method that returns a slice of one of its arguments.
+pub struct BorrowedReturnValue {
+	/// The name of the `&'a [u8]` argument that the return value borrows from.
+	pub source: Ident,
+	/// Is the return value an `Option<&'a [u8]>`?
+	pub optional: bool,
+}
+
+/// Returns if the given type is `&'a [u8]` with the given lifetime.
+fn is_byte_slice_with_lifetime(ty: &Type, lifetime: &Lifetime) -> bool {
+	match ty {
+		Type::Reference(type_ref) =>
+			type_ref.lifetime.as_ref() == Some(lifetime) && is_byte_slice(ty),
+		_ => false,
+	}
+}
+
+/// Returns the type `T`, if the given type is an `Option<T>`.
+fn get_option_inner_type(ty: &Type) -> Option<&Type> {
+	let segment = match ty {
+		Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+		_ => return None,
+	};
+
+	if segment.ident != "Option" {
+		return None
+	}
+
+	match &segment.arguments {
+		PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first() {
+			Some(GenericArgument::Type(ty)) => Some(ty),
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+/// Counts the uses of a lifetime.
+struct CountLifetime<'a>(&'a Lifetime, usize);
+
+impl<'a> Visit<'a> for CountLifetime<'_> {
+	fn visit_lifetime(&mut self, lifetime: &'a Lifetime) {
+		if lifetime == self.0 {
+			self.1 += 1;
+		}
+	}
+}
+
+/// Returns the argument that the return value of the given `Signature` borrows from.
+///
+/// This is supported for methods with exactly one lifetime parameter `'a`, which is only used by
+/// one `&'a [u8]` argument without any argument attribute and by the return value, which is either
+/// `&'a [u8]` or `Option<&'a [u8]>`.
+pub fn get_borrowed_return_value(sig: &Signature) -> Option<BorrowedReturnValue> {
+	if sig.generics.params.len() != 1 || sig.generics.where_clause.is_some() {
+		return None
+	}
+	let lifetime = match sig.generics.params.first() {
+		Some(GenericParam::Lifetime(def)) if def.bounds.is_empty() => &def.lifetime,
+		_ => return None,
+	};
+
+	let ty = match &sig.output {
+		ReturnType::Type(_, ty) => ty,
+		ReturnType::Default => return None,
+	};
+	let optional = match get_option_inner_type(ty) {
+		Some(inner) if is_byte_slice_with_lifetime(inner, lifetime) => true,
+		_ if is_byte_slice_with_lifetime(ty, lifetime) => false,
+		_ => return None,
+	};
+
+	let mut uses = CountLifetime(lifetime, 0);
+	get_function_arguments(sig).for_each(|arg| uses.visit_type(&arg.ty));
+	if uses.1 != 1 {
+		return None
+	}
+
+	get_function_arguments(sig)
+		.find(|arg| arg.attrs.is_empty() && is_byte_slice_with_lifetime(&arg.ty, lifetime))
+		.and_then(|arg| match *arg.pat {
+			Pat::Ident(pat) => Some(BorrowedReturnValue { source: pat.ident, optional }),
+			_ => None,
+		})
+}
+
+/// Generates the `for<'a>` of a function type with the lifetime parameters of the given
+/// `Signature`.
+pub fn generate_for_lifetimes(sig: &Signature) -> TokenStream {
+	if sig.generics.params.is_empty() {
+		quote!()
+	} else {
+		let lifetimes = sig.generics.lifetimes();
+		quote!( for<#( #lifetimes ),*> )
+	}
+}
+
+/// The modules of `std` with host resources, e.g. files or threads, that can not be passed across
+/// the FFI boundary.
+const HOST_RESOURCE_MODULES: &[&str] = &[
+	"fs", "net", "process", "thread", "sync", "rc", "cell", "io",
+];
+
+/// Finds the first type that can not be passed across the FFI boundary and the error for it.
+///
+/// References are checked when the trait is declared, so they are skipped.
+struct FindUnsupportedType<'a>(Option<(&'a Type, String)>);
+
+impl<'a> Visit<'a> for FindUnsupportedType<'a> {
+	fn visit_type(&mut self, ty: &'a Type) {
+		if self.0.is_some() {
+			return
+		}
+
+		let msg = match ty {
+			Type::Reference(_) => return,
+			Type::Ptr(_) => "Raw pointers can not be returned from a runtime interface function, \
+				as they would point into the memory of the host.".into(),
+			Type::ImplTrait(_) | Type::TraitObject(_) => "Trait objects and `impl Trait` can not \
+				be returned from a runtime interface function. Return a concrete type instead."
+				.into(),
+			Type::BareFn(_) => "Function pointers can not be returned from a runtime interface \
+				function, as they would point into the code of the host.".into(),
+			Type::Never(_) => "Runtime interface functions need to return to the runtime.".into(),
+			Type::Infer(_) => "The return type of a runtime interface function needs to be given."
+				.into(),
+			Type::Path(path) => match get_host_resource_name(path) {
+				Some(name) => format!(
+					"`{}` can not be returned from a runtime interface function, as it is only \
+					valid on the host. Return a handle to it instead, see `#[handle]`.",
+					name,
+				),
+				None => return visit::visit_type(self, ty),
+			},
+			_ => return visit::visit_type(self, ty),
+		};
+
+		self.0 = Some((ty, msg));
+	}
+}
+
+/// Returns the name of the given path, if it is a type in one of the [`HOST_RESOURCE_MODULES`]
+/// of `std`.
+fn get_host_resource_name(path: &TypePath) -> Option<String> {
+	let segments = path.path.segments.iter()
+		.map(|segment| segment.ident.to_string())
+		.collect::<Vec<_>>();
+
+	match segments.as_slice() {
+		[krate, module, ..] if path.qself.is_none() &&
+			["std", "core", "alloc"].contains(&krate.as_str()) &&
+			HOST_RESOURCE_MODULES.contains(&module.as_str()) => Some(segments.join("::")),
+		_ => None,
+	}
+}
+
+/// Checks that the return type of the given `Signature` can be passed across the FFI boundary.
+///
+/// The macro only sees the tokens of the return type, so it rejects the kinds of types that never
+/// implement `IntoFFIValue`, e.g. raw pointers or `std::fs::File`. Other types without a conversion
+/// are rejected by the compiler.
+pub fn check_return_type_is_ffi_passable(sig: &Signature) -> Result<()> {
+	let ty = match &sig.output {
+		ReturnType::Type(_, ty) => ty,
+		ReturnType::Default => return Ok(()),
+	};
+
+	let mut finder = FindUnsupportedType(None);
+	finder.visit_type(ty);
+
+	match finder.0 {
+		Some((unsupported, msg)) => Err(Error::new(unsupported.span(), msg)),
+		None => Ok(()),
+	}
+}
+
+/// Returns if the given function argument is annotated with `#[compressed]`.
+pub fn is_compressed_argument(arg: &PatType) -> bool {
+	arg.attrs.iter().any(|a| a.path.is_ident("compressed"))
+}
+
+/// Checks the `#[compressed]` attributes on the arguments of all methods of the given trait.
+///
+/// The attribute is only supported for `&[u8]` arguments without any other argument attribute.
+pub fn check_compressed_arguments(trait_def: &ItemTrait) -> Result<()> {
+	let args = get_trait_methods(trait_def)
+		.flat_map(|method| method.sig.inputs.iter())
+		.filter_map(|arg| match arg {
+			FnArg::Typed(arg) if is_compressed_argument(arg) => Some(arg),
+			_ => None,
+		});
+
+	for arg in args {
+		for attr in arg.attrs.iter().filter(|a| a.path.is_ident("compressed")) {
+			if !attr.tokens.is_empty() {
+				return Err(Error::new(attr.span(), "Expected `#[compressed]`"))
+			}
+		}
+
+		let other_attr = arg.attrs.iter()
+			.find(|a| is_ffi_argument_attribute(a) && !a.path.is_ident("compressed"));
+		if let Some(attr) = other_attr {
+			return Err(Error::new(
+				attr.span(),
+				"`#[compressed]` can not be combined with other argument attributes",
+			))
+		}
+
+		if !is_byte_slice(&arg.ty) {
+			return Err(
+				Error::new(arg.ty.span(), "`#[compressed]` is only supported for `&[u8]` arguments")
+			)
+		}
+	}
+
+	Ok(())
+}
+
+/// Return the size of the scratch region given by the `#[no_alloc(max = N)]` attribute, if
+/// present.
+///
+/// Returns error if it is in incorrect format. Correct format is only `#[no_alloc(max = N)]`.
+pub fn get_no_alloc_max(method: &TraitItemMethod) -> Result<Option<usize>> {
+	method.attrs.iter().find(|attr| attr.path.is_ident("no_alloc"))
+		.map(|attr| {
+			let meta = attr.parse_meta()?;
+
+			if let Meta::List(list) = &meta {
+				if let (1, Some(NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+					path,
+					lit: Lit::Int(max),
+					..
+				})))) = (list.nested.len(), list.nested.first()) {
+					if path.is_ident("max") {
+						return max.base10_parse()
+					}
+				}
+			}
+
+			Err(Error::new(
+				meta.span(),
+				"Unexpected `no_alloc` attribute. The supported format is `#[no_alloc(max = 256)]`",
+			))
+		})
+		.transpose()
+}
+
+/// Checks the `#[no_alloc(max = N)]` attributes of all methods of the given trait.
+///
+/// The `#[compressed]` arguments allocate the compressed data and can not be passed in the
+/// scratch region.
+pub fn check_no_alloc_methods(trait_def: &ItemTrait) -> Result<()> {
+	for method in get_trait_methods(trait_def) {
+		if get_no_alloc_max(method)?.is_none() {
+			continue
+		}
+
+		let compressed = method.sig.inputs.iter().find_map(|arg| match arg {
+			FnArg::Typed(arg) if is_compressed_argument(arg) => Some(arg),
+			_ => None,
+		});
+		if let Some(arg) = compressed {
+			return Err(Error::new(
+				arg.span(),
+				"`#[compressed]` arguments allocate the compressed data and can not be passed \
+				with `#[no_alloc]`",
+			))
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns the given method with all arguments that are annotated with `#[pass_by(raw_ptr)]`
+/// replaced by a `RawSlice` argument and all arguments that are annotated with `#[callback]`
+/// replaced by a `Callback` argument.
+///
+/// This is the signature of the method that is called on the host side. Arguments annotated with
+/// `#[out]` or `#[pass_by(codec)]` are taken as they are by the host side.
+pub fn replace_raw_ptr_arguments(method: &TraitItemMethod) -> TraitItemMethod {
+	let crate_ = generate_crate_access();
+	let mut method = method.clone();
+
+	method.sig.inputs.iter_mut().for_each(|arg| match arg {
+		FnArg::Typed(arg) if is_raw_ptr_argument(arg) => {
+			arg.attrs.retain(|a| !a.path.is_ident("pass_by"));
+			arg.ty = parse_quote!( #crate_::RawSlice );
+		},
+		FnArg::Typed(arg) if is_callback_argument(arg) => {
+			arg.attrs.retain(|a| !a.path.is_ident("callback"));
+			arg.ty = parse_quote!( #crate_::Callback );
+		},
+		FnArg::Typed(arg) => arg.attrs.retain(|a|
+			!is_ffi_argument_attribute(a) && !is_default_argument_attribute(a)
+		),
+		_ => {},
+	});
+
+	method
+}
+
+/// Returns if the given function argument is annotated with `#[callback]`.
+pub fn is_callback_argument(arg: &PatType) -> bool {
+	arg.attrs.iter().any(|a| a.path.is_ident("callback"))
+}
+
+/// Checks the `#[callback]` attributes on the arguments of all methods of the given trait.
+///
+/// The attribute is only supported for `u32` arguments of methods that are only callable from
+/// wasm.
+pub fn check_callback_arguments(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<()> {
+	let args = get_trait_methods(trait_def)
+		.flat_map(|method| {
+			let is_wasm_only = is_wasm_only_method(method, is_wasm_only);
+			method.sig.inputs.iter().map(move |arg| (arg, is_wasm_only))
+		})
+		.filter_map(|(arg, is_wasm_only)| match arg {
+			FnArg::Typed(arg) if is_callback_argument(arg) => Some((arg, is_wasm_only)),
+			_ => None,
+		});
+
+	for (arg, is_wasm_only) in args {
+		for attr in arg.attrs.iter().filter(|a| a.path.is_ident("callback")) {
+			if !attr.tokens.is_empty() {
+				return Err(Error::new(attr.span(), "Expected `#[callback]`"))
+			}
+
+			if !is_wasm_only {
+				return Err(Error::new(
+					attr.span(),
+					"`#[callback]` is only supported in `wasm_only` interfaces and `#[wasm_only]` \
+					methods",
+				))
+			}
+		}
+
+		let other_attr = arg.attrs.iter()
+			.find(|a| a.path.is_ident("pass_by") || a.path.is_ident("out"));
+		if let Some(attr) = other_attr {
+			return Err(Error::new(
+				attr.span(),
+				"`#[callback]` can not be combined with other argument attributes",
+			))
+		}
+
+		let is_u32 = match &*arg.ty {
+			Type::Path(path) => path.qself.is_none() && path.path.is_ident("u32"),
+			_ => false,
+		};
+
+		if !is_u32 {
+			return Err(
+				Error::new(arg.ty.span(), "`#[callback]` is only supported for `u32` arguments")
+			)
+		}
+	}
+
+	Ok(())
+}
+
+/// Checks the `#[native_only]` attributes of all methods of the given trait.
+///
+/// A `#[native_only]` method has no host function, so it can not be compiled for wasm. As the
+/// macro can not know for which side the interface is compiled, the method needs to be gated with
+/// `#[cfg(feature = "std")]`.
+pub fn check_native_only_methods(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<()> {
+	for method in get_trait_methods(trait_def).filter(|method| is_native_only_method(method)) {
+		let attr = method.attrs.iter()
+			.find(|a| a.path.is_ident("native_only"))
+			.expect("`is_native_only_method` checks that the attribute exists; qed");
+
+		if !attr.tokens.is_empty() {
+			return Err(Error::new(attr.span(), "Expected `#[native_only]`"))
+		}
+
+		if is_wasm_only_method(method, is_wasm_only) {
+			return Err(Error::new(
+				attr.span(),
+				"`#[native_only]` can not be combined with `wasm_only` interfaces and \
+				`#[wasm_only]` methods",
+			))
+		}
+
+		let is_std_only = get_cfg_attributes(method)
+			.map(|attr| attr.parse_meta())
+			.collect::<Result<Vec<_>>>()?
+			.iter()
+			.any(|meta| match meta {
+				Meta::List(list) => list.nested.iter().any(is_std_feature),
+				_ => false,
+			});
+
+		if !is_std_only {
+			return Err(Error::new(
+				method.sig.span(),
+				"`#[native_only]` methods can not be compiled for wasm and need to be gated with \
+				`#[cfg(feature = \"std\")]`",
+			))
+		}
+	}
+
+	Ok(())
+}
+
+/// Names of the items that are generated in the module of every interface.
+const RESERVED_FUNCTION_NAMES: &[&str] = &[
+	"host_functions", "install_host_simulator", "latest_version",
+];
+
+/// Checks that the given trait declares at least one method and only declares methods and
+/// constants.
+pub fn check_trait_items(trait_def: &ItemTrait) -> Result<()> {
+	let unsupported = trait_def.items.iter()
+		.find(|item| !matches!(item, TraitItem::Method(_) | TraitItem::Const(_)));
+	if let Some(item) = unsupported {
+		let kind = match item {
+			TraitItem::Type(_) => "Associated types are",
+			TraitItem::Macro(_) => "Macro invocations are",
+			_ => "This item is",
+		};
+
+		return Err(Error::new(
+			item.span(),
+			format!(
+				"{} not supported in a runtime interface, only methods and constants can be \
+				declared",
+				kind,
+			),
+		))
+	}
+
+	if get_trait_methods(trait_def).next().is_none() {
+		return Err(Error::new(
+			trait_def.ident.span(),
+			"A runtime interface needs to declare at least one method",
+		))
+	}
+
+	Ok(())
+}
+
+/// Checks that no method of the given trait has the name of an item the macro generates.
+///
+/// Besides the [`RESERVED_FUNCTION_NAMES`], these are the functions that are generated for the
+/// other methods, e.g. `name_dispatch`, `mock_name` with `mockable` or `name_async` with `async`.
+pub fn check_reserved_method_names(
+	trait_def: &ItemTrait,
+	is_mockable: bool,
+	is_async: bool,
+) -> Result<()> {
+	let mut generated_names = BTreeMap::new();
+	for method in get_trait_methods(trait_def) {
+		let name = &method.sig.ident;
+		let has_attribute = |attr: &str| method.attrs.iter().any(|a| a.path.is_ident(attr));
+
+		generated_names.insert(format!("{}_dispatch", name), name);
+		generated_names.insert(format!("{}_latest_version", name), name);
+		if is_mockable {
+			generated_names.insert(format!("mock_{}", name), name);
+		}
+		if is_async {
+			generated_names.insert(format!("{}_async", name), name);
+		}
+		if has_attribute("batch") {
+			generated_names.insert(format!("{}_batch", name), name);
+		}
+		if has_attribute("stream") {
+			for suffix in &["open", "next", "drop"] {
+				generated_names.insert(format!("{}_stream_{}", name, suffix), name);
+			}
+		}
+	}
+
+	for method in get_trait_methods(trait_def) {
+		let name = method.sig.ident.to_string();
+
+		if RESERVED_FUNCTION_NAMES.contains(&name.as_str()) {
+			return Err(Error::new(
+				method.sig.ident.span(),
+				format!(
+					"`{}` is reserved for the function generated by `#[runtime_interface]`",
+					name,
+				),
+			))
+		}
+
+		if let Some(generated_for) = generated_names.get(&name) {
+			return Err(Error::new(
+				method.sig.ident.span(),
+				format!("`{}` collides with the function generated for `{}`", name, generated_for),
+			))
+		}
+	}
+
+	Ok(())
+}
+
+/// Checks that all versions of every method of the given trait return the same type.
+///
+/// Only the arguments of a method can change between its versions then.
+pub fn check_return_types_of_versions(trait_def: &ItemTrait) -> Result<()> {
+	let mut first_versions = BTreeMap::<&Ident, &TraitItemMethod>::new();
+
+	for method in get_trait_methods(trait_def) {
+		let first_version = match first_versions.entry(&method.sig.ident) {
+			Entry::Vacant(entry) => {
+				entry.insert(method);
+				continue
+			},
+			Entry::Occupied(entry) => *entry.get(),
+		};
+
+		let output = &method.sig.output;
+		let first_output = &first_version.sig.output;
+		if quote!( #output ).to_string() != quote!( #first_output ).to_string() {
+			let mut err = Error::new(
+				method.sig.span(),
+				"All versions of a method need to return the same type with `strict_versioning`",
+			);
+			err.combine(Error::new(
+				first_version.sig.span(),
+				"Other version with a different return type declared here",
+			));
+
+			return Err(err)
+		}
+	}
+
+	Ok(())
+}
+
+/// Generates a warning for every method of the given trait that takes more than `limit` ffi
+/// values.
+///
+/// Every argument is passed as its own ffi value. The warning is the use of a `#[deprecated]`
+/// constant, as there is no other way to emit a warning from a proc macro on stable.
+pub fn generate_wide_signature_warnings(trait_def: &ItemTrait, limit: usize) -> TokenStream {
+	get_trait_methods(trait_def)
+		.filter(|method| !is_native_only_method(method))
+		.filter_map(|method| {
+			let count = get_function_arguments(&method.sig).count();
+			if count <= limit {
+				return None
+			}
+
+			let note = format!(
+				"`{}` takes {} ffi values, which is more than the `warn_wide` limit of {}. \
+				Consider passing the arguments as one struct.",
+				method.sig.ident,
+				count,
+				limit,
+			);
+			let cfg_attrs = get_cfg_attributes(method);
+			let warning = quote_spanned!(method.sig.ident.span() => WIDE_SIGNATURE);
+
+			Some(quote! {
+				#( #cfg_attrs )*
+				const _: () = {
+					#[deprecated(note = #note)]
+					const WIDE_SIGNATURE: () = ();
+					#warning
+				};
+			})
+		})
+		.collect()
+}
+
+/// Returns if the given `cfg` predicate requires the `std` feature.
+fn is_std_feature(predicate: &NestedMeta) -> bool {
+	match predicate {
+		NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit: Lit::Str(lit), .. })) =>
+			path.is_ident("feature") && lit.value() == "std",
+		NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("all") =>
+			list.nested.iter().any(is_std_feature),
+		_ => false,
+	}
+}
+
+/// Returns if the given function argument is annotated with `#[out]`.
+pub fn is_out_argument(arg: &PatType) -> bool {
+	arg.attrs.iter().any(|a| a.path.is_ident("out"))
+}
+
+/// Checks the `#[out]` attributes on the arguments of all methods of the given trait.
+///
+/// The attribute is only supported for `&mut Vec<u8>` arguments.
+pub fn check_out_arguments(trait_def: &ItemTrait) -> Result<()> {
+	let args = get_trait_methods(trait_def)
+		.flat_map(|method| method.sig.inputs.iter())
+		.filter_map(|arg| match arg {
+			FnArg::Typed(arg) if is_out_argument(arg) => Some(arg),
+			_ => None,
+		});
+
+	for arg in args {
+		for attr in arg.attrs.iter().filter(|a| a.path.is_ident("out")) {
+			if !attr.tokens.is_empty() {
+				return Err(Error::new(attr.span(), "Expected `#[out]`"))
+			}
+		}
+
+		let is_mut_byte_vec = match &*arg.ty {
+			Type::Reference(type_ref) if type_ref.mutability.is_some() =>
+				is_byte_vec(&type_ref.elem),
+			_ => false,
+		};
+
+		if !is_mut_byte_vec {
+			return Err(Error::new(
+				arg.ty.span(),
+				"`#[out]` is only supported for `&mut Vec<u8>` arguments",
+			))
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns if the given type is `Vec<u8>`.
+fn is_byte_vec(ty: &Type) -> bool {
+	match get_vec_element(ty) {
+		Some(Type::Path(elem)) => elem.qself.is_none() && elem.path.is_ident("u8"),
+		_ => false,
+	}
+}
+
+/// Returns the type `T`, if the given type is a `Vec<T>`.
+fn get_vec_element(ty: &Type) -> Option<&Type> {
+	let segment = match ty {
+		Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+		_ => None,
+	};
+
+	match segment {
+		Some(segment) if segment.ident == "Vec" => match &segment.arguments {
+			PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first() {
+				Some(GenericArgument::Type(elem)) => Some(elem),
+				_ => None,
+			},
+			_ => None,
+		},
+		_ => None,
+	}
+}
+
+/// Returns the given method with all arguments that are annotated with `#[out]` replaced by a
+/// `&mut OutVec` argument.
+///
+/// This is the signature of the method that is used for the ffi types of the arguments.
+pub fn replace_out_arguments(method: &TraitItemMethod) -> TraitItemMethod {
+	let crate_ = generate_crate_access();
+	let mut method = method.clone();
+
+	method.sig.inputs.iter_mut().for_each(|arg| match arg {
+		FnArg::Typed(arg) if is_out_argument(arg) => {
+			arg.attrs.retain(|a| !a.path.is_ident("out"));
+			arg.ty = parse_quote!( &mut #crate_::OutVec );
+		},
+		_ => {},
+	});
+
+	method
+}
+
+/// Returns the given method with all arguments that are annotated with `#[pass_by(..)]` replaced
+/// by the type of their strategy, e.g. wrapped in `Codec`, all arguments that are annotated with
+/// `#[compressed]` replaced by a `Compressed` argument and all `&[&[u8]]` arguments replaced by a
+/// `ByteSlices` argument. If the method is annotated with `#[pass_by(..)]`, the return value is
+/// replaced as well. A return value that borrows from an argument is replaced by `BorrowedSlice`.
+///
+/// This is the signature of the method that is used for the ffi types of the arguments and the
+/// return value.
+pub fn replace_codec_arguments(method: &TraitItemMethod) -> TraitItemMethod {
+	let crate_ = generate_crate_access();
+	let mut method = method.clone();
+
+	method.sig.inputs.iter_mut().for_each(|arg| match arg {
+		FnArg::Typed(arg) if get_argument_pass_by(arg).is_some() => {
+			let strategy = get_argument_pass_by(arg).expect("Checked by the match guard; qed");
+			arg.attrs.retain(|a| !a.path.is_ident("pass_by"));
+			arg.ty = match &*arg.ty {
+				Type::Reference(type_ref) => {
+					let pass_by_type = strategy.pass_by_type(&type_ref.elem);
+					parse_quote!( &#pass_by_type )
+				},
+				ty => Box::new(strategy.pass_by_type(ty)),
+			};
+		},
+		FnArg::Typed(arg) if is_compressed_argument(arg) => {
+			arg.attrs.retain(|a| !a.path.is_ident("compressed"));
+			arg.ty = parse_quote!( &#crate_::compression::Compressed );
+		},
+		FnArg::Typed(arg) if is_byte_slices_argument(arg) => {
+			arg.ty = parse_quote!( &#crate_::ByteSlices );
+		},
+		_ => {},
+	});
+
+	if get_borrowed_return_value(&method.sig).is_some() {
+		if let ReturnType::Type(_, ty) = &mut method.sig.output {
+			*ty = parse_quote!( #crate_::BorrowedSlice );
+		}
+	} else if let Some(strategy) = get_return_value_pass_by(&method) {
+		if let ReturnType::Type(_, ty) = &mut method.sig.output {
+			let pass_by_type = strategy.pass_by_type(ty);
+			**ty = pass_by_type;
+		}
+	}
+
+	method
+}
+
+/// Returns if the given argument is of a zero sized type, `PhantomData<T>` or `()`.
+///
+/// These arguments don't have a value that could be passed to the host, so they are not passed
+/// at all. The host creates them with `Default::default()`.
+pub fn is_zero_sized_argument(arg: &PatType) -> bool {
+	let ty = match &*arg.ty {
+		Type::Reference(type_ref) => &*type_ref.elem,
+		ty => ty,
+	};
+
+	!arg.attrs.iter().any(is_ffi_argument_attribute) && match ty {
+		Type::Tuple(tuple) => tuple.elems.is_empty(),
+		Type::Path(path) => path.qself.is_none() &&
+			matches!(path.path.segments.last(), Some(s) if s.ident == "PhantomData"),
+		_ => false,
+	}
+}
+
+/// Returns the given method without the arguments of a zero sized type.
+///
+/// This is the signature of the method that is used for the ffi values of the arguments.
+pub fn remove_zero_sized_arguments(method: &TraitItemMethod) -> TraitItemMethod {
+	let mut method = method.clone();
+
+	method.sig.inputs = method.sig.inputs.into_iter()
+		.filter(|arg| match arg {
+			FnArg::Typed(arg) => !is_zero_sized_argument(arg),
+			FnArg::Receiver(_) => true,
+		})
+		.collect();
+
+	method
+}
+
+/// Returns all runtime interface members, with versions.
+pub fn get_runtime_interface<'a>(trait_def: &'a ItemTrait)
+	-> Result<RuntimeInterface<'a>>
+{
+	let mut functions: BTreeMap<syn::Ident, RuntimeInterfaceFunction<'a>> = BTreeMap::new();
+	// The exported host functions, to detect different methods that are exported under the same
+	// name and version.
+	let mut host_functions: BTreeMap<(Ident, u32), &'a TraitItemMethod> = BTreeMap::new();
+
+	for item in get_trait_methods(trait_def) {
+		let name = item.sig.ident.clone();
+		let ItemVersion { version, register_only, alias, min } = get_item_version(item)?
+			.unwrap_or(ItemVersion { version: 1, register_only: false, alias: None, min: false });
+		// Only check that the span name and the export name are valid, they are used when
+		// generating the functions.
+		get_item_span_name(item)?;
+		get_item_export_name(item)?;
+
+		match functions.entry(name.clone()) {
+			Entry::Vacant(entry) => {
+				entry.insert(RuntimeInterfaceFunction::new(version, register_only, item));
+			},
+			Entry::Occupied(mut entry) => {
+				if let Some(existing_item) = entry.get().versions.get(&version) {
+					// Without a version attribute, the declaration is the default version.
+					let mut err = if item.attrs.iter().any(|a| a.path.is_ident("version")) {
+						Error::new(item.span(), "Duplicated version attribute")
+					} else {
+						Error::new(
+							item.sig.span(),
+							format!(
+								"`{}` is declared more than once, so this declaration needs a \
+								`#[version(X)]` attribute with a different version",
+								name,
+							),
+						)
+					};
+					err.combine(Error::new(
+						existing_item.span(),
+						"Previous version with the same number defined here",
+					));
+
+					return Err(err);
+				}
+
+				let interface_item = entry.get_mut();
+				if !register_only && interface_item.latest_version < Some(version) {
+					interface_item.latest_version = Some(version);
+				}
+				interface_item.versions.insert(version, item);
+			}
+		}
+
+		match host_functions.entry((get_exported_name(item)?, version)) {
+			Entry::Vacant(entry) => {
+				entry.insert(item);
+			},
+			Entry::Occupied(entry) => {
+				let host_function = create_host_function_ident(
+					&entry.key().0,
+					version,
+					&trait_def.ident,
+				);
+				let mut err = Error::new(
+					item.sig.span(),
+					format!(
+						"The host function `{}` of this method is already declared by `{}`",
+						host_function,
+						entry.get().sig.ident,
+					),
+				);
+				err.combine(Error::new(
+					entry.get().sig.span(),
+					"Previous declaration of the host function",
+				));
+
+				return Err(err);
+			},
+		}
+
+		if let Some(alias) = alias {
+			functions.get_mut(&name)
+				.expect("Function was inserted above; qed")
+				.aliases
+				.insert(version, alias);
+		}
+
+		if min {
+			let function = functions.get_mut(&name)
+				.expect("Function was inserted above; qed");
+
+			if register_only {
+				return Err(Error::new(
+					item.sig.span(),
+					"The minimum version can not be `register_only`, as runtimes would still call \
+					a version below the minimum",
+				));
+			}
+
+			if function.min_version.is_some() {
+				return Err(Error::new(
+					item.sig.span(),
+					"`min = true` is only allowed once per function",
+				));
+			}
+
+			function.min_version = Some(version);
+		}
+
+		for skipped in get_item_skipped_versions(item)? {
+			let function = functions.get_mut(&name)
+				.expect("Function was inserted above; qed");
+
+			if !function.skipped_versions.insert(skipped) {
+				return Err(Error::new(
+					item.sig.span(),
+					format!("Version '{}' is skipped more than once", skipped),
+				));
+			}
+		}
+
+		if let Some(deprecated_from) = get_item_deprecated_from(item)? {
+			let function = functions.get_mut(&name)
+				.expect("Function was inserted above; qed");
+
+			if function.deprecated_from.is_some() {
+				return Err(Error::new(
+					item.span(),
+					"`deprecated_from` attribute is only allowed once per function",
+				));
+			}
+
+			function.deprecated_from = Some(deprecated_from);
+		}
+	}
+
+	// A version without an implementation is implemented by the next version, if the next version
+	// only adds arguments with `#[default_when_missing(..)]`.
+	for function in functions.values_mut() {
+		for (version, item) in function.versions.iter() {
+			if !has_default_arguments(item) {
+				continue
+			}
+
+			let previous = function.versions.range(..version).next_back();
+			let (previous_version, previous_item) = match previous {
+				Some(previous) => previous,
+				None => return Err(Error::new(
+					item.sig.span(),
+					"`#[default_when_missing]` requires a previous version of this function",
+				)),
+			};
+
+			if previous_item.default.is_some() || function.aliases.contains_key(previous_version) {
+				return Err(Error::new(
+					previous_item.sig.span(),
+					"The version before a version with `#[default_when_missing]` arguments can \
+					not have an implementation or `alias`",
+				));
+			}
+
+			let mut required_args = 0;
+			for arg in item.sig.inputs.iter() {
+				match arg {
+					FnArg::Typed(arg) if get_default_argument(arg)?.is_none() => required_args += 1,
+					_ => {},
+				}
+			}
+
+			if required_args != get_function_arguments(&previous_item.sig).count() {
+				return Err(Error::new(
+					previous_item.sig.span(),
+					"This version needs to take the arguments of the next version without the \
+					arguments with `#[default_when_missing]`",
+				));
+			}
+
+			function.aliases.insert(*previous_version, *version);
+		}
+	}
+
+	for function in functions.values() {
+		if function.latest_version.is_none() {
+			let (_, item) = function.versions.iter().next()
+				.expect("Every function has at least one version; qed");
+
+			return Err(Error::new(
+				item.sig.span(),
+				"At least one version of this function must not be `register_only`",
+			));
+		}
+
+		// The bare function must never fall back to a version below the minimum.
+		if function.latest_version < function.min_version {
+			let (_, item) = function.latest_version();
+
+			return Err(Error::new(
+				item.sig.span(),
+				"The latest version that is not `register_only` is below the minimum version",
+			));
+		}
+
+		for skipped in function.skipped_versions.iter() {
+			let (latest_declared, item) = function.versions.iter().next_back()
+				.expect("Every function has at least one version; qed");
+			let first_declared = function.versions.keys().next()
+				.expect("Every function has at least one version; qed");
+
+			if let Some(item) = function.versions.get(skipped) {
+				return Err(Error::new(
+					item.sig.span(),
+					format!("Version '{}' is skipped, but declared for this function", skipped),
+				));
+			}
+
+			if skipped <= first_declared || skipped > latest_declared {
+				return Err(Error::new(
+					item.sig.span(),
+					format!(
+						"Skipped version '{}' must be between the first and the last version",
+						skipped,
+					),
+				));
+			}
+		}
+
+		// The old versions of `#[latest_only]` methods are removed, so they start at the latest.
+		let is_latest_only = function.versions.values().any(|item| is_latest_only_method(item));
+		let mut next_expected = match function.versions.keys().next() {
+			Some(first) if is_latest_only => *first,
+			_ => 1,
+		};
+		for (version, item) in function.versions.iter() {
+			while function.skipped_versions.contains(&next_expected) {
+				next_expected += 1;
+			}
+
+			if next_expected != *version {
+				return Err(Error::new(
+					item.sig.span(),
+					format!("Unexpected version attribute: missing version '{}' for this function", next_expected),
+				));
+			}
+			next_expected += 1;
+		}
+
+		for (version, alias) in function.aliases.iter() {
+			let item = function.versions[version];
+			// The aliased version is below this version, but may be skipped.
+			let aliased_item = match function.versions.get(alias) {
+				Some(aliased_item) => *aliased_item,
+				None => {
+					let attr = item.attrs.iter().find(|a| a.path.is_ident("version"))
+						.expect("Versions with `alias` have a version attribute; qed");
+
+					return Err(Error::new(
+						attr.span(),
+						format!("`alias = {}` refers to a skipped or undeclared version", alias),
+					))
+				},
+			};
+			if is_wasm_only_method(item, false) != is_wasm_only_method(aliased_item, false) {
+				return Err(Error::new(
+					item.sig.span(),
+					"`#[wasm_only]` needs to be given on both or none of a version with `alias` \
+					and the version it refers to",
+				));
 			}
-			next_expected += 1;
 		}
 	}
 
@@ -0,0 +1,736 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The options of [`#[runtime_interface]`](../attr.runtime_interface.html) and the attributes
+//! of the methods of a runtime interface.
+//!
+//! Multiple options can be combined, e.g. `#[runtime_interface(wasm_only, track_caller)]`.
+//!
+//! # Argument sizes
+//!
+//! By calling the macro like `#[runtime_interface(ffi_size)]`, every version additionally
+//! exposes a constant with the number of bytes of the ffi values of all its arguments on the
+//! native side, e.g. `CALL_VERSION_1_FFI_SIZE`. For `fn f(a: u32, b: u64, c: &[u8])` this is
+//! `4 + 8 + 8`, as `u32` is passed as `u32` and `u64` and `&[u8]` are passed as `u64`. Tools can
+//! compare this value between releases to detect changes of the host function signatures.
+//!
+//! # Signature hashes
+//!
+//! Every version exposes the hash of the argument types and the return type of its host function
+//! as constant, e.g. `CALL_VERSION_1_ABI_HASH: u64`. The hash is computed by the macro from the
+//! declared types, so it is the same for every compiler version and on the native and the wasm
+//! side. A runtime and a node can compare the hashes to detect an accidental change of a host
+//! function signature. Changing a type changes the hash, even if the new type uses the same ffi
+//! type.
+//!
+//! # Tracing
+//!
+//! Each function call on the host side is traced in a span that is named after the versioned
+//! function name, e.g. `call_version_2`. A method can be annotated with
+//! `#[span_name = "name"]` to use a custom span name instead. This can be used to group all
+//! versions of a function under the same span name. Every span records the called version of
+//! the function in the `version` field, so the calls can still be told apart by their version.
+//!
+//! Entering the spans can be disabled at compile time by disabling the default `interface-tracing`
+//! feature of this crate, which `sp-io` forwards as its own default `interface-tracing` feature.
+//! In this case the macro does not generate any tracing code. To only disable the spans of a
+//! single function, e.g. a function that is called very often, the method can be annotated with
+//! `#[no_tracing]`.
+//!
+//! By calling the macro like `#[runtime_interface(debug_args)]`, every call of a function on the
+//! host side additionally logs the arguments it is called with as a `debug` event with the target
+//! `runtime_interface`. This requires all arguments to implement `Debug` and is only compiled in
+//! with `debug_assertions`, so it is a no-op in release builds.
+//!
+//! # Renaming the exported host function
+//!
+//! The name of the exported host function is derived from the name of the trait method. To keep
+//! the exported name of a function when renaming the method, the method can be annotated with
+//! `#[export_name = "old_name"]`. The generated bare function keeps the new name, while the host
+//! function is exported as `ext_TRAIT_NAME_old_name_version_VERSION`. The attribute applies to the
+//! version of the function it is given on.
+//!
+//! Every exported host function name and version can only be declared once. Exporting two
+//! different methods under the same name and version is rejected at compile time.
+//!
+//! By calling the macro like `#[runtime_interface(prefix = "crypto")]`, the exported names of all
+//! methods are prefixed with `crypto_`. A method `blake2` is then exported as
+//! `ext_TRAIT_NAME_crypto_blake2_version_VERSION`, which namespaces the host functions of
+//! interfaces that are split across modules. The prefix also applies to names given with
+//! `#[export_name]`.
+//!
+//! # Attributes of the methods
+//!
+//! Attributes on a trait method that are not consumed by the macro, e.g. `#[must_use]` or the doc
+//! comments, are forwarded to the bare functions on the host and on the wasm side. So, callers
+//! that drop the result of a `#[must_use]` method get a warning in native and in wasm builds.
+//!
+//! The lints that are only triggered by the declared signature, `clippy::too_many_arguments` and
+//! `clippy::type_complexity`, are allowed on all generated functions. A method with many
+//! arguments doesn't need to be allowed by the users, as they can't reach the generated code.
+//!
+//! # Deprecating versions
+//!
+//! A `#[deprecated]` attribute on a trait method only applies to the given version of the
+//! function. To deprecate all versions of a function starting from a given version, the method
+//! can be annotated with `#[deprecated_from(X)]`. Any host functions of deprecated versions are
+//! marked as deprecated, so anyone wiring them up gets a compiler warning.
+//!
+//! # Registering a version without calling it
+//!
+//! A version can be declared as `#[version(X, register_only)]`. The host function of this version
+//! is registered on the host, but the bare function still calls the latest version that is not
+//! `register_only`. This way a node can support a new version of a function before any runtime
+//! starts to use it.
+//!
+//! # Declaring the minimum version
+//!
+//! A version can be declared as `#[version(X, min = true)]` to mark it as the minimum version
+//! runtimes need to call. The versions below are still registered on the host, so blocks of
+//! older runtimes can still be executed, but the bare function never calls them. The minimum
+//! version can not be `register_only` and only one version of a function can be the minimum.
+//!
+//! # Skipping a version
+//!
+//! A version number that is intentionally absent, e.g. because it was reserved for a reverted
+//! change or only released on another branch, can be skipped by annotating any version of the
+//! function with `#[skip_version(X)]`. The versions can then have a gap at `X`. A host function
+//! with the signature of the version before `X` is still registered for version `X`, to keep
+//! the numbering, but calling it returns an error and traps the wasm execution.
+//!
+//! ```
+//! # use sp_runtime_interface::runtime_interface;
+//! #[runtime_interface]
+//! trait Interface {
+//!     #[skip_version(2)]
+//!     fn call() -> u32 {
+//!         1
+//!     }
+//!
+//!     #[version(3)]
+//!     fn call() -> u32 {
+//!         3
+//!     }
+//! }
+//! ```
+//!
+//! # Reusing the implementation of a previous version
+//!
+//! A new version that only changes the signature of a function, but not its logic, can be
+//! declared as `#[version(X, alias = Y)]` without an implementation. The function of version `X`
+//! converts its arguments with `Into` into the argument types of version `Y`, calls version `Y`
+//! and converts the return value back with `Into`.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     fn sum(data: Vec<u8>) -> u32 {
+//!         data.iter().map(|b| *b as u32).sum()
+//!     }
+//!
+//!     // Calls `sum_version_1(data.into()).into()`.
+//!     #[version(2, alias = 1)]
+//!     fn sum(data: &[u8]) -> u32;
+//! }
+//! ```
+//!
+//! # Strict versioning
+//!
+//! By calling the macro like `#[runtime_interface(strict_versioning)]`, all versions of a method
+//! need to return the same type, only the arguments can change between the versions. A version
+//! that returns another type is rejected at compile time. Without `strict_versioning`, a version
+//! with `alias` can return another type that is converted with `Into`.
+//!
+//! # Keeping only the latest version
+//!
+//! When any version of a method is annotated with `#[latest_only]`, nothing is generated for the
+//! versions below the latest version that is not `register_only`. Their host functions are not
+//! registered either, so runtimes that still call them can not be executed anymore. Versions with
+//! `alias` can not be combined with `#[latest_only]`.
+//!
+//! ```
+//! # use sp_runtime_interface::runtime_interface;
+//! #[runtime_interface]
+//! trait Interface {
+//!     // Only `call_version_2` is generated.
+//!     #[latest_only]
+//!     fn call() -> u32 {
+//!         1
+//!     }
+//!
+//!     #[version(2)]
+//!     fn call() -> u32 {
+//!         2
+//!     }
+//! }
+//! ```
+//!
+//! By calling the macro like `#[runtime_interface(no_legacy)]`, every method is treated as
+//! `#[latest_only]`. This is meant for new interfaces that no runtime calls in an older version, as
+//! only the latest version of every method is generated.
+//!
+//! By calling the macro like `#[runtime_interface(keep_versions = 3)]`, only the latest 3 versions
+//! of every method are generated, e.g. to still execute the blocks that are in flight while the
+//! runtime is upgraded. With versions 1 to 5, only versions 3, 4 and 5 are generated. A kept
+//! version can not have an `alias` of a removed version. `keep_versions` can not be combined with
+//! `no_legacy`, which is the same as `keep_versions = 1`.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface(keep_versions = 2)]
+//! trait Interface {
+//!     // Not generated.
+//!     fn call() -> u32 {
+//!         1
+//!     }
+//!
+//!     #[version(2)]
+//!     fn call() -> u32 {
+//!         2
+//!     }
+//!
+//!     #[version(3)]
+//!     fn call() -> u32 {
+//!         3
+//!     }
+//! }
+//! ```
+//!
+//! # Default values for new arguments
+//!
+//! A new version that adds arguments can give them a default value with
+//! `#[default_when_missing(value)]`. The previous version is then declared without an
+//! implementation and calls the new version with the default values for the added arguments.
+//! The other arguments and the return value are converted with `Into`, as in an alias. Runtimes
+//! that were built with the previous version keep working without a copy of its implementation.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     // Calls `scale_version_2(value.into(), 2).into()`.
+//!     fn scale(value: u32) -> u32;
+//!
+//!     #[version(2)]
+//!     fn scale(value: u32, #[default_when_missing(2)] factor: u32) -> u32 {
+//!         value * factor
+//!     }
+//! }
+//! ```
+//!
+//! # Selecting the version at runtime
+//!
+//! The bare function always calls the latest version. For a method whose versions all take the
+//! same arguments and return the same type, the host side additionally gets a
+//! `{name}_dispatch(version, ..)` function. It calls the given version and returns `None` if the
+//! method has no such version. So, a host can pick the version from the metadata of the calling
+//! runtime.
+//!
+//! # Batch functions
+//!
+//! A method annotated with `#[batch]` gets an additional function `NAME_batch`. It takes a `Vec`
+//! of tuples with the arguments of the method and returns a `Vec` with all results. The batch
+//! function crosses the boundary between wasm and the host only once and calls the method for
+//! every element on the host. Reference arguments are passed as owned values in the batch, `&[T]`
+//! as `Vec<T>` and `&T` as `T`. Batching methods with `&mut` or `&str` arguments is not
+//! supported.
+//!
+//! Each version of the batch function calls the version of the method with the same number, so
+//! `#[batch]` is required on the first version of the method. Versions of the method without
+//! `#[batch]` are skipped by the batch function, see `#[skip_version]`.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     // Generates `fn is_empty_batch(items: Vec<(Vec<u8>,)>) -> Vec<bool>`.
+//!     #[batch]
+//!     fn is_empty(data: &[u8]) -> bool {
+//!         data.is_empty()
+//!     }
+//! }
+//! ```
+//!
+//! # Streams
+//!
+//! A method annotated with `#[stream]` returns `impl Iterator<Item = T>` and its bare function
+//! returns a [`stream::Stream<T>`](crate::stream::Stream). The iterator is kept on the host and
+//! every item is only created and passed to the runtime when the runtime pulls it. This is done
+//! with the additional host functions `NAME_stream_open`, `NAME_stream_next` and
+//! `NAME_stream_drop`, the iterator on the host is dropped together with the stream. The iterator
+//! needs to be `Send + 'static` and `T` needs to be passable as `Option<T>`. `#[wasm_only]` methods
+//! can not be `#[stream]`s and all versions of a method need to be `#[stream]` if one of them is.
+//!
+//! The iterators are kept in an extension of the externalities of the current execution. The
+//! handles of the streams start at `0` in every execution and are not valid in any other one.
+//! When the runtime traps or never drops a stream, its iterator is dropped together with the
+//! externalities. Therefore, `#[stream]` can not be used in a `no_externalities` interface.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     // Generates `fn numbers(count: u32) -> Stream<u32>`.
+//!     #[stream]
+//!     fn numbers(count: u32) -> impl Iterator<Item = u32> {
+//!         0..count
+//!     }
+//! }
+//! ```
+//!
+//! # Output arguments
+//!
+//! A `&mut Vec<u8>` argument can be annotated with `#[out]`. The host implementation gets the
+//! content of the vector and can change it arbitrarily, including its length. After the call the
+//! new content of the vector is written back into the wasm memory and the vector on the wasm side
+//! is replaced with it. This way a host function can fill a buffer that is provided by the caller.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     fn fill(#[out] buffer: &mut Vec<u8>) {
+//!         buffer.resize(32, 0);
+//!     }
+//! }
+//! ```
+//!
+//! # Returning a slice of an argument
+//!
+//! References can not be returned in general, but a function can return a slice of one of its
+//! arguments. It declares one lifetime `'a` that is only used by one `&'a [u8]` argument and by the
+//! return value, which is `&'a [u8]` or `Option<&'a [u8]>`. Natively, the returned slice is a
+//! borrow of the argument. The host function returns the offset and the length of the slice in the
+//! argument, see [`BorrowedSlice`](crate::BorrowedSlice), and the wasm side takes the slice from
+//! its own argument. So, nothing is copied back into the wasm memory. A returned slice that is not
+//! a slice of the argument returns an error, which traps the execution, except for an empty slice.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     fn find<'a>(haystack: &'a [u8], needle: &[u8]) -> Option<&'a [u8]> {
+//!         (0..haystack.len())
+//!             .find(|pos| haystack[*pos..].starts_with(needle))
+//!             .map(|pos| &haystack[pos..pos + needle.len()])
+//!     }
+//! }
+//! ```
+//!
+//! # Result return values
+//!
+//! A function can return a `Result<T, E>`. The result is passed between wasm and the host as any
+//! other codec encoded value, so `E` can be any codec type, e.g. an enum with domain errors. The
+//! encoding holds the variant and the value of this variant. Like any other function, a function
+//! that takes `&self`/`&mut self` and returns a `Result` panics when it is called outside of an
+//! `Externalities`-provided environment.
+//!
+//! By calling the macro like `#[runtime_interface(no_panic)]`, all functions that take
+//! `&self`/`&mut self` return an error instead: they need to return a `Result<T, E>` where `E`
+//! implements `From<`[`NoExternalities`](crate::NoExternalities)`>`, e.g. `()`, and return
+//! `Err(NoExternalities.into())` when called outside of an `Externalities`-provided environment. A
+//! function that doesn't return a `Result` fails to compile.
+//!
+//! By calling the macro like `#[runtime_interface(error_enum)]`, an enum `{Trait}Error` is
+//! generated in the interface module with one variant per function, named after the function in
+//! pascal case. It can be used to tag the errors of the functions, e.g.
+//! `validate(x).map_err(|()| InterfaceError::Validate)`, so callers of many functions can match on
+//! the function that failed. The enum converts into the name of the function.
+//!
+//! # Methods taking `self`
+//!
+//! A function that takes `&self`/`&mut self` can only be called with `Externalities` set. By
+//! calling the macro like `#[runtime_interface(deny_unused_self)]`, a `self` receiver that was
+//! added by mistake is caught: the implementation of such a function needs to use `self`. A
+//! function that intentionally requires `Externalities` without using them can be annotated with
+//! `#[requires_externalities]`.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface(deny_unused_self)]
+//! trait Interface {
+//!     fn has_value(&mut self, key: &[u8]) -> bool {
+//!         self.storage(key).is_some()
+//!     }
+//!
+//!     // Only callable while a block is executed.
+//!     #[requires_externalities]
+//!     fn checked_add(&self, a: u32, b: u32) -> Option<u32> {
+//!         a.checked_add(b)
+//!     }
+//! }
+//! ```
+//!
+//! # Supertraits
+//!
+//! The interface trait can not have any supertraits, e.g. `trait MyIface: BaseIface`. The macro
+//! only generates the bare functions and host functions for the methods of the trait itself, so
+//! methods of a supertrait would not be callable from the runtime. Methods that are shared by
+//! multiple interfaces need to be declared in each of these interfaces.
+//!
+//! # Native only methods
+//!
+//! The opposite of a `#[wasm_only]` method is a `#[native_only]` method. It has no host function
+//! and is only callable from the native side, so its arguments are not required to cross the wasm
+//! boundary. This allows `&dyn Trait` arguments, which are passed through to the implementation.
+//! The method can not be compiled for wasm and needs to be gated with `#[cfg(feature = "std")]`.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     #[cfg(feature = "std")]
+//!     #[native_only]
+//!     fn bytes(data: &dyn AsRef<[u8]>) -> Vec<u8> {
+//!         data.as_ref().to_vec()
+//!     }
+//! }
+//! ```
+//!
+//! # Reading slices without copying
+//!
+//! In a `wasm_only` interface or a `#[wasm_only]` method, a `&[u8]` argument can be annotated with
+//! `#[pass_by(raw_ptr)]`. The wasm side still takes a `&[u8]`, but the host implementation receives
+//! a [`RawSlice`](crate::RawSlice) with the pointer and the length of the slice instead of a copy
+//! of the data. The data can then be read on demand from the wasm memory using the
+//! `FunctionContext`, like `first_byte` in the example of [wasm only
+//! interfaces](../attr.runtime_interface.html#wasm-only-interfaces) does.
+//!
+//! # Compressed arguments
+//!
+//! A `&[u8]` argument can be annotated with `#[compressed]`. The wasm side compresses the data with
+//! [`compression::compress`](crate::compression::compress) before calling the host function, and
+//! the host decompresses it before calling the implementation, which still gets the `&[u8]`. This
+//! is meant for large inputs that compress well, as less data is copied out of the wasm memory, at
+//! the cost of compressing it. The compression is a fixed LZ77 variant implemented by this crate.
+//! Data that can not be decompressed returns an error, which traps the execution.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     fn count_zeros(#[compressed] data: &[u8]) -> u32 {
+//!         data.iter().filter(|b| **b == 0).count() as u32
+//!     }
+//! }
+//! ```
+//!
+//! # Passing arguments without allocating
+//!
+//! A method can be annotated with `#[no_alloc(max = N)]`. The SCALE encoded arguments of the method
+//! are then not stored in a `Vec<u8>` or the allocator set with
+//! [`wasm::set_argument_allocator`](crate::wasm::set_argument_allocator), but in an array of `N`
+//! bytes on the stack of the wasm side. The size of the encoded arguments is only known at runtime,
+//! so the call panics if they need more than `N` bytes. Arguments that are passed as pointer to
+//! their own memory, like `&[u8]`, don't take any space of the array. `#[compressed]` arguments are
+//! not supported, as the compressed data is allocated.
+//!
+//! ```
+//! # use sp_runtime_interface::pass_by::PassByCodec;
+//! # #[derive(PassByCodec, codec::Encode, codec::Decode)]
+//! # pub struct Point { x: u32, y: u32 }
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     #[no_alloc(max = 64)]
+//!     fn squared_distance(point: &Point) -> u64 {
+//!         point.x as u64 * point.x as u64 + point.y as u64 * point.y as u64
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! # Callback handles
+//!
+//! In a `wasm_only` interface or a `#[wasm_only]` method, a `u32` argument can be annotated with
+//! `#[callback]` to mark it as the handle of a callback in the runtime, e.g. the index of a
+//! function in its table. The handle is still passed as `u32`, but the host implementation
+//! receives a [`Callback`](crate::Callback), so it can not be mixed up with any other integer.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface(wasm_only)]
+//! trait Interface {
+//!     fn register(&mut self, #[callback] callback: u32) -> u32 {
+//!         callback.index()
+//!     }
+//! }
+//! ```
+//!
+//! # Resource handles
+//!
+//! A function that creates a resource on the host and returns its `u32` id can be annotated with
+//! `#[handle(Name)]`. It then returns a `NameHandle`, a newtype that is generated in the interface
+//! module and passed as `u32`. The functions that use the resource take the handle instead of a
+//! `u32`, so it can not be mixed up with any other integer. The implementation still returns the
+//! `u32` and reads it from the handle with `.0`. Without a name, `#[handle]` names the handle
+//! after the function, e.g. `CreateHasherHandle`.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     #[handle(Hasher)]
+//!     fn create_hasher() -> u32 {
+//!         1
+//!     }
+//!
+//!     fn hasher_finish(hasher: HasherHandle) -> u32 {
+//!         hasher.0
+//!     }
+//! }
+//! ```
+//!
+//! # Constants
+//!
+//! The interface trait can declare associated constants with a value. Each constant is exposed as
+//! `pub const` of the generated module on the native and the wasm side, so the runtime can read it
+//! without calling into the host.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface]
+//! trait Interface {
+//!     const PROTOCOL_VERSION: u32 = 2;
+//!
+//!     fn protocol_version() -> u32 {
+//!         PROTOCOL_VERSION
+//!     }
+//! }
+//! ```
+//!
+//! # Tracking the caller
+//!
+//! By calling the macro like `#[runtime_interface(track_caller)]`, all generated bare functions
+//! are annotated with `#[track_caller]`. So, when the function panics, e.g. because it was called
+//! outside of an `Externalities`-provided environment, the panic location points to the caller
+//! instead of the generated code.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface(track_caller)]
+//! trait Interface {
+//!     fn storage_root(&mut self) -> Vec<u8> {
+//!         self.storage_root()
+//!     }
+//! }
+//! ```
+//!
+//! # Inlining the wasm side
+//!
+//! On the wasm side a bare function only forwards its arguments to the host function. By calling
+//! the macro like `#[runtime_interface(inline_wasm)]`, the bare functions on the wasm side are
+//! annotated with `#[inline(always)]` to remove this extra call. The functions on the native side
+//! are not inlined.
+//!
+//! # Async implementations
+//!
+//! By calling the macro like `#[runtime_interface(async)]`, the implementations of the trait
+//! methods are the bodies of `async` blocks. So, they can `.await` futures, e.g. of an async
+//! networking backend. For every function the macro generates a `{name}_async` function on the
+//! native side, that returns the future of the latest version. A method taking `self` gets the
+//! [`Externalities`](crate::Externalities) as first argument of its `{name}_async` function:
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface(async)]
+//! trait Interface {
+//!     fn fetch(key: Vec<u8>) -> Vec<u8> {
+//!         futures::future::ready(key).await
+//!     }
+//!
+//!     fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+//!         futures::future::ready(self.storage(key)).await
+//!     }
+//! }
+//!
+//! async fn caller(ext: &mut dyn sp_runtime_interface::Externalities) {
+//!     assert_eq!(vec![1], interface::fetch_async(vec![1]).await);
+//!     let _ = interface::get_async(ext, b"key").await;
+//! }
+//! ```
+//!
+//! Host functions are executed synchronously, so a call from wasm and a call of the bare function
+//! drive the future to completion with `futures::executor::block_on`. The wasm side stays
+//! unchanged. As the `{name}_async` functions call the implementation directly, `async` can not be
+//! combined with `mockable`, `record`, `audit` or `wrap`. The native side needs the `async` feature
+//! of this crate.
+//!
+//! # Recording calls
+//!
+//! By calling the macro like `#[runtime_interface(record)]`, every call of a function version on
+//! the native side, including the calls from wasm, is reported to the
+//! [`record::Recorder`](crate::record::Recorder) that is set for the current thread with
+//! [`record::record_calls`](crate::record::record_calls). The recorder gets the name of the
+//! function version and the SCALE encoded arguments, so all arguments need to implement `Encode`.
+//! Calls of a version with `alias` are recorded as calls of the version it refers to. The native
+//! side needs the `record` feature of this crate.
+//!
+//! # Auditing calls
+//!
+//! By calling the macro like `#[runtime_interface(audit)]`, every call of a function version on the
+//! native side, including the calls from wasm, is reported to the
+//! [`audit::AuditSink`](crate::audit::AuditSink) that is installed with
+//! [`audit::set_audit_sink`](crate::audit::set_audit_sink), before the implementation is called.
+//! Unlike the recorder, the sink is installed for all threads. It gets the name of the function,
+//! the version and the SCALE encoded arguments. A `Mutex` of any `std::io::Write` is a sink that
+//! writes one line per call, e.g. to an audit log file. The native side needs the `audit` feature
+//! of this crate.
+//!
+//! # Wrapping calls
+//!
+//! By calling the macro like `#[runtime_interface(wrap = my::wrap_call)]`, the call of every
+//! function on the host side is wrapped by `my::wrap_call`, e.g. to collect metrics. It is called
+//! with the versioned name of the function and a closure that calls the implementation, and returns
+//! the result of the closure. So, it needs a signature like
+//! `fn wrap_call<R>(function: &str, call: impl FnOnce() -> R) -> R`.
+//!
+//! # Detecting re-entrant calls
+//!
+//! A host function that calls back into wasm can be called again before its first call returned.
+//! This can lead to confusing traces or to `Externalities` that are already borrowed. By calling
+//! the macro like `#[runtime_interface(reentrancy_guard)]`, every function version on the native
+//! side marks itself as entered with [`host::enter_function`](crate::host::enter_function) while it
+//! is executed, and a re-entrant call is logged as error.
+//!
+//! # Mocking
+//!
+//! By calling the macro like `#[runtime_interface(mockable)]`, a `mock_*` function is generated
+//! for every function of the interface. It takes a closure with the same signature as the bare
+//! function and makes the bare function call this closure on the native side for the current
+//! thread, instead of calling into the `Externalities`. The mocked implementation stays active
+//! until the guard returned by `mock_*` is dropped. This makes it possible to test code that
+//! calls into a runtime interface without setting up any `Externalities`.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface(mockable)]
+//! trait Interface {
+//!     fn random_seed(&mut self) -> [u8; 32] {
+//!         [0; 32]
+//!     }
+//! }
+//! ```
+//!
+//! In a test, `let _guard = interface::mock_random_seed(|| [1; 32]);` makes
+//! `interface::random_seed()` return `[1; 32]` until `_guard` goes out of scope.
+//!
+//! `mockable` can not be combined with `wasm_only`, as there is no native implementation to mock.
+//!
+//! # Pure interfaces
+//!
+//! By calling the macro like `#[runtime_interface(no_externalities)]`, the interface trait is
+//! implemented for `()` instead of `&mut dyn Externalities`, and the functions on the native side
+//! call it directly. This is meant for stateless functions, e.g. hashing, that can be called in any
+//! environment. The methods of such an interface can not take `self`, except `#[wasm_only]`
+//! methods, which get the `FunctionContext` as before. `no_externalities` can not be combined with
+//! `wasm_only`.
+//!
+//! ```
+//! #[sp_runtime_interface::runtime_interface(no_externalities)]
+//! trait Interface {
+//!     fn double(value: u32) -> u64 {
+//!         value as u64 * 2
+//!     }
+//! }
+//! ```
+//!
+//! # Conversion benches
+//!
+//! By calling the macro like `#[runtime_interface(benches)]`, a `benches` module is generated that
+//! contains the conversions the host functions do, without calling the implementation. For every
+//! function version `name_version_X`, `benches::name_version_X_arguments` converts the wasm values
+//! into the host values of the arguments and `benches::name_version_X_return_value` converts a
+//! return value into the wasm value. Both take the `FunctionContext` to read and write the wasm
+//! memory, so they can be called with dummy data in a benchmark, e.g. with criterion. The module is
+//! only compiled with the `bench` feature of the crate that declares the interface.
+//!
+//! # Generated roundtrip tests
+//!
+//! By calling the macro like `#[runtime_interface(gen_tests)]`, a `roundtrip_tests` module is
+//! generated that is only compiled for the tests of the crate declaring the interface. It
+//! contains a test `NAME_version_X_ARG_roundtrip` for every argument of every function version.
+//! The test converts random values of the argument type into their ffi value and back with the
+//! host side conversions, using [`quickcheck`](https://docs.rs/quickcheck), and checks that this
+//! gives the original value. Arguments that don't support this, because their owned type doesn't
+//! implement `quickcheck::Arbitrary`, `PartialEq` or `host::IntoFFIValue`, or because the host
+//! passes them differently than the runtime, e.g. `&[u32]` or `#[compressed]` arguments, are
+//! skipped. The generated tests need
+//! the `gen-tests` feature of this crate, e.g. enabled in the `dev-dependencies`.
+//!
+//! # Warning about wide signatures
+//!
+//! Every argument of a function is passed as its own ffi value. By calling the macro like
+//! `#[runtime_interface(warn_wide = 6)]`, every method that takes more than 6 arguments, not
+//! counting `self`, gets a `deprecated` warning when the interface is compiled. Passing the
+//! arguments as one struct, e.g. with `PassBy<PassBy = Codec>`, makes it cross the boundary as
+//! one value. `#[native_only]` methods are not checked, as they don't have a host function.
+//!
+//! # Host simulator
+//!
+//! By calling the macro like `#[runtime_interface(host_simulator)]`, an `install_host_simulator`
+//! function is generated on the native side. It registers all versions of all host functions of the
+//! interface in a [`simulator::HostSimulator`](crate::simulator::HostSimulator), which calls them
+//! by their exported names with the ffi values as arguments, like an executor does. This makes it
+//! possible to test the host side of an interface without a wasm executor.
+//!
+//! ```
+//! use sp_runtime_interface::{simulator::HostSimulator, sp_wasm_interface::Value};
+//!
+//! #[sp_runtime_interface::runtime_interface(no_externalities, host_simulator)]
+//! trait Interface {
+//!     fn add(a: u32, b: u32) -> u32 {
+//!         a + b
+//!     }
+//! }
+//!
+//! let mut simulator = HostSimulator::new();
+//! interface::install_host_simulator(&mut simulator);
+//!
+//! let result = simulator.call("ext_interface_add_version_1", &[Value::I32(1), Value::I32(2)]);
+//! assert_eq!(Ok(Some(Value::I32(3))), result);
+//! ```
+//!
+//! # Registration only builds
+//!
+//! By calling the macro like `#[runtime_interface(registration_only)]`, the host side
+//! implementation of the interface is not compiled when the `registration-only` feature of the
+//! crate that declares the interface is enabled. This leaves out the implementation of the trait
+//! and the bare functions on the host side. The `HostFunctions` are still compiled, with their
+//! names and signatures, so they can be registered in an executor. Calling one of them from wasm
+//! returns an error, which traps the execution. This is useful for a node that needs to register
+//! the host functions of an interface, without ever hosting the interface.
+//!
+//! # Compiling out host functions
+//!
+//! By calling the macro like `#[runtime_interface(host_function_features)]`, every host function is
+//! left out of `HostFunctions` when the feature `disable_hostfn_<interface>_<name>` of the crate
+//! that declares the interface is enabled, e.g. `disable_hostfn_my_interface_hash` for the method
+//! `hash` of `MyInterface`. The feature removes all versions of the host function. `<name>` is the
+//! exported name, so it includes the `prefix` and `#[export_name]` is respected. The crate should
+//! declare one of these features for every host function, so a node can disable the host functions
+//! it doesn't want to provide. As the features are off by default, a crate that doesn't declare
+//! them registers all host functions. A runtime that calls a host function that is compiled out
+//! fails to instantiate, as the host function is missing.
+//!
+//! # Custom function context
+//!
+//! By calling the macro like `#[runtime_interface(context = my::Context)]`, the `#[wasm_only]`
+//! methods are implemented for `&mut dyn my::Context` instead of `&mut dyn FunctionContext`.
+//! This is useful for an executor whose context trait mirrors `FunctionContext`. The generated
+//! host functions still get a `FunctionContext` and pass it as `&mut &mut dyn FunctionContext`,
+//! so the given trait needs to be implemented for `&mut dyn FunctionContext`.
+//!
+//! # Custom externalities accessor
+//!
+//! The native side of the methods taking `self` gets the `Externalities` from
+//! `sp_externalities::with_externalities`. A runtime that provides them in another way can call
+//! the macro like `#[runtime_interface(with_externalities = my::accessor)]` to make the generated
+//! code call the given function instead. It needs the same signature as `with_externalities`,
+//! `fn<R>(f: impl FnOnce(&mut dyn Externalities) -> R) -> Option<R>`, and returns `None` when no
+//! `Externalities` are available. `with_externalities` can not be combined with `wasm_only` or
+//! `no_externalities`.
+//!
+//! # Re-exported crate
+//!
+//! The generated code accesses this crate as `sp_runtime_interface`, so it needs to be a direct
+//! dependency. A crate that re-exports `sp-runtime-interface` can call the macro like
+//! `#[runtime_interface(crate = my_framework::runtime_interface)]` to make the generated code use
+//! the given path instead. The path needs to be resolvable from the module of the interface.
@@ -0,0 +1,107 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Auditing of the calls into runtime interfaces.
+//!
+//! Every call into a runtime interface declared with `#[runtime_interface(audit)]` is reported
+//! to the [`AuditSink`] that is installed with [`set_audit_sink`], before the implementation is
+//! called. Unlike the `Recorder` of the `record` module, the sink is global, so it gets the calls
+//! of all threads.
+
+use std::{io::Write, sync::{Mutex, RwLock}};
+
+lazy_static::lazy_static! {
+	static ref AUDIT_SINK: RwLock<Option<Box<dyn AuditSink>>> = RwLock::new(None);
+}
+
+/// Something that audits the calls into runtime interfaces.
+pub trait AuditSink: Send + Sync {
+	/// Audits a call of the given version of `function` with the SCALE encoded arguments.
+	///
+	/// The arguments are encoded one after another in the order they are declared.
+	fn audit(&self, function: &'static str, version: u32, args: &[u8]);
+}
+
+/// Writes one line per call, with the function, the version and the hex encoded arguments, e.g.
+/// `get 1 0x0c010203`.
+impl<W: Write + Send> AuditSink for Mutex<W> {
+	fn audit(&self, function: &'static str, version: u32, args: &[u8]) {
+		let args = args.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+		let mut writer = self.lock().unwrap_or_else(|e| e.into_inner());
+
+		if let Err(e) = writeln!(writer, "{} {} 0x{}", function, version, args) {
+			sp_tracing::tracing::error!(
+				target: "runtime_interface",
+				"Could not write the audit log: {}",
+				e,
+			);
+		}
+	}
+}
+
+/// Install the given sink for the calls of all threads and return the previously installed sink.
+///
+/// `None` removes the installed sink.
+pub fn set_audit_sink(sink: Option<Box<dyn AuditSink>>) -> Option<Box<dyn AuditSink>> {
+	let mut installed = AUDIT_SINK.write().unwrap_or_else(|e| e.into_inner());
+	std::mem::replace(&mut *installed, sink)
+}
+
+/// Audits a call of the given function version, if a sink is installed.
+///
+/// The arguments are only encoded when a sink is installed.
+#[doc(hidden)]
+pub fn audit_call(function: &'static str, version: u32, encode_args: impl FnOnce() -> Vec<u8>) {
+	let sink = AUDIT_SINK.read().unwrap_or_else(|e| e.into_inner());
+
+	if let Some(sink) = &*sink {
+		sink.audit(function, version, &encode_args());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+
+	#[derive(Clone, Default)]
+	struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+	impl Write for SharedLog {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().write(buf)
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn calls_are_written_in_order_to_the_installed_sink() {
+		audit_call("not_audited", 1, || unreachable!("No sink is installed"));
+
+		let log = SharedLog::default();
+		set_audit_sink(Some(Box::new(Mutex::new(log.clone()))));
+		audit_call("first", 1, || vec![1]);
+		audit_call("second", 2, || vec![2, 0xff]);
+		assert!(set_audit_sink(None).is_some());
+
+		audit_call("not_audited", 1, || unreachable!("The sink is removed"));
+		let log = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+		assert_eq!("first 1 0x01\nsecond 2 0x02ff\n", log);
+	}
+}
@@ -0,0 +1,194 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The compression of the `&[u8]` arguments annotated with `#[compressed]`.
+//!
+//! The wasm side compresses the data with [`compress`] and the host decompresses it with
+//! [`decompress`] before calling the implementation. The format is a simple LZ77 variant that
+//! works without `std` and without any allocation besides the output:
+//!
+//! - The length of the data as `u32` in little endian.
+//! - A sequence of tokens. A byte `n < 0x80` is followed by `n + 1` literal bytes. A byte
+//!   `0x80 | n` is followed by an `u16` offset in little endian and copies `n + 4` bytes, starting
+//!   `offset` bytes before the end of the data that is decompressed so far.
+
+use crate::util::usize_to_u32;
+
+use sp_std::{vec, vec::Vec};
+
+/// The shortest repetition that is stored as a copy.
+const MIN_COPY: usize = 4;
+/// The longest repetition that is stored as one copy.
+const MAX_COPY: usize = MIN_COPY + 0x7F;
+/// The most literal bytes that are stored after one token.
+const MAX_LITERALS: usize = 0x80;
+/// The number of bits of the hashes of the first bytes of a repetition.
+const HASH_BITS: u32 = 12;
+
+/// The type of the `#[compressed]` arguments in the signature of the host function.
+///
+/// It is passed as `u64`, the same way as `[u8]`, but points to the compressed data. The host
+/// implementation gets the decompressed data.
+pub struct Compressed;
+
+/// Compress the given `data`.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+	let mut res = Vec::with_capacity(4 + data.len() + data.len() / MAX_LITERALS + 1);
+	res.extend_from_slice(&usize_to_u32(data.len()).to_le_bytes());
+
+	// The last position of every hash, plus one to keep `0` for no position.
+	let mut positions = vec![0usize; 1 << HASH_BITS];
+	let mut literals_start = 0;
+	let mut pos = 0;
+	while pos + MIN_COPY <= data.len() {
+		let hash = hash(&data[pos..pos + MIN_COPY]);
+		let candidate = positions[hash].checked_sub(1);
+		positions[hash] = pos + 1;
+
+		let start = match candidate {
+			Some(start) if pos - start <= u16::max_value() as usize &&
+				data[start..start + MIN_COPY] == data[pos..pos + MIN_COPY] => start,
+			_ => {
+				pos += 1;
+				continue
+			},
+		};
+
+		let len = (MIN_COPY..MAX_COPY)
+			.find(|len| pos + len == data.len() || data[start + len] != data[pos + len])
+			.unwrap_or(MAX_COPY);
+
+		push_literals(&mut res, &data[literals_start..pos]);
+		res.push(0x80 | (len - MIN_COPY) as u8);
+		res.extend_from_slice(&((pos - start) as u16).to_le_bytes());
+
+		pos += len;
+		literals_start = pos;
+	}
+	push_literals(&mut res, &data[literals_start..]);
+
+	res
+}
+
+/// Decompress the given data that was compressed with [`compress`].
+///
+/// Returns `None` if the data is not valid.
+pub fn decompress(compressed: &[u8]) -> Option<Vec<u8>> {
+	if compressed.len() < 4 {
+		return None
+	}
+	let (len, mut tokens) = compressed.split_at(4);
+	let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+
+	// The length is not trusted, every token expands to at most `MAX_COPY` bytes.
+	let mut res = Vec::with_capacity(len.min(tokens.len().saturating_mul(MAX_COPY)));
+	while let Some((&token, rest)) = tokens.split_first() {
+		tokens = rest;
+
+		if token < 0x80 {
+			let count = token as usize + 1;
+			if tokens.len() < count {
+				return None
+			}
+
+			res.extend_from_slice(&tokens[..count]);
+			tokens = &tokens[count..];
+		} else {
+			if tokens.len() < 2 {
+				return None
+			}
+			let offset = u16::from_le_bytes([tokens[0], tokens[1]]) as usize;
+			tokens = &tokens[2..];
+
+			if offset == 0 || offset > res.len() {
+				return None
+			}
+
+			// The copy can overlap with the bytes it creates, so it is done byte by byte.
+			let start = res.len() - offset;
+			for i in 0..(token & 0x7F) as usize + MIN_COPY {
+				let byte = res[start + i];
+				res.push(byte);
+			}
+		}
+
+		if res.len() > len {
+			return None
+		}
+	}
+
+	if res.len() == len {
+		Some(res)
+	} else {
+		None
+	}
+}
+
+/// Returns the hash of the given first bytes of a repetition.
+fn hash(bytes: &[u8]) -> usize {
+	let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+	(value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Push the given literal bytes with their tokens.
+fn push_literals(res: &mut Vec<u8>, literals: &[u8]) {
+	for chunk in literals.chunks(MAX_LITERALS) {
+		res.push((chunk.len() - 1) as u8);
+		res.extend_from_slice(chunk);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn large_buffer_survives_the_roundtrip() {
+		// Repeating runs that compress well, mixed with noise that doesn't.
+		let data = (0..1024 * 1024u32)
+			.map(|i| if i % 1000 < 500 {
+				(i % 7) as u8
+			} else {
+				(i.wrapping_mul(2_654_435_761) >> 24) as u8
+			})
+			.collect::<Vec<_>>();
+
+		let compressed = compress(&data);
+
+		assert!(compressed.len() < data.len() * 3 / 4);
+		assert_eq!(Some(data), decompress(&compressed));
+	}
+
+	#[test]
+	fn short_buffers_survive_the_roundtrip() {
+		for data in &[&[][..], &[1], &[1, 2, 3, 4], &[5; 200], b"abcabcabcabcabc"] {
+			assert_eq!(Some(data.to_vec()), decompress(&compress(data)));
+		}
+	}
+
+	#[test]
+	fn invalid_data_is_rejected() {
+		let compressed = compress(&[3; 100]);
+
+		assert_eq!(None, decompress(&compressed[..compressed.len() - 1]));
+		assert_eq!(None, decompress(&[]));
+		// A copy before the start of the data.
+		assert_eq!(None, decompress(&[4, 0, 0, 0, 0x80, 1, 0]));
+		// More data than the length says.
+		assert_eq!(None, decompress(&[1, 0, 0, 0, 1, 1, 2]));
+	}
+}
@@ -20,6 +20,8 @@ use crate::RIType;
 
 use sp_wasm_interface::{FunctionContext, Result};
 
+use std::{cell::RefCell, rc::Rc, thread::LocalKey};
+
 /// Something that can be converted into a ffi value.
 pub trait IntoFFIValue: RIType {
 	/// Convert `self` into a ffi value.
@@ -34,7 +36,8 @@ pub trait IntoFFIValue: RIType {
 ///
 /// This should only be used for types which have a fixed size, like slices. Other types like a vec
 /// do not work with this interface, as we can not call into wasm to reallocate memory. So, this
-/// trait should be implemented carefully.
+/// trait should be implemented carefully. The only exception is [`OutVec`](crate::OutVec), which
+/// writes the vec into newly allocated memory and lets the wasm side replace its vec.
 pub trait IntoPreallocatedFFIValue: RIType {
 	/// As `Self` can be an unsized type, it needs to be represented by a sized type at the host.
 	/// This `SelfInstance` is the sized type.
@@ -63,3 +66,157 @@ pub trait FromFFIValue: RIType {
 		arg: Self::FFIType,
 	) -> Result<Self::SelfInstance>;
 }
+
+/// The mocked implementation of a bare function.
+///
+/// Used by `#[runtime_interface(mockable)]` interfaces to store the mocked implementation of a
+/// function per thread.
+pub struct MockedImplementation<F: ?Sized>(RefCell<Option<Rc<F>>>);
+
+impl<F: ?Sized> MockedImplementation<F> {
+	/// Create a new instance without any mocked implementation.
+	pub fn new() -> Self {
+		Self(RefCell::new(None))
+	}
+
+	/// Returns the mocked implementation, if there is any.
+	pub fn get(&self) -> Option<Rc<F>> {
+		self.0.borrow().clone()
+	}
+}
+
+impl<F: ?Sized> Default for MockedImplementation<F> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Set `mock` as the implementation of the function stored in `key` for the current thread.
+///
+/// # Returns
+///
+/// Returns [`RestoreMock`] that restores the previous implementation on drop.
+pub fn set_mock<F: ?Sized>(
+	key: &'static LocalKey<MockedImplementation<F>>,
+	mock: Rc<F>,
+) -> RestoreMock<F> {
+	let previous = key.with(|m| m.0.replace(Some(mock)));
+	RestoreMock(key, previous)
+}
+
+/// Restores the previous implementation of a mocked function on drop.
+pub struct RestoreMock<F: ?Sized + 'static>(
+	&'static LocalKey<MockedImplementation<F>>,
+	Option<Rc<F>>,
+);
+
+impl<F: ?Sized> Drop for RestoreMock<F> {
+	fn drop(&mut self) {
+		let previous = self.1.take();
+		// The thread local is maybe already destroyed, when the thread is shutting down.
+		let _ = self.0.try_with(|m| *m.0.borrow_mut() = previous);
+	}
+}
+
+thread_local! {
+	/// The names of the function versions that are currently executed on this thread.
+	static ENTERED_FUNCTIONS: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+}
+
+/// Marks the given function version as entered on the current thread, until the returned
+/// [`EnteredFunction`] is dropped.
+///
+/// Used by `#[runtime_interface(reentrancy_guard)]` interfaces. If the function is already
+/// entered, it was called again before the first call returned, e.g. by a host function that calls
+/// back into wasm. This is logged as error together with all functions that are currently entered.
+pub fn enter_function(function: &'static str) -> EnteredFunction {
+	let is_reentrant = ENTERED_FUNCTIONS.with(|entered| {
+		let mut entered = entered.borrow_mut();
+		let is_reentrant = entered.contains(&function);
+
+		if is_reentrant {
+			sp_tracing::tracing::error!(
+				target: "runtime_interface",
+				"`{}` is called re-entrant, the entered functions are: {:?}",
+				function,
+				entered,
+			);
+		}
+
+		entered.push(function);
+		is_reentrant
+	});
+
+	EnteredFunction { is_reentrant }
+}
+
+/// Marks a function version as left on drop.
+pub struct EnteredFunction {
+	is_reentrant: bool,
+}
+
+impl EnteredFunction {
+	/// Returns if the function was already entered, when it was entered again.
+	pub fn is_reentrant(&self) -> bool {
+		self.is_reentrant
+	}
+}
+
+impl Drop for EnteredFunction {
+	fn drop(&mut self) {
+		// The thread local is maybe already destroyed, when the thread is shutting down.
+		let _ = ENTERED_FUNCTIONS.try_with(|entered| entered.borrow_mut().pop());
+	}
+}
+
+/// Asserts at compile time that `T` can be used as argument type on the host side.
+///
+/// Called by `#[runtime_interface]` for every argument type, so a missing [`FromFFIValue`]
+/// implementation is reported at the argument type.
+#[doc(hidden)]
+pub fn assert_argument_type<T: FromFFIValue + ?Sized>() {}
+
+/// Asserts at compile time that `T` can be used as return type on the host side.
+///
+/// Called by `#[runtime_interface]` for every return type, so a missing [`IntoFFIValue`]
+/// implementation is reported at the return type.
+#[doc(hidden)]
+pub fn assert_return_type<T: IntoFFIValue>() {}
+
+/// Asserts at compile time that the wasm side type `W` and the host side type `H` are passed
+/// using the same ffi type.
+///
+/// The host function implementations generated by `#[runtime_interface]` call this for every
+/// argument and the return value. So, the host function can not expect a different ffi type than
+/// the one the wasm side passes.
+///
+/// ```compile_fail,E0271
+/// sp_runtime_interface::host::assert_same_ffi_type::<u32, u64>();
+/// ```
+#[doc(hidden)]
+pub fn assert_same_ffi_type<W, H>()
+where
+	W: RIType + ?Sized,
+	H: RIType<FFIType = <W as RIType>::FFIType> + ?Sized,
+{}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reentrant_calls_are_detected() {
+		let first = enter_function("first_version_1");
+		assert!(!first.is_reentrant());
+
+		{
+			let second = enter_function("second_version_1");
+			assert!(!second.is_reentrant());
+			assert!(enter_function("first_version_1").is_reentrant());
+		}
+
+		assert!(!enter_function("second_version_1").is_reentrant());
+		drop(first);
+		assert!(!enter_function("first_version_1").is_reentrant());
+	}
+}
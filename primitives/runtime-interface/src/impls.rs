@@ -17,8 +17,10 @@
 //! Provides implementations for the runtime interface traits.
 
 use crate::{
-	RIType, Pointer, pass_by::{PassBy, Codec, Inner, PassByInner, Enum},
-	util::{unpack_ptr_and_len, pack_ptr_and_len},
+	RIType, Pointer, RawSlice, OutVec, Callback, ByteSlices, BorrowedSlice,
+	compression::{self, Compressed},
+	pass_by::{PassBy, Codec, Inner, PassByInner, Enum},
+	util::{unpack_ptr_and_len, pack_ptr_and_len, usize_to_u32},
 };
 #[cfg(feature = "std")]
 use crate::host::*;
@@ -31,12 +33,14 @@ use static_assertions::assert_eq_size;
 #[cfg(feature = "std")]
 use sp_wasm_interface::{FunctionContext, Result};
 
-use codec::{Encode, Decode};
+use codec::{Encode, Decode, Output};
 
-use sp_std::{any::TypeId, mem, vec::Vec};
+use sp_std::{any::TypeId, mem, vec::Vec, borrow::Cow, convert::TryFrom, ops::Range};
 
-#[cfg(feature = "std")]
-use sp_std::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+use core::time::Duration;
 
 // Make sure that our assumptions for storing a pointer + its size in `u64` is valid.
 #[cfg(all(not(feature = "std"), not(feature = "disable_target_static_assertions")))]
@@ -149,6 +153,9 @@ impl IntoFFIValue for bool {
 ///
 /// If `T == u8` the length and the pointer are taken directly from `Self`.
 /// Otherwise `Self` is encoded and the length and the pointer are taken from the encoded vector.
+///
+/// As return value, the host allocates the vector in the wasm memory and the runtime takes
+/// ownership of this allocation, so the data is not copied again and freed when it is dropped.
 impl<T> RIType for Vec<T> {
 	type FFIType = u64;
 }
@@ -162,10 +169,10 @@ impl<T: 'static + Encode> IntoFFIValue for Vec<T> {
 			Cow::Owned(self.encode())
 		};
 
-		let ptr = context.allocate_memory(vec.as_ref().len() as u32)?;
+		let ptr = context.allocate_memory(usize_to_u32(vec.as_ref().len()))?;
 		context.write_memory(ptr, &vec)?;
 
-		Ok(pack_ptr_and_len(ptr.into(), vec.len() as u32))
+		Ok(pack_ptr_and_len(ptr.into(), usize_to_u32(vec.len())))
 	}
 }
 
@@ -180,9 +187,9 @@ impl<T: 'static + Decode> FromFFIValue for Vec<T> {
 
 #[cfg(not(feature = "std"))]
 impl<T: 'static + Encode> IntoFFIValue for Vec<T> {
-	type Owned = Vec<u8>;
+	type Owned = ArgumentBuffer;
 
-	fn into_ffi_value(&self) -> WrappedFFIValue<u64, Vec<u8>> {
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64, ArgumentBuffer> {
 		self[..].into_ffi_value()
 	}
 }
@@ -207,6 +214,33 @@ impl<T: 'static + Decode> FromFFIValue for Vec<T> {
 	}
 }
 
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+///
+/// The bytes are copied into the wasm memory in both cases, so the host doesn't need to allocate
+/// a `Vec<u8>` to return a borrowed slice. The wasm side always gets the owned bytes.
+impl RIType for Cow<'static, [u8]> {
+	type FFIType = u64;
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for Cow<'static, [u8]> {
+	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u64> {
+		let ptr = context.allocate_memory(usize_to_u32(self.len()))?;
+		context.write_memory(ptr, &self)?;
+
+		Ok(pack_ptr_and_len(ptr.into(), usize_to_u32(self.len())))
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for Cow<'static, [u8]> {
+	fn from_ffi_value(arg: u64) -> Self {
+		Cow::Owned(<Vec<u8> as FromFFIValue>::from_ffi_value(arg))
+	}
+}
+
 /// The type is passed as `u64`.
 ///
 /// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
@@ -261,20 +295,90 @@ impl IntoPreallocatedFFIValue for [u8] {
 
 #[cfg(not(feature = "std"))]
 impl<T: 'static + Encode> IntoFFIValue for [T] {
-	type Owned = Vec<u8>;
+	type Owned = ArgumentBuffer;
 
-	fn into_ffi_value(&self) -> WrappedFFIValue<u64, Vec<u8>> {
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64, ArgumentBuffer> {
 		if TypeId::of::<T>() == TypeId::of::<u8>() {
 			let slice = unsafe { mem::transmute::<&[T], &[u8]>(self) };
-			pack_ptr_and_len(slice.as_ptr() as u32, slice.len() as u32).into()
+			pack_ptr_and_len(slice.as_ptr() as u32, usize_to_u32(slice.len())).into()
 		} else {
-			let data = self.encode();
-			let ffi_value = pack_ptr_and_len(data.as_ptr() as u32, data.len() as u32);
-			(ffi_value, data).into()
+			let data = ArgumentBuffer::encode(self);
+			(data.ffi_value(), data).into()
 		}
 	}
 }
 
+/// Chunks of bytes written into one buffer with a table of their offsets.
+///
+/// The buffer starts with the number of chunks and the end offset of every chunk in the data, all
+/// as `u32` in little endian. The data of all chunks follows, without any separator. Used by
+/// [`pass_by::Chunks`](crate::pass_by::Chunks) and [`ByteSlices`].
+pub(crate) struct OffsetTable<'a, T>(pub(crate) &'a [T]);
+
+impl OffsetTable<'_, Vec<u8>> {
+	/// Reads the chunks from the given buffer.
+	///
+	/// Returns `None` if the buffer is not valid.
+	pub(crate) fn decode(buffer: &[u8]) -> Option<Vec<Vec<u8>>> {
+		let ranges = chunk_ranges(buffer)?;
+
+		Some(ranges.into_iter().map(|range| buffer[range].to_vec()).collect())
+	}
+}
+
+impl<T: AsRef<[u8]>> Encode for OffsetTable<'_, T> {
+	fn size_hint(&self) -> usize {
+		4 * (self.0.len() + 1) + self.0.iter().map(|chunk| chunk.as_ref().len()).sum::<usize>()
+	}
+
+	fn encode_to<O: Output>(&self, dest: &mut O) {
+		dest.write(&usize_to_u32(self.0.len()).to_le_bytes());
+
+		let mut end = 0;
+		for chunk in self.0 {
+			end += chunk.as_ref().len();
+			dest.write(&usize_to_u32(end).to_le_bytes());
+		}
+
+		for chunk in self.0 {
+			dest.write(chunk.as_ref());
+		}
+	}
+}
+
+/// Returns the ranges of the chunks in the given buffer of an [`OffsetTable`].
+///
+/// Returns `None` if the buffer is not valid.
+fn chunk_ranges(buffer: &[u8]) -> Option<Vec<Range<usize>>> {
+	let read_u32 = |at: usize| buffer.get(at..at + 4)
+		.map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+
+	let count = read_u32(0)?;
+	// The offset table is in the buffer, so `count` is not larger than the buffer.
+	let data_start = count.checked_add(1)?.checked_mul(4)?;
+	if data_start > buffer.len() {
+		return None
+	}
+
+	let mut ranges = Vec::with_capacity(count);
+	let mut start = data_start;
+	for i in 1..=count {
+		let end = data_start.checked_add(read_u32(4 * i)?)?;
+		if end < start || end > buffer.len() {
+			return None
+		}
+
+		ranges.push(start..end);
+		start = end;
+	}
+
+	if start == buffer.len() {
+		Some(ranges)
+	} else {
+		None
+	}
+}
+
 /// Implement the traits for the `[u8; N]` arrays, where `N` is the input to this macro.
 macro_rules! impl_traits_for_arrays {
 	(
@@ -364,6 +468,32 @@ impl<T: codec::Codec> PassBy for Option<T> {
 	type PassBy = Codec<Self>;
 }
 
+impl<K: codec::Codec + Ord, V: codec::Codec> PassBy
+	for sp_std::collections::btree_map::BTreeMap<K, V>
+{
+	type PassBy = Codec<Self>;
+}
+
+/// Implement `PassBy` with `Codec` for the tuple of the given element types and all shorter tuples.
+///
+/// Each element is encoded using its own `Codec` implementation, so tuples can be nested.
+macro_rules! for_tuples {
+	( $first:ident, $( $rest:ident, )* ) => {
+		impl<$first: codec::Codec, $( $rest: codec::Codec, )*> PassBy for ($first, $( $rest, )*) {
+			type PassBy = Codec<Self>;
+		}
+
+		for_tuples!( $( $rest, )* );
+	};
+	() => {
+		impl PassBy for () {
+			type PassBy = Codec<Self>;
+		}
+	};
+}
+
+for_tuples!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R,);
+
 /// Implement `PassBy` with `Inner` for the given fixed sized hash types.
 macro_rules! for_primitive_types {
 	{ $( $hash:ident $n:expr ),* $(,)? } => {
@@ -426,7 +556,35 @@ impl IntoFFIValue for str {
 
 	fn into_ffi_value(&self) -> WrappedFFIValue<u64, ()> {
 		let bytes = self.as_bytes();
-		pack_ptr_and_len(bytes.as_ptr() as u32, bytes.len() as u32).into()
+		pack_ptr_and_len(bytes.as_ptr() as u32, usize_to_u32(bytes.len())).into()
+	}
+}
+
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+///
+/// The length and the pointer of the UTF-8 bytes are taken directly from `Self`, the same way as
+/// for `str`.
+impl RIType for String {
+	type FFIType = u64;
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for String {
+	type SelfInstance = String;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<String> {
+		<str as FromFFIValue>::from_ffi_value(context, arg)
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for String {
+	type Owned = ();
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u64, ()> {
+		self.as_str().into_ffi_value()
 	}
 }
 
@@ -473,6 +631,185 @@ impl<T: sp_wasm_interface::PointerType> IntoFFIValue for Pointer<T> {
 	}
 }
 
+/// The type is passed as `u64`, the same way as `[u8]`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+impl RIType for RawSlice {
+	type FFIType = u64;
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for RawSlice {
+	type SelfInstance = Self;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, arg: u64) -> Result<Self> {
+		let (ptr, len) = unpack_ptr_and_len(arg);
+
+		Ok(RawSlice { ptr: Pointer::new(ptr), len })
+	}
+}
+
+/// The type is passed as `u64`, the same way as `[u8]`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit` of the compressed data.
+impl RIType for Compressed {
+	type FFIType = u64;
+}
+
+#[cfg(not(feature = "std"))]
+impl Compressed {
+	/// Compress the given `data` and return the ffi value of the compressed data.
+	pub fn into_ffi_value(data: &[u8]) -> WrappedFFIValue<u64, Vec<u8>> {
+		let compressed = compression::compress(data);
+		let ffi_value = pack_ptr_and_len(
+			compressed.as_ptr() as u32,
+			usize_to_u32(compressed.len()),
+		);
+
+		(ffi_value, compressed).into()
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for Compressed {
+	type SelfInstance = Vec<u8>;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<Vec<u8>> {
+		let (ptr, len) = unpack_ptr_and_len(arg);
+		let compressed = context.read_memory(Pointer::new(ptr), len)?;
+
+		compression::decompress(&compressed)
+			.ok_or_else(|| "Could not decompress the data from wasm".into())
+	}
+}
+
+/// The type is passed as `u64`, the same way as `[u8]`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit` of the buffer that holds the
+/// slices, see [`OffsetTable`].
+impl RIType for ByteSlices {
+	type FFIType = u64;
+}
+
+#[cfg(not(feature = "std"))]
+impl ByteSlices {
+	/// Write the given `slices` into one buffer and return the ffi value of the buffer.
+	pub fn into_ffi_value(slices: &[&[u8]]) -> WrappedFFIValue<u64, ArgumentBuffer> {
+		let data = ArgumentBuffer::encode(&OffsetTable(slices));
+		(data.ffi_value(), data).into()
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for ByteSlices {
+	type SelfInstance = ByteSlices;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<ByteSlices> {
+		let (ptr, len) = unpack_ptr_and_len(arg);
+		let buffer = context.read_memory(Pointer::new(ptr), len)?;
+		let ranges = chunk_ranges(&buffer).ok_or("Could not decode the byte slices from wasm")?;
+
+		Ok(ByteSlices { buffer, ranges })
+	}
+}
+
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | offset 32bit` of the returned slice in the
+/// argument it borrows from. `None` is passed as `u64::max_value()`.
+impl RIType for BorrowedSlice {
+	type FFIType = u64;
+}
+
+#[cfg(not(feature = "std"))]
+impl BorrowedSlice {
+	/// Returns the slice of `source` that is given by the ffi value `arg` the host returned.
+	pub fn from_ffi_value(source: &[u8], arg: u64) -> Option<&[u8]> {
+		if arg == u64::max_value() {
+			return None
+		}
+
+		let (offset, len) = unpack_ptr_and_len(arg);
+		let start = offset as usize;
+		let slice = source.get(start..start + len as usize)
+			.expect("The host only returns slices of `source`; qed");
+
+		Some(slice)
+	}
+}
+
+#[cfg(feature = "std")]
+impl BorrowedSlice {
+	/// Returns the ffi value of `slice`, which needs to be a slice of `source`.
+	///
+	/// An empty slice is passed as the empty slice at the start of `source`, as it doesn't need to
+	/// point into `source`.
+	pub fn into_ffi_value(source: &[u8], slice: Option<&[u8]>) -> Result<u64> {
+		let slice = match slice {
+			Some(slice) if slice.is_empty() => return Ok(pack_ptr_and_len(0, 0)),
+			Some(slice) => slice,
+			None => return Ok(u64::max_value()),
+		};
+
+		let offset = (slice.as_ptr() as usize).checked_sub(source.as_ptr() as usize)
+			.filter(|offset| offset + slice.len() <= source.len())
+			.ok_or("The returned slice is not a slice of the argument it borrows from")?;
+
+		Ok(pack_ptr_and_len(usize_to_u32(offset), usize_to_u32(slice.len())))
+	}
+}
+
+/// The type is passed as `u32`.
+impl RIType for Callback {
+	type FFIType = u32;
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for Callback {
+	type SelfInstance = Self;
+
+	fn from_ffi_value(_: &mut dyn FunctionContext, arg: u32) -> Result<Self> {
+		Ok(Callback(arg))
+	}
+}
+
+/// The type is passed as `u32`.
+///
+/// The `u32` is the pointer to the packed pointer and length of the vector.
+impl RIType for OutVec {
+	type FFIType = u32;
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for OutVec {
+	type SelfInstance = Vec<u8>;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u32) -> Result<Vec<u8>> {
+		let mut ptr_and_len = [0u8; 8];
+		context.read_memory_into(Pointer::new(arg), &mut ptr_and_len)?;
+		let (ptr, len) = unpack_ptr_and_len(u64::from_le_bytes(ptr_and_len));
+
+		context.read_memory(Pointer::new(ptr), len)
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoPreallocatedFFIValue for OutVec {
+	type SelfInstance = Vec<u8>;
+
+	fn into_preallocated_ffi_value(
+		self_instance: Vec<u8>,
+		context: &mut dyn FunctionContext,
+		allocated: u32,
+	) -> Result<()> {
+		let ptr = context.allocate_memory(usize_to_u32(self_instance.len()))?;
+		context.write_memory(ptr, &self_instance)?;
+
+		let ptr_and_len = pack_ptr_and_len(ptr.into(), usize_to_u32(self_instance.len()));
+		context.write_memory(Pointer::new(allocated), &ptr_and_len.to_le_bytes())
+	}
+}
+
 /// Implement the traits for `u128`/`i128`
 macro_rules! for_u128_i128 {
 	($type:ty) => {
@@ -525,6 +862,62 @@ macro_rules! for_u128_i128 {
 for_u128_i128!(u128);
 for_u128_i128!(i128);
 
+/// The number of nanoseconds in a second.
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Converts the given nanoseconds into a `Duration`.
+///
+/// Returns `None` if the seconds don't fit into a `u64`.
+fn duration_from_nanos(nanos: u128) -> Option<Duration> {
+	let secs = u64::try_from(nanos / NANOS_PER_SEC).ok()?;
+	Some(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+}
+
+/// `Duration` is passed as `u32`.
+///
+/// The `u32` is a pointer to the nanoseconds of the duration, as little endian `u128`.
+impl RIType for Duration {
+	type FFIType = u32;
+}
+
+#[cfg(not(feature = "std"))]
+impl IntoFFIValue for Duration {
+	type Owned = ArgumentBuffer;
+
+	fn into_ffi_value(&self) -> WrappedFFIValue<u32, ArgumentBuffer> {
+		// An `u128` is SCALE encoded as its little endian bytes and the lower half of the ffi value
+		// of the buffer is the pointer.
+		let nanos = ArgumentBuffer::encode(&self.as_nanos());
+		(nanos.ffi_value() as u32, nanos).into()
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl FromFFIValue for Duration {
+	fn from_ffi_value(arg: u32) -> Duration {
+		duration_from_nanos(u128::from_ffi_value(arg))
+			.expect("The host only passes the nanoseconds of a `Duration`; qed")
+	}
+}
+
+#[cfg(feature = "std")]
+impl FromFFIValue for Duration {
+	type SelfInstance = Duration;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u32) -> Result<Duration> {
+		let nanos = u128::from_ffi_value(context, arg)?;
+		duration_from_nanos(nanos)
+			.ok_or_else(|| format!("{} nanoseconds overflow a `Duration`", nanos))
+	}
+}
+
+#[cfg(feature = "std")]
+impl IntoFFIValue for Duration {
+	fn into_ffi_value(self, context: &mut dyn FunctionContext) -> Result<u32> {
+		self.as_nanos().into_ffi_value(context)
+	}
+}
+
 impl PassBy for sp_wasm_interface::ValueType {
 	type PassBy = Enum<sp_wasm_interface::ValueType>;
 }
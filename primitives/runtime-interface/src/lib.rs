@@ -40,7 +40,8 @@
 //! 3. Pass as mutable function argument: [`host::IntoPreallocatedFFIValue`]
 //!
 //! The traits are implemented for most of the common types like `[T]`, `Vec<T>`, arrays and
-//! primitive types.
+//! primitive types. If a type in the signature is missing one of these traits, the compiler error
+//! points at the type and names the missing trait.
 //!
 //! For custom types, we provide the [`PassBy`](pass_by::PassBy) trait and strategies that define
 //! how a type is passed between the wasm runtime and the node. Each strategy also provides a derive
@@ -54,6 +55,14 @@
 //! them in front of. The implementation of [`RIType`] each type provides more information on how
 //! the data is passed.
 //!
+//! A returned `Vec<u8>` is allocated by the host directly in the wasm memory and the runtime
+//! takes ownership of it. So even multi-megabyte return values are copied only once.
+//!
+//! Arguments that are SCALE encoded on the wasm side are stored in a `Vec<u8>` for the duration
+//! of the call. A runtime can provide its own allocator for these buffers, e.g. a scratch
+//! allocator, with [`wasm::set_argument_allocator`]. Methods annotated with `#[no_alloc(max = N)]`
+//! store them in an array on the stack instead.
+//!
 //! # Declaring a runtime interface
 //!
 //! Declaring a runtime interface is similar to declaring a trait in Rust:
@@ -88,23 +97,64 @@
 //! | `i64` | `i64` | `Identity` |
 //! | `u128` | `u32` | `v.as_ptr()` (pointer to a 16 byte array) |
 //! | `bool` | `u8` | `if v { 1 } else { 0 }` |
+//! | `Duration` | `u32` | `v.as_nanos().as_ptr()` (pointer to a 16 byte array) |
 //! | `&str` | `u64` | <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> |
+//! | `String` (only as argument) | `u64` | <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> |
 //! | `&[u8]` | `u64` | <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> |
+//! | `&[T]`, `Vec<T>` (as argument with `#[pass_by(width)]`) `where T: u16, u32, u64` | `u64` | <code>(v.len() * size_of::&lt;T&gt;()) 32bit << 32 &#124; v.as_ptr() 32bit</code> (little endian) |
 //! | `Vec<u8>` | `u64` | <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> |
+//! | `Cow<'static, [u8]>` | `u64` | <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> (only as return value) |
+//! | `Vec<Vec<u8>>` with `#[pass_by(chunks)]` | `u64` | `let e = chunks.len() ++ end offsets ++ data;` (all as `u32` LE)<br><br><code>e.len() 32bit << 32 &#124; e.as_ptr() 32bit</code> |
+//! | `Vec<bool>` with `#[pass_by(bitmap)]` | `u64` | `let e = v.len() as u32 ++ bits;` (`u32` LE, 8 bits per byte, least significant first)<br><br><code>e.len() 32bit << 32 &#124; e.as_ptr() 32bit</code> |
+//! | `&'a [u8]`, `Option<&'a [u8]>` (only as return value borrowing from an argument) | `u64` | <code>v.len() 32bit << 32 &#124; offset in the argument 32bit</code>, `None` is `u64::max_value()` |
+//! | `&[&[u8]]` (only as argument) | `u64` | `let e = slices.len() ++ end offsets ++ data;` (all as `u32` LE)<br><br><code>e.len() 32bit << 32 &#124; e.as_ptr() 32bit</code> |
 //! | `Vec<T> where T: Encode` | `u64` | `let e = v.encode();`<br><br><code>e.len() 32bit << 32 &#124; e.as_ptr() 32bit</code> |
 //! | `&[T] where T: Encode` | `u64` | `let e = v.encode();`<br><br><code>e.len() 32bit << 32 &#124; e.as_ptr() 32bit</code> |
 //! | `[u8; N]` | `u32` | `v.as_ptr()` |
 //! | `*const T` | `u32` | `Identity` |
 //! | `Option<T>` | `u64` | `let e = v.encode();`<br><br><code>e.len() 32bit << 32 &#124; e.as_ptr() 32bit</code> |
+//! | `BTreeMap<K, V>` | `u64` | `let e = v.encode();`<br><br><code>e.len() 32bit << 32 &#124; e.as_ptr() 32bit</code> |
+//! | `(T1, .., TN)` | `u64` | `let e = v.encode();`<br><br><code>e.len() 32bit << 32 &#124; e.as_ptr() 32bit</code> |
 //! | [`T where T: PassBy<PassBy=Inner>`](pass_by::Inner) | Depends on inner | Depends on inner |
 //! | [`T where T: PassBy<PassBy=Codec>`](pass_by::Codec) | `u64`| <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> |
 //!
 //! `Identity` means that the value is converted directly into the corresponding FFI type.
+//!
+//! Types that are passed by codec can also be passed as shared reference `&T`. The wasm side
+//! encodes the value behind the reference and the host decodes it into an owned value, so `T` is
+//! not required to implement `Clone`.
+//!
+//! A slice `&[T]` of a type that is not an integer, e.g. a list of enum operations, is encoded as
+//! one buffer. So, any number of these values is passed with one ffi value.
+//!
+//! A `&[&[u8]]`, e.g. a list of messages to hash, is written into one buffer the same way as a
+//! `#[pass_by(chunks)]` `Vec<Vec<u8>>`. The host implementation gets a `&[&[u8]]` that borrows the
+//! slices from the buffer, see [`ByteSlices`].
+//!
+//! A `Vec<bool>` with `#[pass_by(bitmap)]`, e.g. the results of a batch verification, is packed
+//! into a bitmap with one bit per `bool`, instead of the one byte per `bool` of its encoding, see
+//! [`pass_by::Bitmap`].
+//!
+//! A `String` argument is passed as its UTF-8 bytes, the same way as a `&str`, so the wasm side
+//! doesn't need to encode it. The host checks that the bytes are valid UTF-8.
+//!
+//! A `[u8; N]` argument, e.g. a public key, is passed by value without a slice. The wasm side
+//! passes the pointer to the array of the caller and the host reads the `N` bytes from there.
+//!
+//! An `Option<[u8; N]>`, e.g. an optional hash, is passed as `Option<T>`. Its encoding is one byte
+//! that flags if the value is present, followed by the `N` bytes of the array only if it is.
+//!
+//! A `core::time::Duration` is passed as its nanoseconds, so it keeps its sub-second precision.
+//! `SystemTime` is not supported, as it doesn't exist in wasm. Pass the `Duration` since the
+//! `UNIX_EPOCH` instead.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate self as sp_runtime_interface;
 
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
 #[doc(hidden)]
 #[cfg(feature = "std")]
 pub use sp_wasm_interface;
@@ -187,6 +237,8 @@ pub use sp_std;
 ///         }
 ///     }
 ///
+///     // The doc comments of the latest version of the trait method are put on the
+///     // generated function, for native and wasm.
 ///     pub fn call(data: &[u8]) -> Vec<u8> {
 ///         // only latest version is exposed
 ///         call_version_2(data)
@@ -196,19 +248,48 @@ pub use sp_std;
 ///         <&mut dyn sp_externalities::Externalities as Interface>::call_version_1(data)
 ///     }
 ///
+///     // Every version also exposes its versioned function name and the signature of its host
+///     // function.
+///     pub const CALL_VERSION_1: &str = "call_version_1";
+///
+///     pub fn call_version_1_signature() -> sp_wasm_interface::Signature {
+///         sp_wasm_interface::Signature::new(
+///             &[sp_wasm_interface::ValueType::I64][..],
+///             Some(sp_wasm_interface::ValueType::I64),
+///         )
+///     }
+///
 ///     fn call_version_2(data: &[u8]) -> Vec<u8> {
 ///         <&mut dyn sp_externalities::Externalities as Interface>::call_version_2(data)
 ///     }
 ///
+///     pub const CALL_VERSION_2: &str = "call_version_2";
+///
 ///     pub fn set_or_clear(optional: Option<Vec<u8>>) {
 ///         set_or_clear_version_1(optional)
 ///     }
 ///
 ///     fn set_or_clear_version_1(optional: Option<Vec<u8>>) {
-///         sp_externalities::with_externalities(|mut ext| Interface::set_or_clear_version_1(&mut ext, optional))
-///             .expect("`set_or_clear` called outside of an Externalities-provided environment.")
+///         let res = sp_externalities::with_externalities(|mut ext| {
+///             Interface::set_or_clear_version_1(&mut ext, optional)
+///         });
+///         match res {
+///             Some(res) => res,
+///             None => sp_runtime_interface::externalities_unavailable("set_or_clear_version_1"),
+///         }
 ///     }
 ///
+///     pub const SET_OR_CLEAR_VERSION_1: &str = "set_or_clear_version_1";
+///
+///     /// Every function with every version that is provided by the interface.
+///     pub const INTERFACE_FUNCTIONS: &[(&str, u32)] =
+///         &[("call", 1), ("call", 2), ("set_or_clear", 1)];
+///
+///     /// The latest version of all functions and of every function.
+///     pub const fn latest_version() -> u32 { 2 }
+///     pub const fn call_latest_version() -> u32 { 2 }
+///     pub const fn set_or_clear_latest_version() -> u32 { 1 }
+///
 ///     /// This type implements the `HostFunctions` trait (from `sp-wasm-interface`) and
 ///     /// provides the host implementation for the wasm side. The host implementation converts the
 ///     /// arguments from wasm to native and calls the corresponding native function.
@@ -216,6 +297,13 @@ pub use sp_std;
 ///     /// This type needs to be passed to the wasm executor, so that the host functions will be
 ///     /// registered in the executor.
 ///     pub struct HostFunctions;
+///
+///     /// Returns all versions of all host functions of the interface, the same as
+///     /// `HostFunctions::host_functions()`. This can be used to register the host functions in
+///     /// a custom executor.
+///     pub fn host_functions() -> Vec<&'static dyn sp_wasm_interface::Function> {
+///         Vec::new()
+///     }
 /// }
 /// ```
 ///
@@ -274,6 +362,54 @@ pub use sp_std;
 /// representation. On the host each argument is converted back to the native representation and
 /// the native implementation is called. Any return value is handled in the same way.
 ///
+/// `usize` and `isize` are rejected as argument and return types, also inside other types like
+/// `Vec<usize>`. They are 32 bit in wasm, but usually 64 bit on the host, so a fixed-width integer
+/// type needs to be used instead. Only `#[native_only]` methods can use them.
+///
+/// An argument can be annotated with `#[pass_by(codec)]` to pass it SCALE encoded, regardless of
+/// the strategy of its type. This is useful for types that could change in the future, e.g. a
+/// small `Copy` struct that is passed by its inner value. Annotating the method with
+/// `#[pass_by(codec)]` does the same for the return value. The types only need to implement
+/// `Encode` and `Decode`.
+///
+/// ```
+/// # use sp_runtime_interface::runtime_interface;
+/// #[runtime_interface]
+/// trait Interface {
+///     #[pass_by(codec)]
+///     fn increment(#[pass_by(codec)] value: u32) -> u32 {
+///         value + 1
+///     }
+/// }
+/// ```
+///
+/// A `Vec<Vec<u8>>` argument or return value can opt into `#[pass_by(chunks)]`, which writes the
+/// chunks into one buffer with a table of their offsets, see [`pass_by::Chunks`]. Without the
+/// attribute, it is SCALE encoded like any other `Vec<T>`, so existing methods keep their ABI.
+/// The same goes for a `&[T]` or `Vec<T>` argument of `u16`, `u32` or `u64` with
+/// `#[pass_by(width)]`, which is passed as the little endian bytes of its elements without
+/// encoding it, see [`pass_by::Width`], and for a `Vec<bool>` with `#[pass_by(bitmap)]`, which is
+/// packed into a bitmap, see [`pass_by::Bitmap`].
+///
+/// ```
+/// # use sp_runtime_interface::runtime_interface;
+/// #[runtime_interface]
+/// trait Interface {
+///     #[pass_by(chunks)]
+///     fn split(#[pass_by(chunks)] chunks: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+///         chunks.into_iter().flat_map(|c| c.into_iter().map(|b| vec![b])).collect()
+///     }
+///
+///     fn sum(#[pass_by(width)] values: &[u64]) -> u64 {
+///         values.iter().sum()
+///     }
+/// }
+/// ```
+///
+/// Arguments of a zero sized type, `PhantomData<T>` or `()`, don't need to implement [`RIType`].
+/// They are not passed to the host and don't appear in the signature of the host function. The
+/// host creates them with `Default::default()`.
+///
 /// # Wasm only interfaces
 ///
 /// Some interfaces are only required from within the wasm runtime e.g. the allocator interface.
@@ -283,6 +419,38 @@ pub use sp_std;
 /// 1. The generated functions are not callable from the native side.
 /// 2. The trait as shown above is not implemented for `Externalities` and is instead implemented
 ///    for `FunctionExecutor` (from `sp-wasm-interface`).
+///
+/// Functions can not return references, as any borrow of the `FunctionContext` ends with the call
+/// into the host. To give the runtime access to host provided memory, allocate it in the wasm
+/// memory and return the `Pointer<u8>` to it, like the allocator interface of `sp-io` does.
+///
+/// Single methods of an interface can be annotated with `#[wasm_only]`. The other methods stay
+/// callable from the native side, while the annotated methods get the changes from above. Their
+/// implementations are put into a separate trait, named `TRAIT_NAMEWasmOnly`, that is implemented
+/// for `FunctionContext`. `#[wasm_only]` methods can not be `#[batch]`ed.
+///
+/// ```
+/// #[sp_runtime_interface::runtime_interface]
+/// trait Interface {
+///     fn storage_root(&mut self) -> Vec<u8> {
+///         self.storage_root()
+///     }
+///
+///     #[wasm_only]
+///     fn first_byte(&mut self, #[pass_by(raw_ptr)] data: &[u8]) -> u32 {
+///         let mut byte = [0u8; 1];
+///         if data.len > 0 {
+///             self.read_memory_into(data.ptr, &mut byte).expect("`data` is valid memory");
+///         }
+///         byte[0] as u32
+///     }
+/// }
+/// ```
+///
+/// # Options and attributes
+///
+/// The options of the macro, e.g. `#[runtime_interface(wasm_only)]`, and the attributes of the
+/// methods, e.g. `#[version(2)]`, are documented in the [`attributes`] module.
 pub use sp_runtime_interface_proc_macro::runtime_interface;
 
 #[doc(hidden)]
@@ -294,12 +462,60 @@ pub use sp_externalities::{
 #[doc(hidden)]
 pub use codec;
 
+#[doc(hidden)]
+#[cfg(feature = "async")]
+pub use futures;
+
+/// Expands to nothing, as the feature required by an option of `#[runtime_interface]` is enabled.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __feature_enabled {
+	($message:literal) => {};
+}
+
+/// Fails with the given message, as the feature required by an option of `#[runtime_interface]`
+/// is disabled.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __feature_disabled {
+	($message:literal) => { compile_error!($message); };
+}
+
+/// The checks of the features that are required by the options of `#[runtime_interface]`.
+#[doc(hidden)]
+pub mod features {
+	#[cfg(feature = "async")]
+	pub use crate::__feature_enabled as require_async;
+	#[cfg(not(feature = "async"))]
+	pub use crate::__feature_disabled as require_async;
+	#[cfg(feature = "record")]
+	pub use crate::__feature_enabled as require_record;
+	#[cfg(not(feature = "record"))]
+	pub use crate::__feature_disabled as require_record;
+	#[cfg(feature = "audit")]
+	pub use crate::__feature_enabled as require_audit;
+	#[cfg(not(feature = "audit"))]
+	pub use crate::__feature_disabled as require_audit;
+}
+
 pub(crate) mod impls;
 #[cfg(feature = "std")]
 pub mod host;
+#[cfg(feature = "record")]
+pub mod record;
+#[cfg(feature = "audit")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod simulator;
+#[doc(hidden)]
+#[cfg(feature = "gen-tests")]
+pub mod testing;
 #[cfg(any(not(feature = "std"), doc))]
 pub mod wasm;
+pub mod attributes;
 pub mod pass_by;
+pub mod stream;
+pub mod compression;
 
 mod util;
 
@@ -325,3 +541,96 @@ pub type Pointer<T> = *mut T;
 /// A pointer that can be used in a runtime interface function signature.
 #[cfg(feature = "std")]
 pub type Pointer<T> = sp_wasm_interface::Pointer<T>;
+
+/// A byte slice in the wasm memory, given by the pointer to its first byte and its length.
+///
+/// An argument `#[pass_by(raw_ptr)] data: &[u8]` of a wasm only runtime interface method is passed
+/// as `RawSlice` to the host implementation. So, the data is not copied out of the wasm memory and
+/// can be read on demand using the `FunctionContext`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawSlice {
+	/// The pointer to the first byte of the slice.
+	pub ptr: Pointer<u8>,
+	/// The length of the slice in bytes.
+	pub len: u32,
+}
+
+/// The handle of a callback in the wasm runtime, e.g. the index of a function in its table.
+///
+/// An argument `#[callback] callback: u32` of a wasm only runtime interface method is passed as
+/// `u32` to the host, but the host implementation gets it as `Callback`. So, the handle can not be
+/// mixed up with any other integer on the host side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Callback(u32);
+
+impl Callback {
+	/// Returns the handle that was given by the wasm side.
+	pub fn index(&self) -> u32 {
+		self.0
+	}
+}
+
+/// The byte slices of a `&[&[u8]]` argument, read from the wasm memory.
+///
+/// An argument `data: &[&[u8]]` is passed as `ByteSlices` to the host, with all slices written
+/// into one buffer with a table of their offsets, the same way as the chunks of a `Vec<Vec<u8>>`.
+/// The host implementation still gets a `&[&[u8]]`, which borrows the slices from this buffer.
+pub struct ByteSlices {
+	#[cfg(feature = "std")]
+	buffer: sp_std::vec::Vec<u8>,
+	#[cfg(feature = "std")]
+	ranges: sp_std::vec::Vec<sp_std::ops::Range<usize>>,
+}
+
+#[cfg(feature = "std")]
+impl ByteSlices {
+	/// Returns the slices that borrow from the buffer.
+	pub fn slices(&self) -> Vec<&[u8]> {
+		self.ranges.iter().map(|range| &self.buffer[range.clone()]).collect()
+	}
+}
+
+/// The return value of a method that returns a slice of one of its arguments.
+///
+/// A method `fn find<'a>(haystack: &'a [u8], needle: &[u8]) -> Option<&'a [u8]>` returns a
+/// `BorrowedSlice` from its host function. The host passes the offset and the length of the
+/// returned slice in `haystack` and the wasm side takes this slice from its own `haystack`. So,
+/// the return value borrows from the argument on both sides.
+pub struct BorrowedSlice;
+
+/// The error of a function that was called outside of an `Externalities`-provided environment.
+///
+/// The methods taking `self` of a `#[runtime_interface(no_panic)]` interface return a
+/// `Result<T, E>` and return `Err(NoExternalities.into())` in this case, instead of panicking. So,
+/// `E` needs to implement `From<NoExternalities>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoExternalities;
+
+impl From<NoExternalities> for () {
+	fn from(_: NoExternalities) {}
+}
+
+/// Panics, because the given function was called outside of an `Externalities`-provided
+/// environment.
+///
+/// This is an unlikely path of the generated functions. It is kept out of line, so it doesn't
+/// prevent the inlining of the common path. It tracks the caller, so a function generated with
+/// `track_caller` still reports the location of its caller.
+#[doc(hidden)]
+#[cfg(feature = "std")]
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub fn externalities_unavailable(function: &str) -> ! {
+	panic!("`{}` called outside of an Externalities-provided environment.", function)
+}
+
+/// A `Vec<u8>` that is given as `#[out]` argument to a runtime interface function.
+///
+/// An argument `#[out] data: &mut Vec<u8>` is passed as pointer to the packed pointer and length
+/// of the vector in the wasm memory. The host reads the vector from there and calls the host
+/// implementation with it. Afterwards the vector is written into newly allocated wasm memory and
+/// its pointer and length are stored at the given pointer, so that the wasm side can replace the
+/// content of the vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutVec;
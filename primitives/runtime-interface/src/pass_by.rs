@@ -17,9 +17,14 @@
 //! Provides the [`PassBy`](PassBy) trait to simplify the implementation of the
 //! runtime interface traits for custom types.
 //!
-//! [`Codec`], [`Inner`] and [`Enum`] are the provided strategy implementations.
+//! [`Codec`], [`Inner`] and [`Enum`] are the provided strategy implementations. [`Chunks`],
+//! [`Width`] and [`Bitmap`] are only used for arguments and return values annotated with
+//! `#[pass_by(chunks)]`, `#[pass_by(width)]` and `#[pass_by(bitmap)]`.
 
-use crate::{RIType, util::{unpack_ptr_and_len, pack_ptr_and_len}};
+use crate::{
+	RIType, impls::OffsetTable,
+	util::{unpack_ptr_and_len, pack_ptr_and_len, usize_to_u32},
+};
 
 #[cfg(feature = "std")]
 use crate::host::*;
@@ -29,6 +34,9 @@ use crate::wasm::*;
 #[cfg(feature = "std")]
 use sp_wasm_interface::{FunctionContext, Pointer, Result};
 
+#[cfg(feature = "std")]
+use codec::Encode;
+
 use sp_std::{marker::PhantomData, convert::TryFrom};
 
 #[cfg(not(feature = "std"))]
@@ -224,10 +232,10 @@ impl<T: codec::Codec> PassByImpl<T> for Codec<T> {
 		context: &mut dyn FunctionContext,
 	) -> Result<Self::FFIType> {
 		let vec = instance.encode();
-		let ptr = context.allocate_memory(vec.len() as u32)?;
+		let ptr = context.allocate_memory(usize_to_u32(vec.len()))?;
 		context.write_memory(ptr, &vec)?;
 
-		Ok(pack_ptr_and_len(ptr.into(), vec.len() as u32))
+		Ok(pack_ptr_and_len(ptr.into(), usize_to_u32(vec.len())))
 	}
 
 	fn from_ffi_value(
@@ -243,12 +251,11 @@ impl<T: codec::Codec> PassByImpl<T> for Codec<T> {
 
 #[cfg(not(feature = "std"))]
 impl<T: codec::Codec> PassByImpl<T> for Codec<T> {
-	type Owned = Vec<u8>;
+	type Owned = ArgumentBuffer;
 
 	fn into_ffi_value(instance: &T) -> WrappedFFIValue<Self::FFIType, Self::Owned> {
-		let data = instance.encode();
-		let ffi_value = pack_ptr_and_len(data.as_ptr() as u32, data.len() as u32);
-		(ffi_value, data).into()
+		let data = ArgumentBuffer::encode(instance);
+		(data.ffi_value(), data).into()
 	}
 
 	fn from_ffi_value(arg: Self::FFIType) -> T {
@@ -274,6 +281,17 @@ impl<T: codec::Codec> RIType for Codec<T> {
 	type FFIType = u64;
 }
 
+/// Used for arguments annotated with `#[pass_by(codec)]`, which are passed as `Codec<T>` to the
+/// host.
+#[cfg(feature = "std")]
+impl<T: codec::Codec> FromFFIValue for Codec<T> {
+	type SelfInstance = T;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<T> {
+		<Self as PassByImpl<T>>::from_ffi_value(context, arg)
+	}
+}
+
 /// Trait that needs to be implemented by a type that should be passed between wasm and the host,
 /// by using the inner type. See [`Inner`] for more information.
 pub trait PassByInner: Sized {
@@ -449,3 +467,237 @@ impl<T: Copy + Into<u8> + TryFrom<u8, Error = ()>> PassByImpl<T> for Enum<T> {
 impl<T: Copy + Into<u8> + TryFrom<u8>> RIType for Enum<T> {
 	type FFIType = u8;
 }
+
+/// The implementation of the pass by chunks strategy. This strategy writes the chunks of a
+/// `Vec<Vec<u8>>` into one buffer, that starts with the number of chunks and the end offset of
+/// every chunk in the data, all as `u32` in little endian, followed by the data of all chunks.
+///
+/// Compared to the SCALE encoding, which puts a compact length in front of every chunk, the
+/// offsets don't need to be parsed as variable length integers, at the cost of 4 bytes per chunk.
+///
+/// This strategy is only used for arguments and return values that are annotated with
+/// `#[pass_by(chunks)]`, a `Vec<Vec<u8>>` is SCALE encoded otherwise.
+pub struct Chunks;
+
+#[cfg(feature = "std")]
+impl PassByImpl<Vec<Vec<u8>>> for Chunks {
+	fn into_ffi_value(
+		instance: Vec<Vec<u8>>,
+		context: &mut dyn FunctionContext,
+	) -> Result<Self::FFIType> {
+		let vec = OffsetTable(&instance[..]).encode();
+		let ptr = context.allocate_memory(usize_to_u32(vec.len()))?;
+		context.write_memory(ptr, &vec)?;
+
+		Ok(pack_ptr_and_len(ptr.into(), usize_to_u32(vec.len())))
+	}
+
+	fn from_ffi_value(
+		context: &mut dyn FunctionContext,
+		arg: Self::FFIType,
+	) -> Result<Vec<Vec<u8>>> {
+		let (ptr, len) = unpack_ptr_and_len(arg);
+		let vec = context.read_memory(Pointer::new(ptr), len)?;
+		OffsetTable::decode(&vec).ok_or_else(|| "Could not decode the chunks from wasm".into())
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl PassByImpl<Vec<Vec<u8>>> for Chunks {
+	type Owned = ArgumentBuffer;
+
+	fn into_ffi_value(instance: &Vec<Vec<u8>>) -> WrappedFFIValue<Self::FFIType, Self::Owned> {
+		let data = ArgumentBuffer::encode(&OffsetTable(&instance[..]));
+		(data.ffi_value(), data).into()
+	}
+
+	fn from_ffi_value(arg: Self::FFIType) -> Vec<Vec<u8>> {
+		let data = <Vec<u8> as FromFFIValue>::from_ffi_value(arg);
+		OffsetTable::decode(&data).expect("Host to wasm values are encoded correctly; qed")
+	}
+}
+
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+///
+/// The length and the pointer are taken from the buffer with the chunks.
+impl RIType for Chunks {
+	type FFIType = u64;
+}
+
+/// Used for arguments annotated with `#[pass_by(chunks)]`, which are passed as `Chunks` to the
+/// host.
+#[cfg(feature = "std")]
+impl FromFFIValue for Chunks {
+	type SelfInstance = Vec<Vec<u8>>;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<Vec<Vec<u8>>> {
+		<Self as PassByImpl<Vec<Vec<u8>>>>::from_ffi_value(context, arg)
+	}
+}
+
+/// The implementation of the pass by width strategy. This strategy passes a `&[T]` or `Vec<T>`
+/// argument of `u16`, `u32` or `u64` as the little endian bytes of its elements, without encoding
+/// it. Wasm is little endian, so the wasm side passes the memory of the slice as it is and the host
+/// reads the elements with their width.
+///
+/// This strategy is only used for arguments that are annotated with `#[pass_by(width)]`, these
+/// slices are SCALE encoded otherwise.
+pub struct Width<T>(PhantomData<T>);
+
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`, the length is the number of
+/// bytes of the slice.
+impl<T> RIType for Width<T> {
+	type FFIType = u64;
+}
+
+/// Implement the width strategy for the given integer types.
+macro_rules! impl_width_for_integers {
+	( $( $int:ty ),* ) => {
+		$(
+			#[cfg(not(feature = "std"))]
+			impl Width<$int> {
+				/// Returns the ffi value of the given slice, which is passed without a copy.
+				pub fn into_ffi_value(instance: &[$int]) -> WrappedFFIValue<u64> {
+					let len = usize_to_u32(sp_std::mem::size_of_val(instance));
+					pack_ptr_and_len(instance.as_ptr() as u32, len).into()
+				}
+			}
+
+			/// Used for arguments annotated with `#[pass_by(width)]`, which are passed as
+			/// `Width<T>` to the host.
+			#[cfg(feature = "std")]
+			impl FromFFIValue for Width<$int> {
+				type SelfInstance = Vec<$int>;
+
+				fn from_ffi_value(
+					context: &mut dyn FunctionContext,
+					arg: u64,
+				) -> Result<Vec<$int>> {
+					const WIDTH: usize = sp_std::mem::size_of::<$int>();
+
+					let (ptr, len) = unpack_ptr_and_len(arg);
+					let bytes = context.read_memory(Pointer::new(ptr), len)?;
+
+					if bytes.len() % WIDTH != 0 {
+						return Err(format!(
+							"Slice of `{}` has {} bytes, which is not a multiple of {}",
+							stringify!($int),
+							bytes.len(),
+							WIDTH,
+						))
+					}
+
+					Ok(bytes.chunks_exact(WIDTH).map(|chunk| {
+						let mut le_bytes = [0; WIDTH];
+						le_bytes.copy_from_slice(chunk);
+						<$int>::from_le_bytes(le_bytes)
+					}).collect())
+				}
+			}
+		)*
+	}
+}
+
+impl_width_for_integers!(u16, u32, u64);
+
+/// The implementation of the pass by bitmap strategy. This strategy packs a `Vec<bool>` into a
+/// buffer that starts with the number of bits as `u32` in little endian, followed by the bits,
+/// eight per byte starting with the least significant bit. The unused bits of the last byte are
+/// zero.
+///
+/// Compared to the SCALE encoding, which uses one byte per `bool`, this is eight times smaller,
+/// e.g. for the results of a batch verification.
+///
+/// This strategy is only used for arguments and return values that are annotated with
+/// `#[pass_by(bitmap)]`, a `Vec<bool>` is SCALE encoded otherwise.
+pub struct Bitmap;
+
+impl Bitmap {
+	/// Packs the given bits into the buffer.
+	fn encode(bits: &[bool]) -> Vec<u8> {
+		let mut buffer = Vec::with_capacity(4 + (bits.len() + 7) / 8);
+		buffer.extend_from_slice(&usize_to_u32(bits.len()).to_le_bytes());
+
+		buffer.extend(bits.chunks(8).map(|bits| {
+			bits.iter().enumerate().fold(0u8, |byte, (i, bit)| byte | ((*bit as u8) << i))
+		}));
+
+		buffer
+	}
+
+	/// Reads the bits from the given buffer.
+	///
+	/// Returns `None` if the buffer is not valid.
+	fn decode(buffer: &[u8]) -> Option<Vec<bool>> {
+		let (len, bytes) = (buffer.get(..4)?, &buffer[4..]);
+		let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+		if bytes.len() != len.checked_add(7)? / 8 {
+			return None
+		}
+
+		Some((0..len).map(|i| bytes[i / 8] & (1 << (i % 8)) != 0).collect())
+	}
+}
+
+#[cfg(feature = "std")]
+impl PassByImpl<Vec<bool>> for Bitmap {
+	fn into_ffi_value(
+		instance: Vec<bool>,
+		context: &mut dyn FunctionContext,
+	) -> Result<Self::FFIType> {
+		let vec = Self::encode(&instance);
+		let ptr = context.allocate_memory(usize_to_u32(vec.len()))?;
+		context.write_memory(ptr, &vec)?;
+
+		Ok(pack_ptr_and_len(ptr.into(), usize_to_u32(vec.len())))
+	}
+
+	fn from_ffi_value(
+		context: &mut dyn FunctionContext,
+		arg: Self::FFIType,
+	) -> Result<Vec<bool>> {
+		let (ptr, len) = unpack_ptr_and_len(arg);
+		let vec = context.read_memory(Pointer::new(ptr), len)?;
+		Self::decode(&vec).ok_or_else(|| "Could not decode the bitmap from wasm".into())
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl PassByImpl<Vec<bool>> for Bitmap {
+	type Owned = Vec<u8>;
+
+	fn into_ffi_value(instance: &Vec<bool>) -> WrappedFFIValue<Self::FFIType, Self::Owned> {
+		let data = Self::encode(instance);
+		let ffi_value = pack_ptr_and_len(data.as_ptr() as u32, usize_to_u32(data.len()));
+		(ffi_value, data).into()
+	}
+
+	fn from_ffi_value(arg: Self::FFIType) -> Vec<bool> {
+		let data = <Vec<u8> as FromFFIValue>::from_ffi_value(arg);
+		Self::decode(&data).expect("Host to wasm values are encoded correctly; qed")
+	}
+}
+
+/// The type is passed as `u64`.
+///
+/// The `u64` value is build by `length 32bit << 32 | pointer 32bit`
+///
+/// The length and the pointer are taken from the buffer with the bits.
+impl RIType for Bitmap {
+	type FFIType = u64;
+}
+
+/// Used for arguments annotated with `#[pass_by(bitmap)]`, which are passed as `Bitmap` to the
+/// host.
+#[cfg(feature = "std")]
+impl FromFFIValue for Bitmap {
+	type SelfInstance = Vec<bool>;
+
+	fn from_ffi_value(context: &mut dyn FunctionContext, arg: u64) -> Result<Vec<bool>> {
+		<Self as PassByImpl<Vec<bool>>>::from_ffi_value(context, arg)
+	}
+}
@@ -0,0 +1,72 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Recording of the calls into runtime interfaces.
+//!
+//! Every call into a runtime interface declared with `#[runtime_interface(record)]` is reported
+//! to the [`Recorder`] that is set for the current thread. This includes the calls that are made
+//! from wasm, as the host functions call the native implementations.
+
+environmental::environmental!(recorder: trait Recorder);
+
+/// Something that records the calls into runtime interfaces.
+pub trait Recorder {
+	/// Records a call of the given function version with the SCALE encoded arguments.
+	///
+	/// `function` is the name of the called function version, e.g. `get_version_1`. The arguments
+	/// are encoded one after another in the order they are declared.
+	fn record(&mut self, function: &'static str, args: Vec<u8>);
+}
+
+impl Recorder for Vec<(&'static str, Vec<u8>)> {
+	fn record(&mut self, function: &'static str, args: Vec<u8>) {
+		self.push((function, args));
+	}
+}
+
+/// Set the given recorder while executing the given closure. The recorder is only set for the
+/// same thread this function was called from.
+pub fn record_calls<F, R>(recorder: &mut dyn Recorder, f: F) -> R
+	where F: FnOnce() -> R
+{
+	recorder::using(recorder, f)
+}
+
+/// Records a call of the given function version, if a recorder is set.
+///
+/// The arguments are only encoded when a recorder is set.
+#[doc(hidden)]
+pub fn record_call(function: &'static str, encode_args: impl FnOnce() -> Vec<u8>) {
+	recorder::with(|recorder| recorder.record(function, encode_args()));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn calls_are_only_recorded_with_a_recorder() {
+		record_call("not_recorded_version_1", || unreachable!("No recorder is set"));
+
+		let mut recorder = Vec::new();
+		record_calls(&mut recorder, || {
+			record_call("first_version_1", || vec![1]);
+			record_call("second_version_2", || vec![2, 3]);
+		});
+
+		assert_eq!(vec![("first_version_1", vec![1]), ("second_version_2", vec![2, 3])], recorder);
+	}
+}
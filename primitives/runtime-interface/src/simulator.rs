@@ -0,0 +1,107 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A simulation of the host side of a wasm executor for testing host functions.
+//!
+//! The [`HostSimulator`] holds the wasm memory in a `Vec` and calls the registered host functions
+//! by their names, like the executor does when a runtime calls them. Interfaces declared with
+//! `#[runtime_interface(host_simulator)]` provide `install_host_simulator` to register all of
+//! their host functions.
+
+use sp_wasm_interface::{Function, FunctionContext, Pointer, Result, Sandbox, Value, WordSize};
+
+use std::collections::BTreeMap;
+
+/// The host functions and the wasm memory of a simulated wasm executor.
+///
+/// Every allocation is appended to the memory and is never freed.
+#[derive(Default)]
+pub struct HostSimulator {
+	functions: BTreeMap<String, &'static dyn Function>,
+	memory: Vec<u8>,
+}
+
+impl HostSimulator {
+	/// Create a simulator without any host functions.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Register the given host functions.
+	///
+	/// A function that is registered under the same name is replaced.
+	pub fn register(&mut self, functions: impl IntoIterator<Item = &'static dyn Function>) {
+		self.functions.extend(functions.into_iter().map(|f| (f.name().into(), f)));
+	}
+
+	/// Returns if a host function with the given name is registered.
+	pub fn is_registered(&self, name: &str) -> bool {
+		self.functions.contains_key(name)
+	}
+
+	/// Call the host function with the given name and the given ffi values as arguments.
+	pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Option<Value>> {
+		let function = *self.functions.get(name)
+			.ok_or_else(|| format!("Host function `{}` is not registered", name))?;
+
+		function.execute(self, &mut args.iter().cloned())
+	}
+
+	/// Allocate memory for the given `data`, write it and return the pointer to it.
+	pub fn write(&mut self, data: &[u8]) -> Result<Pointer<u8>> {
+		let ptr = self.allocate_memory(data.len() as WordSize)?;
+		self.write_memory(ptr, data)?;
+		Ok(ptr)
+	}
+
+	fn range(&self, address: Pointer<u8>, len: usize) -> Result<std::ops::Range<usize>> {
+		let start = u32::from(address) as usize;
+
+		if start + len > self.memory.len() {
+			Err(format!("Access of {} bytes at {} is out of bounds", len, start))
+		} else {
+			Ok(start..start + len)
+		}
+	}
+}
+
+impl FunctionContext for HostSimulator {
+	fn read_memory_into(&self, address: Pointer<u8>, dest: &mut [u8]) -> Result<()> {
+		let range = self.range(address, dest.len())?;
+		dest.copy_from_slice(&self.memory[range]);
+		Ok(())
+	}
+
+	fn write_memory(&mut self, address: Pointer<u8>, data: &[u8]) -> Result<()> {
+		let range = self.range(address, data.len())?;
+		self.memory[range].copy_from_slice(data);
+		Ok(())
+	}
+
+	fn allocate_memory(&mut self, size: WordSize) -> Result<Pointer<u8>> {
+		let ptr = self.memory.len();
+		self.memory.resize(ptr + size as usize, 0);
+		Ok(Pointer::new(ptr as u32))
+	}
+
+	fn deallocate_memory(&mut self, _: Pointer<u8>) -> Result<()> {
+		Ok(())
+	}
+
+	fn sandbox(&mut self) -> &mut dyn Sandbox {
+		panic!("The sandbox is not supported by the `HostSimulator`")
+	}
+}
@@ -0,0 +1,175 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The streams returned by the methods annotated with `#[stream]`.
+//!
+//! The host keeps the iterator returned by the method and gives out a handle to it. The
+//! [`Stream`] pulls the items one by one through the handle, so only the items that are read are
+//! created and copied into the wasm memory.
+
+/// The items of an iterator that is kept on the host.
+///
+/// Every call to [`Iterator::next`] calls into the host to get the next item. The iterator on the
+/// host is dropped when the stream is dropped.
+pub struct Stream<T> {
+	handle: u32,
+	next: fn(u32) -> Option<T>,
+	drop: fn(u32),
+}
+
+impl<T> Stream<T> {
+	/// Create the stream for the given `handle`.
+	///
+	/// `next` returns the next item of the iterator with the given handle and `drop` drops the
+	/// iterator.
+	#[doc(hidden)]
+	pub fn new(handle: u32, next: fn(u32) -> Option<T>, drop: fn(u32)) -> Self {
+		Self { handle, next, drop }
+	}
+}
+
+impl<T> Iterator for Stream<T> {
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		(self.next)(self.handle)
+	}
+}
+
+impl<T> Drop for Stream<T> {
+	fn drop(&mut self) {
+		(self.drop)(self.handle)
+	}
+}
+
+#[cfg(feature = "std")]
+pub use host::{open, next, close};
+
+#[cfg(feature = "std")]
+mod host {
+	use sp_externalities::{Externalities, Extension};
+	use std::{any::{Any, TypeId}, collections::HashMap};
+
+	/// The iterators of the streams that are open in one execution, by their handle.
+	///
+	/// This is registered as extension of the externalities when the first stream is opened. So,
+	/// the iterators are dropped with the externalities of the execution, even if the runtime
+	/// traps or never drops its `Stream`, and the handles of one execution are not valid in any
+	/// other.
+	#[derive(Default)]
+	struct StreamIterators {
+		iterators: HashMap<u32, Box<dyn Any + Send>>,
+		/// The handle of the next stream, the handles start at `0` in every execution.
+		next_handle: u32,
+	}
+
+	impl Extension for StreamIterators {
+		fn as_mut_any(&mut self) -> &mut dyn Any {
+			self
+		}
+	}
+
+	/// Returns the iterators of the given externalities and registers them if there are none.
+	fn iterators(ext: &mut dyn Externalities) -> &mut StreamIterators {
+		let type_id = TypeId::of::<StreamIterators>();
+
+		if ext.extension_by_type_id(type_id).is_none() {
+			ext.register_extension_with_type_id(type_id, Box::new(StreamIterators::default()))
+				.expect("`#[stream]` methods require externalities that support extensions");
+		}
+
+		ext.extension_by_type_id(type_id)
+			.and_then(Any::downcast_mut)
+			.expect("The iterators are registered above; qed")
+	}
+
+	/// Keeps the given iterator in the externalities and returns the handle to it.
+	#[doc(hidden)]
+	pub fn open<I>(ext: &mut dyn Externalities, iter: I) -> u32
+	where
+		I: Iterator + Send + 'static,
+		I::Item: 'static,
+	{
+		let iter: Box<dyn Iterator<Item = I::Item> + Send> = Box::new(iter);
+		let iterators = iterators(ext);
+		let handle = iterators.next_handle;
+		iterators.next_handle = handle.checked_add(1)
+			.expect("Less than `u32::max_value()` streams are opened in one execution; qed");
+
+		iterators.iterators.insert(handle, Box::new(iter));
+		handle
+	}
+
+	/// Returns the next item of the iterator with the given handle.
+	///
+	/// Returns `None` if there is no iterator with the handle or if it has another item type.
+	#[doc(hidden)]
+	pub fn next<T: 'static>(ext: &mut dyn Externalities, handle: u32) -> Option<T> {
+		iterators(ext).iterators.get_mut(&handle)?
+			.downcast_mut::<Box<dyn Iterator<Item = T> + Send>>()?
+			.next()
+	}
+
+	/// Drops the iterator with the given handle.
+	///
+	/// Without externalities there are no iterators to drop, as they are dropped together with the
+	/// externalities.
+	#[doc(hidden)]
+	pub fn close(handle: u32) {
+		let iter = sp_externalities::with_externalities(|ext| {
+			iterators(ext).iterators.remove(&handle)
+		});
+		drop(iter);
+	}
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+	use super::*;
+	use sp_externalities::{Externalities, set_and_run_with_externalities};
+
+	#[test]
+	fn items_are_pulled_until_the_stream_is_closed() {
+		let mut ext = sp_io::TestExternalities::default();
+		let mut ext = ext.ext();
+		let ext: &mut dyn Externalities = &mut ext;
+
+		let stream = open(ext, 0..3u32);
+		let other = open(ext, vec![true].into_iter());
+		assert_eq!((0, 1), (stream, other));
+
+		assert_eq!(Some(0), next::<u32>(ext, stream));
+		assert_eq!(None, next::<bool>(ext, stream));
+		assert_eq!(Some(1), next::<u32>(ext, stream));
+
+		set_and_run_with_externalities(ext, || close(other));
+		assert_eq!(None, next::<bool>(ext, other));
+	}
+
+	#[test]
+	fn every_execution_has_its_own_streams() {
+		let mut first = sp_io::TestExternalities::default();
+		let mut first = first.ext();
+		assert_eq!(0, open(&mut first, 0..3u32));
+
+		let mut second = sp_io::TestExternalities::default();
+		let mut second = second.ext();
+		assert_eq!(None, next::<u32>(&mut second, 0));
+		assert_eq!(0, open(&mut second, 5..6u32));
+		assert_eq!(Some(5), next::<u32>(&mut second, 0));
+		assert_eq!(Some(0), next::<u32>(&mut first, 0));
+	}
+}
@@ -0,0 +1,110 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The roundtrip checks of the tests generated by `#[runtime_interface(gen_tests)]`.
+//!
+//! Every argument type `T` of the interface is checked with [`Roundtrip`]. Random values of
+//! `T::SelfInstance` are converted into their ffi value and back with the host side conversions,
+//! which needs to give the original value. The check is only done for types that support it, for
+//! all other types [`SkipRoundtrip`] is called instead, which does nothing.
+
+use crate::{
+	RIType, host::{IntoFFIValue, FromFFIValue}, simulator::HostSimulator, compression::Compressed,
+};
+
+use std::{any::TypeId, fmt::Debug, marker::PhantomData};
+
+pub use quickcheck;
+
+/// The argument type `T` that is checked.
+///
+/// The check is called as `(&Roundtrip::<T>::new()).check()`. If `T` supports the check, this
+/// calls [`CheckRoundtrip::check`], otherwise the reference is taken again and
+/// [`SkipRoundtrip::check`] is called.
+pub struct Roundtrip<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized> Roundtrip<T> {
+	/// Create the check for `T`.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: ?Sized> Default for Roundtrip<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Checks the roundtrip of random values of `T::SelfInstance`.
+pub trait CheckRoundtrip {
+	/// Run the check and panic if the roundtrip of any value fails.
+	fn check(&self);
+}
+
+impl<T> CheckRoundtrip for Roundtrip<T> where
+	T: FromFFIValue + ?Sized + 'static,
+	T::SelfInstance: IntoFFIValue + RIType<FFIType = T::FFIType> + quickcheck::Arbitrary
+		+ PartialEq + Debug,
+{
+	fn check(&self) {
+		// Slices of these integers are passed from wasm as their little endian bytes, but the host
+		// returns them SCALE encoded. So, they can not be converted back on the host.
+		if is_passed_by_width::<T::SelfInstance>() {
+			return
+		}
+
+		// `#[compressed]` arguments are compressed by the wasm side, but the host converts their
+		// `Vec<u8>` without compressing it.
+		if TypeId::of::<T>() == TypeId::of::<Compressed>() {
+			return
+		}
+
+		quickcheck::quickcheck(roundtrip::<T> as fn(T::SelfInstance) -> bool)
+	}
+}
+
+/// The check of the types that don't support [`CheckRoundtrip`].
+pub trait SkipRoundtrip {
+	/// Does nothing.
+	fn check(&self);
+}
+
+impl<T: ?Sized> SkipRoundtrip for &Roundtrip<T> {
+	fn check(&self) {}
+}
+
+/// Converts `value` into its ffi value and back and returns if this gives `value` again.
+fn roundtrip<T>(value: T::SelfInstance) -> bool where
+	T: FromFFIValue + ?Sized,
+	T::SelfInstance: IntoFFIValue + RIType<FFIType = T::FFIType> + Clone + PartialEq,
+{
+	let mut context = HostSimulator::new();
+
+	match value.clone().into_ffi_value(&mut context) {
+		Ok(ffi_value) => T::from_ffi_value(&mut context, ffi_value).map_or(false, |v| v == value),
+		Err(_) => false,
+	}
+}
+
+/// Returns if `T` is a `Vec` of integers that is passed as the little endian bytes of its
+/// elements.
+fn is_passed_by_width<T: 'static>() -> bool {
+	let id = TypeId::of::<T>();
+
+	id == TypeId::of::<Vec<u16>>() || id == TypeId::of::<Vec<u32>>() ||
+		id == TypeId::of::<Vec<u64>>()
+}
@@ -41,9 +41,23 @@ pub fn unpack_ptr_and_len(val: u64) -> (u32, u32) {
 	(ptr, len)
 }
 
+/// Converts the given length into an `u32`.
+///
+/// Values that do not fit into an `u32` would be truncated silently. To catch bugs early, this
+/// panics with `debug_assertions` enabled instead.
+pub fn usize_to_u32(value: usize) -> u32 {
+	debug_assert!(
+		value <= u32::max_value() as usize,
+		"`{}` does not fit into the 32 bit FFI value",
+		value,
+	);
+
+	value as u32
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{pack_ptr_and_len, unpack_ptr_and_len};
+	use super::{pack_ptr_and_len, unpack_ptr_and_len, usize_to_u32};
 
 	#[test]
 	fn ptr_len_packing_unpacking() {
@@ -56,4 +70,16 @@ mod tests {
 		assert_eq!(PTR, ptr);
 		assert_eq!(LEN, len);
 	}
+	#[test]
+	fn usize_to_u32_keeps_values_that_fit() {
+		assert_eq!(0, usize_to_u32(0));
+		assert_eq!(u32::max_value(), usize_to_u32(u32::max_value() as usize));
+	}
+
+	#[test]
+	#[cfg(all(debug_assertions, target_pointer_width = "64"))]
+	#[should_panic(expected = "does not fit into the 32 bit FFI value")]
+	fn usize_to_u32_panics_on_overflow() {
+		usize_to_u32(u32::max_value() as usize + 1);
+	}
 }
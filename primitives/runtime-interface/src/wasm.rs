@@ -16,9 +16,13 @@
 
 //! Traits required by the runtime interface from the wasm side.
 
-use crate::RIType;
+use crate::{RIType, util::{pack_ptr_and_len, usize_to_u32}};
 
-use sp_std::cell::Cell;
+use sp_std::{cell::Cell, marker::PhantomData, ptr, slice, vec::Vec};
+
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use codec::{Encode, Output};
 
 /// Something that can be created from a ffi value.
 ///
@@ -76,6 +80,35 @@ impl<T, O> From<(T, O)> for WrappedFFIValue<T, O> {
 	}
 }
 
+/// Represents a wrapped `#[out] &mut Vec<u8>` argument.
+///
+/// The pointer to the packed pointer and length of the vector is passed as ffi value to the host.
+/// The host may replace the packed pointer and length with the new content of the vector, which is
+/// written back into the vector by [`WrappedOutVec::write_back`].
+pub struct WrappedOutVec<'a> {
+	vec: &'a mut Vec<u8>,
+	ptr_and_len: Cell<u64>,
+}
+
+impl<'a> WrappedOutVec<'a> {
+	/// Wrap the given vector.
+	pub fn new(vec: &'a mut Vec<u8>) -> Self {
+		let ptr_and_len = Cell::new(pack_ptr_and_len(vec.as_ptr() as u32, usize_to_u32(vec.len())));
+
+		Self { vec, ptr_and_len }
+	}
+
+	/// Returns the ffi value, the pointer to the packed pointer and length of the vector.
+	pub fn get(&self) -> u32 {
+		self.ptr_and_len.as_ptr() as u32
+	}
+
+	/// Write the vector that was given back by the host into the wrapped vector.
+	pub fn write_back(self) {
+		*self.vec = <Vec<u8> as FromFFIValue>::from_ffi_value(self.ptr_and_len.get());
+	}
+}
+
 /// The state of an exchangeable function.
 #[derive(Clone, Copy)]
 enum ExchangeableFunctionState {
@@ -128,6 +161,210 @@ impl<T: Copy> ExchangeableFunction<T> {
 	}
 }
 
+/// Allocates the buffers of the encoded arguments, see [`set_argument_allocator`].
+pub struct ArgumentAllocator {
+	/// Allocate a buffer of the given number of bytes, which is never `0`.
+	pub allocate: fn(len: usize) -> *mut u8,
+	/// Free a buffer that was returned by `allocate` for the given number of bytes.
+	pub deallocate: fn(ptr: *mut u8, len: usize),
+}
+
+/// The allocator set with [`set_argument_allocator`] or null to use a `Vec`.
+static ARGUMENT_ALLOCATOR: AtomicPtr<ArgumentAllocator> = AtomicPtr::new(ptr::null_mut());
+
+/// Set the allocator of the buffers that hold the arguments while they are passed to the host.
+///
+/// Arguments that are passed SCALE encoded (for example a `Vec<T>` of a non integer `T` or a
+/// [`Codec`](crate::pass_by::Codec) type) are encoded into a buffer that lives as long as the
+/// call into the host. By default this buffer is a `Vec<u8>`. Arguments that are passed as
+/// pointer to their own memory, like `&[u8]`, don't need a buffer and never call the allocator.
+///
+/// `None` resets to the default.
+pub fn set_argument_allocator(allocator: Option<&'static ArgumentAllocator>) {
+	let allocator = allocator.map_or(ptr::null_mut(), |a| a as *const _ as *mut _);
+	ARGUMENT_ALLOCATOR.store(allocator, Ordering::Relaxed);
+}
+
+/// The buffer that holds a SCALE encoded argument while it is passed to the host.
+///
+/// Allocated with the allocator set by [`set_argument_allocator`].
+pub struct ArgumentBuffer(ArgumentBufferInner);
+
+enum ArgumentBufferInner {
+	Vec(Vec<u8>),
+	Allocated { ptr: *mut u8, len: usize, allocator: &'static ArgumentAllocator },
+}
+
+impl ArgumentBuffer {
+	/// Encode `value` into a new buffer.
+	pub fn encode<T: Encode + ?Sized>(value: &T) -> Self {
+		let allocator = ARGUMENT_ALLOCATOR.load(Ordering::Relaxed);
+		if allocator.is_null() {
+			return Self(ArgumentBufferInner::Vec(value.encode()))
+		}
+		// The pointer is only set from a `&'static ArgumentAllocator`; qed
+		let allocator = unsafe { &*allocator };
+
+		// Encode once to get the length, so the buffer is allocated with the exact size.
+		let mut counter = CountingOutput(0);
+		value.encode_to(&mut counter);
+		let len = counter.0;
+		if len == 0 {
+			return Self(ArgumentBufferInner::Vec(Vec::new()))
+		}
+
+		let ptr = (allocator.allocate)(len);
+		// The allocator returns a buffer of `len` bytes; qed
+		let mut output = SliceOutput(unsafe { slice::from_raw_parts_mut(ptr, len) });
+		value.encode_to(&mut output);
+
+		Self(ArgumentBufferInner::Allocated { ptr, len, allocator })
+	}
+
+	/// Returns the ffi value that points to the buffer.
+	pub fn ffi_value(&self) -> u64 {
+		let (ptr, len) = match &self.0 {
+			ArgumentBufferInner::Vec(data) => (data.as_ptr(), data.len()),
+			ArgumentBufferInner::Allocated { ptr, len, .. } => (*ptr as *const u8, *len),
+		};
+
+		pack_ptr_and_len(ptr as u32, usize_to_u32(len))
+	}
+}
+
+impl Drop for ArgumentBuffer {
+	fn drop(&mut self) {
+		if let ArgumentBufferInner::Allocated { ptr, len, allocator } = self.0 {
+			(allocator.deallocate)(ptr, len);
+		}
+	}
+}
+
+/// The scratch region of the `#[no_alloc(max = N)]` method that is currently called.
+#[derive(Clone, Copy)]
+struct Scratch {
+	/// The name of the method, for the panic message.
+	name: &'static str,
+	start: *mut u8,
+	len: usize,
+	/// The number of bytes that are handed out.
+	used: usize,
+}
+
+/// The [`Scratch`] set by [`ScratchArguments::enter`].
+struct ScratchRegion(Cell<Option<Scratch>>);
+
+// Wasm does not support threads, so this is safe; qed.
+unsafe impl Sync for ScratchRegion {}
+
+static SCRATCH_REGION: ScratchRegion = ScratchRegion(Cell::new(None));
+
+/// The allocator that hands out the scratch region of a `#[no_alloc(max = N)]` method.
+static SCRATCH_ALLOCATOR: ArgumentAllocator = ArgumentAllocator {
+	allocate: allocate_scratch,
+	deallocate: deallocate_scratch,
+};
+
+fn allocate_scratch(len: usize) -> *mut u8 {
+	let mut scratch = SCRATCH_REGION.0.get()
+		.expect("The scratch allocator is only set while a scratch region is entered; qed");
+
+	let used = scratch.used.saturating_add(len);
+	if used > scratch.len {
+		no_alloc_exceeded(scratch.name, used, scratch.len)
+	}
+
+	// The region is at least `used` bytes long; qed
+	let ptr = unsafe { scratch.start.add(scratch.used) };
+	scratch.used = used;
+	SCRATCH_REGION.0.set(Some(scratch));
+
+	ptr
+}
+
+/// The scratch region is handed out once per call and is not reused.
+fn deallocate_scratch(_: *mut u8, _: usize) {}
+
+/// Passes the encoded arguments of a `#[no_alloc(max = N)]` method in a fixed scratch region,
+/// until it is dropped.
+///
+/// The allocator set with [`set_argument_allocator`] is replaced by an allocator that hands out
+/// the given region and panics if the encoded arguments need more bytes than it has.
+#[doc(hidden)]
+pub struct ScratchArguments<'a> {
+	previous: Option<Scratch>,
+	previous_allocator: *mut ArgumentAllocator,
+	_scratch: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> ScratchArguments<'a> {
+	/// Enter the `scratch` region for the arguments of the method with the given `name`.
+	pub fn enter(name: &'static str, scratch: &'a mut [u8]) -> Self {
+		let region = Scratch { name, start: scratch.as_mut_ptr(), len: scratch.len(), used: 0 };
+		let previous = SCRATCH_REGION.0.replace(Some(region));
+		let previous_allocator = ARGUMENT_ALLOCATOR.swap(
+			&SCRATCH_ALLOCATOR as *const _ as *mut _,
+			Ordering::Relaxed,
+		);
+
+		Self { previous, previous_allocator, _scratch: PhantomData }
+	}
+}
+
+impl Drop for ScratchArguments<'_> {
+	fn drop(&mut self) {
+		ARGUMENT_ALLOCATOR.store(self.previous_allocator, Ordering::Relaxed);
+		SCRATCH_REGION.0.set(self.previous);
+	}
+}
+
+/// Panics because the encoded arguments of the `#[no_alloc(max = N)]` method with the given
+/// `name` need more than `max` bytes.
+#[cold]
+fn no_alloc_exceeded(name: &str, len: usize, max: usize) -> ! {
+	panic!(
+		"The encoded arguments of `{0}` need at least {1} bytes, more than the {2} bytes of \
+		`#[no_alloc(max = {2})]`",
+		name,
+		len,
+		max,
+	)
+}
+
+/// Counts the encoded bytes.
+struct CountingOutput(usize);
+
+impl Output for CountingOutput {
+	fn write(&mut self, bytes: &[u8]) {
+		self.0 += bytes.len();
+	}
+}
+
+/// Writes the encoded bytes into a slice of the exact encoded length.
+struct SliceOutput<'a>(&'a mut [u8]);
+
+impl Output for SliceOutput<'_> {
+	fn write(&mut self, bytes: &[u8]) {
+		let (head, tail) = sp_std::mem::take(&mut self.0).split_at_mut(bytes.len());
+		head.copy_from_slice(bytes);
+		self.0 = tail;
+	}
+}
+
+/// Asserts at compile time that `T` can be used as argument type on the wasm side.
+///
+/// Called by `#[runtime_interface]` for every argument type, so a missing [`IntoFFIValue`]
+/// implementation is reported at the argument type.
+#[doc(hidden)]
+pub fn assert_argument_type<T: IntoFFIValue + ?Sized>() {}
+
+/// Asserts at compile time that `T` can be used as return type on the wasm side.
+///
+/// Called by `#[runtime_interface]` for every return type, so a missing [`FromFFIValue`]
+/// implementation is reported at the return type.
+#[doc(hidden)]
+pub fn assert_return_type<T: FromFFIValue>() {}
+
 // Wasm does not support threads, so this is safe; qed.
 unsafe impl<T> Sync for ExchangeableFunction<T> {}
 
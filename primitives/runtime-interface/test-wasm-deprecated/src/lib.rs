@@ -40,6 +40,30 @@ pub trait TestApi {
 	}
 }
 
+/// The api of a runtime that was built on another branch, which released version 2.
+#[runtime_interface]
+pub trait TestApiSkipVersion {
+	fn skipped_version() -> u32 {
+		// should not be called
+		unimplemented!()
+	}
+
+	#[version(2)]
+	fn skipped_version() -> u32 {
+		// should not be called
+		unimplemented!()
+	}
+}
+
+/// The api of a runtime that was built before version 2 added the `factor` argument.
+#[runtime_interface]
+pub trait TestApiDefaultArgument {
+	fn scale(_value: u32) -> u32 {
+		// should not be called
+		unimplemented!()
+	}
+}
+
 wasm_export_functions! {
 	fn test_versionning_works() {
 		// old api allows only 42 and 50
@@ -49,4 +73,14 @@ wasm_export_functions! {
 		assert!(!test_api::test_versionning(142));
 		assert!(!test_api::test_versionning(0));
 	}
-}
\ No newline at end of file
+
+	fn test_skipped_version() {
+		// Version 2 is skipped by the new api, so this traps.
+		test_api_skip_version::skipped_version();
+	}
+
+	fn test_default_argument() {
+		// Version 1 is implemented by version 2 with the default factor.
+		assert_eq!(42, test_api_default_argument::scale(21));
+	}
+}
@@ -18,13 +18,27 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use sp_runtime_interface::runtime_interface;
+use sp_runtime_interface::{
+	runtime_interface, NoExternalities, pass_by::{PassByCodec, PassByInner},
+};
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
 
 #[cfg(not(feature = "std"))]
 use sp_std::{prelude::*, mem, convert::TryFrom};
 
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
 use sp_core::{sr25519::Public, wasm_export_functions};
 
+use sp_std::{borrow::Cow, collections::btree_map::BTreeMap, marker::PhantomData};
+
+use codec::{Encode, Decode};
+
+use core::time::Duration;
+
 // Include the WASM binary
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -32,13 +46,95 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 /// Used in the `test_array_as_mutable_reference` test.
 const TEST_ARRAY: [u8; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
 
-#[runtime_interface]
+#[cfg(feature = "std")]
+std::thread_local! {
+	/// The data of the hashers created by `create_hasher`, indexed by their handle.
+	static HASHERS: std::cell::RefCell<Vec<Vec<u8>>> = Default::default();
+
+	/// The number of calls of [`TestApiWrapped`] that were wrapped by `count_call`.
+	pub static WRAPPED_CALLS: std::cell::Cell<u32> = Default::default();
+}
+
+/// A custom struct that is passed by reference to the runtime interface.
+///
+/// It doesn't implement `Clone` to check that passing it by reference doesn't require a copy.
+#[derive(PassByCodec, Encode, Decode, Debug, PartialEq)]
+pub struct Point {
+	/// The x coordinate.
+	pub x: u32,
+	/// The y coordinate.
+	pub y: u32,
+}
+
+/// A small `Copy` struct that is passed by its inner `u32`, unless `#[pass_by(codec)]` is given.
+#[derive(PassByInner, Encode, Decode, Clone, Copy, Debug, PartialEq)]
+pub struct Counter(pub u32);
+
+/// An operation that is passed in a list of operations to the runtime interface.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub enum Op {
+	/// Add the value.
+	Add(u32),
+	/// Multiply by the value.
+	Mul(u32),
+	/// Set to zero.
+	Reset,
+}
+
+/// A domain error with data that is returned in a `Result` by the runtime interface.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub enum DomainError {
+	/// The given data is empty.
+	Empty,
+	/// The given data is rejected with `code` and `reason`.
+	Rejected {
+		/// The code of the rejection.
+		code: u32,
+		/// The reason of the rejection.
+		reason: Vec<u8>,
+	},
+}
+
+/// The runtime interface that is tested.
+///
+/// `missing_docs` is denied to check that the docs of the trait methods are put on the generated
+/// functions.
+#[runtime_interface(mockable, debug_args, error_enum, ffi_size, benches)]
+#[deny(missing_docs)]
 pub trait TestApi {
+	/// The version of the protocol, readable without calling into the host.
+	const PROTOCOL_VERSION: u32 = 3;
+
 	/// Returns the input data as result.
 	fn return_input(data: Vec<u8>) -> Vec<u8> {
 		data
 	}
 
+	/// Returns the input data as result, but uses a custom tracing span name.
+	#[span_name = "custom_span_name"]
+	fn return_input_with_span_name(data: Vec<u8>) -> Vec<u8> {
+		data
+	}
+
+	/// Returns the input data as result, but without entering a tracing span.
+	#[no_tracing]
+	fn return_input_without_tracing(data: Vec<u8>) -> Vec<u8> {
+		data
+	}
+
+	/// Returns the input data as result, but the host function is exported under another name.
+	#[export_name = "return_input_legacy"]
+	fn return_input_with_export_name(data: Vec<u8>) -> Vec<u8> {
+		data
+	}
+
+	/// Returns the bytes `data` refers to. Takes a trait object, so it is only callable natively.
+	#[cfg(feature = "std")]
+	#[native_only]
+	fn native_only_bytes(data: &dyn AsRef<[u8]>) -> Vec<u8> {
+		data.as_ref().to_vec()
+	}
+
 	/// Returns 16kb data.
 	///
 	/// # Note
@@ -50,6 +146,11 @@ pub trait TestApi {
 		vec![0; 4 * 1024]
 	}
 
+	/// Returns `len` bytes, every byte set to its index modulo `256`.
+	fn return_large_buffer(len: u32) -> Vec<u8> {
+		(0..len).map(|i| i as u8).collect()
+	}
+
 	/// Set the storage at key with value.
 	fn set_storage(&mut self, key: &[u8], data: &[u8]) {
 		self.place_storage(key.to_vec(), Some(data.to_vec()));
@@ -66,6 +167,86 @@ pub trait TestApi {
 		Some(data)
 	}
 
+	/// Returns the given optional data as result.
+	fn return_option_vec(data: Option<Vec<u8>>) -> Option<Vec<u8>> {
+		data
+	}
+
+	/// Returns `count` small chunks, passed as one buffer with a table of their offsets.
+	#[pass_by(chunks)]
+	fn return_chunks(count: u32) -> Vec<Vec<u8>> {
+		(0..count).map(|i| vec![i as u8; (i % 16) as usize]).collect()
+	}
+
+	/// Returns the same chunks as `return_chunks`, but SCALE encoded.
+	#[pass_by(codec)]
+	fn return_chunks_by_codec(count: u32) -> Vec<Vec<u8>> {
+		(0..count).map(|i| vec![i as u8; (i % 16) as usize]).collect()
+	}
+
+	/// Returns `count` mixed booleans, passed as a bitmap.
+	#[pass_by(bitmap)]
+	fn return_bools(count: u32) -> Vec<bool> {
+		(0..count).map(|i| i % 3 == 0 || i % 7 == 0).collect()
+	}
+
+	/// Returns the same booleans as `return_bools`, but SCALE encoded.
+	#[pass_by(codec)]
+	fn return_bools_by_codec(count: u32) -> Vec<bool> {
+		(0..count).map(|i| i % 3 == 0 || i % 7 == 0).collect()
+	}
+
+	/// Returns the number of chunks and the sum of their lengths.
+	fn count_chunks(#[pass_by(chunks)] chunks: Vec<Vec<u8>>) -> (u32, u32) {
+		(chunks.len() as u32, chunks.iter().map(|chunk| chunk.len() as u32).sum())
+	}
+
+	/// Returns the given slices as owned chunks.
+	fn return_slices(slices: &[&[u8]]) -> Vec<Vec<u8>> {
+		slices.iter().map(|slice| slice.to_vec()).collect()
+	}
+
+	/// Returns the first occurrence of `needle` in `haystack` as a slice of `haystack`.
+	fn find<'a>(haystack: &'a [u8], needle: &[u8]) -> Option<&'a [u8]> {
+		if needle.is_empty() {
+			return Some(&haystack[..0])
+		}
+
+		haystack.windows(needle.len())
+			.position(|window| window == needle)
+			.map(|pos| &haystack[pos..pos + needle.len()])
+	}
+
+	/// Returns `data` without its leading zeros as a slice of `data`.
+	fn trim_leading_zeros<'a>(data: &'a [u8]) -> &'a [u8] {
+		let start = data.iter().position(|byte| *byte != 0).unwrap_or(data.len());
+		&data[start..]
+	}
+
+	/// Returns the given data, that was compressed by the wasm side.
+	fn return_compressed_input(#[compressed] data: &[u8]) -> Vec<u8> {
+		data.to_vec()
+	}
+
+	/// Returns the hash that repeats the first byte of `data`, if there is one.
+	fn maybe_hash(data: &[u8]) -> Option<[u8; 32]> {
+		data.first().map(|byte| [*byte; 32])
+	}
+
+	/// Returns `data` if it starts with `1` and otherwise the matching `DomainError`.
+	fn check_data(data: Vec<u8>) -> Result<Vec<u8>, DomainError> {
+		match data.first() {
+			None => Err(DomainError::Empty),
+			Some(1) => Ok(data),
+			Some(code) => Err(DomainError::Rejected { code: *code as u32, reason: data.clone() }),
+		}
+	}
+
+	/// Returns the given result.
+	fn return_result(result: Result<Vec<u8>, DomainError>) -> Result<Vec<u8>, DomainError> {
+		result
+	}
+
 	/// Get an array as input and returns a subset of this array.
 	fn get_and_return_array(data: [u8; 34]) -> [u8; 16] {
 		let mut res = [0u8; 16];
@@ -73,6 +254,13 @@ pub trait TestApi {
 		res
 	}
 
+	/// Returns if `pubkey` is the blake2 256-bit hash of `msg`.
+	///
+	/// Stands in for verifying a signature, to test an array that is passed by value.
+	fn verify(pubkey: [u8; 32], msg: &[u8]) -> bool {
+		sp_core::hashing::blake2_256(msg) == pubkey
+	}
+
 	/// Take and fill mutable array.
 	fn array_as_mutable_reference(data: &mut [u8; 16]) {
 		data.copy_from_slice(&TEST_ARRAY);
@@ -88,6 +276,11 @@ pub trait TestApi {
 	/// This also checks that we accept `_` (wild card) argument names.
 	fn invalid_utf8_data(_: &str) {}
 
+	/// Returns the number of chars of `data` and its UTF-8 bytes.
+	fn inspect_string(data: String) -> (u32, Vec<u8>) {
+		(data.chars().count() as u32, data.into_bytes())
+	}
+
 	/// Overwrite the native implementation in wasm. The native implementation always returns
 	/// `false` and the replacement function will return always `true`.
 	fn overwrite_native_function_implementation() -> bool {
@@ -104,14 +297,580 @@ pub trait TestApi {
 		val
 	}
 
+	/// Returns the sum of the given `u128` values.
+	fn big_add(a: u128, b: u128) -> u128 {
+		a + b
+	}
+
+	/// A function that is never compiled, to check that `#[cfg]` is forwarded to all generated
+	/// items.
+	#[cfg(any())]
+	fn never_compiled(&self, data: &[u8]) -> u32 {
+		data.len() as u32
+	}
+
+	/// Returns if the sum of the bytes of `data` is equal to `sum`.
+	///
+	/// Also generates `check_sum_batch` to check multiple sums with one call into the host.
+	#[batch]
+	fn check_sum(data: &[u8], sum: u32) -> bool {
+		data.iter().map(|b| *b as u32).sum::<u32>() == sum
+	}
+
+	/// Returns the blake2 256-bit hash of the given data.
+	fn blake(data: &[u8]) -> [u8; 32] {
+		sp_core::hashing::blake2_256(data)
+	}
+
+	/// Creates a hasher on the host and returns its handle.
+	#[handle(Hasher)]
+	fn create_hasher() -> u32 {
+		HASHERS.with(|hashers| {
+			let mut hashers = hashers.borrow_mut();
+			hashers.push(Vec::new());
+			hashers.len() as u32 - 1
+		})
+	}
+
+	/// Feeds `data` into the given hasher.
+	fn hasher_update(hasher: HasherHandle, data: &[u8]) {
+		HASHERS.with(|hashers| hashers.borrow_mut()[hasher.0 as usize].extend_from_slice(data))
+	}
+
+	/// Returns the blake2 256-bit hash of all data that was fed into the given hasher.
+	fn hasher_finish(hasher: HasherHandle) -> [u8; 32] {
+		HASHERS.with(|hashers| sp_core::hashing::blake2_256(&hashers.borrow()[hasher.0 as usize]))
+	}
+
+	/// Returns the sum of all bytes in `data`.
+	fn sum_bytes(data: Vec<u8>) -> u32 {
+		data.iter().map(|b| *b as u32).sum()
+	}
+
+	/// Returns the sum of all bytes in `data`.
+	///
+	/// Only takes `data` as a slice, the implementation of version 1 is reused.
+	#[version(2, alias = 1)]
+	fn sum_bytes(data: &[u8]) -> u32;
+
+	/// Returns `[4, 5, 6]` as owned vector if `owned` is set, otherwise `[1, 2, 3]` as slice.
+	fn return_cow(owned: bool) -> Cow<'static, [u8]> {
+		if owned {
+			Cow::Owned(vec![4, 5, 6])
+		} else {
+			Cow::Borrowed(&[1, 2, 3])
+		}
+	}
+
+	/// Applies all `ops` to `value` in order and returns the result.
+	///
+	/// The list of operations is encoded as one buffer.
+	fn apply_ops(value: u32, ops: &[Op]) -> u32 {
+		ops.iter().fold(value, |value, op| match op {
+			Op::Add(x) => value.wrapping_add(*x),
+			Op::Mul(x) => value.wrapping_mul(*x),
+			Op::Reset => 0,
+		})
+	}
+
+	/// Returns the keys of `map` in the order they are iterated and all values concatenated.
+	fn keys_and_values(map: BTreeMap<u32, Vec<u8>>) -> (Vec<u32>, Vec<u8>) {
+		let keys = map.keys().copied().collect();
+		let values = map.into_iter().flat_map(|(_, v)| v).collect();
+		(keys, values)
+	}
+
+	/// Returns `data` as vector, the slice is passed with the width of `u32` to the host.
+	fn return_u32_slice(#[pass_by(width)] data: &[u32]) -> Vec<u32> {
+		data.to_vec()
+	}
+
+	/// Returns `data` as vector, the slice is passed with the width of `u64` to the host.
+	fn return_u64_slice(#[pass_by(width)] data: &[u64]) -> Vec<u64> {
+		data.to_vec()
+	}
+
+	/// Returns the sum of `data`, the vector is passed with the width of `u16` to the host.
+	fn sum_u16_vec(#[pass_by(width)] data: Vec<u16>) -> u32 {
+		data.iter().map(|v| *v as u32).sum()
+	}
+
+	/// Returns the sum of `a` and `b`, truncated to 32 bits.
+	fn add(a: u32, b: u64) -> u32 {
+		(a as u64 + b) as u32
+	}
+
+	/// Splits `x` into the upper and the lower 32 bits.
+	fn split(x: u64) -> (u32, u32) {
+		((x >> 32) as u32, x as u32)
+	}
+
+	/// Returns `x` split into the upper and the lower 32 bits and the number of set bits.
+	fn split_and_count_ones(x: u64) -> ((u32, u32), u8) {
+		(((x >> 32) as u32, x as u32), x.count_ones() as u8)
+	}
+
+	/// Returns the empty tuple.
+	#[allow(clippy::unused_unit)]
+	fn return_empty_tuple() -> () {
+		()
+	}
+
+	/// Returns the squared distance of the given `point` to the origin.
+	fn squared_distance(point: &Point) -> u64 {
+		point.x as u64 * point.x as u64 + point.y as u64 * point.y as u64
+	}
+
+	/// Returns the squared distance of the given `point` to the origin. The encoded `point` is
+	/// passed in a scratch region on the stack.
+	#[no_alloc(max = 8)]
+	fn scratch_squared_distance(point: &Point) -> u64 {
+		point.x as u64 * point.x as u64 + point.y as u64 * point.y as u64
+	}
+
+	/// Returns the number of points, but the scratch region is too small for more than one point.
+	#[no_alloc(max = 12)]
+	fn scratch_count_points(points: Vec<Point>) -> u32 {
+		points.len() as u32
+	}
+
+	/// Returns the sum of `a`, `b` and the length of `c`.
+	fn sum_of_arguments(a: u32, b: u64, c: &[u8]) -> u64 {
+		a as u64 + b + c.len() as u64
+	}
+
+	/// Returns the sum of `a` and `b`.
+	///
+	/// The `PhantomData` is zero sized and not passed to the host.
+	fn add_with_marker(a: u32, _marker: PhantomData<Point>, b: u32) -> u32 {
+		a + b
+	}
+
+	/// Returns the squares of all numbers below `count`.
+	///
+	/// The squares are only calculated when they are read by the runtime.
+	#[stream]
+	fn squares(count: u32) -> impl Iterator<Item = u64> {
+		(0..count as u64).map(|i| i * i)
+	}
+
+	/// Returns the given `duration` plus one and a half seconds.
+	fn add_one_and_a_half_seconds(duration: Duration) -> Duration {
+		duration + Duration::from_millis(1500)
+	}
+
+	/// Returns the sum of all nine arguments.
+	///
+	/// Checks that the generated functions with many arguments don't trigger clippy lints.
+	fn sum_of_nine(
+		a: u8,
+		b: u16,
+		c: u32,
+		d: u64,
+		e: u8,
+		f: u16,
+		g: u32,
+		h: u64,
+		i: &[u8],
+	) -> u64 {
+		a as u64 + b as u64 + c as u64 + d + e as u64 + f as u64 + g as u64 + h + i.len() as u64
+	}
+
+	/// Returns the value of the given `counter`.
+	fn counter_value(counter: Counter) -> u32 {
+		counter.0
+	}
+
+	/// Returns the given `counter` incremented by one.
+	///
+	/// The counter is passed SCALE encoded in both directions.
+	#[pass_by(codec)]
+	fn increment_counter(#[pass_by(codec)] counter: Counter) -> Counter {
+		Counter(counter.0 + 1)
+	}
+
+	/// Appends `[4, 5, 6]` to the given `data`.
+	fn append_to_vec(#[out] data: &mut Vec<u8>) {
+		data.extend_from_slice(&[4, 5, 6]);
+	}
+
+	/// Returns the input if it is even, otherwise an error.
+	fn validate(&self, x: u32) -> Result<u32, ()> {
+		if x % 2 == 0 {
+			Ok(x)
+		} else {
+			Err(())
+		}
+	}
+
+	/// Returns the called version.
+	fn called_version() -> u32 {
+		1
+	}
+
+	/// Returns the called version.
+	#[version(2)]
+	fn called_version() -> u32 {
+		2
+	}
+
+	/// Returns the called version.
+	///
+	/// This is the minimum version, versions 1 and 2 are only registered for old runtimes.
+	#[version(3, min = true)]
+	fn called_version() -> u32 {
+		3
+	}
+
+	/// Returns the called version.
+	///
+	/// This version is only registered on the host, the runtime still calls version 3.
+	#[version(4, register_only)]
+	fn called_version() -> u32 {
+		4
+	}
+
+	/// Returns if the given `data` is `42` or `50`.
 	fn test_versionning(&self, data: u32) -> bool {
 		data == 42 || data == 50
 	}
 
+	/// Returns if the given `data` is `42`.
 	#[version(2)]
 	fn test_versionning(&self, data: u32) -> bool {
 		data == 42
 	}
+
+	/// Returns if the given `data` is `42` or `60`.
+	///
+	/// This version is only registered on the host, the runtime still calls version 2.
+	#[version(3, register_only)]
+	fn test_versionning(&self, data: u32) -> bool {
+		data == 42 || data == 60
+	}
+}
+
+/// A wasm only interface for testing.
+#[runtime_interface(wasm_only, inline_wasm)]
+pub trait TestApiWasmOnly {
+	/// Returns the index of the given `callback`.
+	fn callback_index(&mut self, #[callback] callback: u32) -> u32 {
+		callback.index()
+	}
+
+	/// Returns the sum of the bytes of `data`, read directly from the wasm memory.
+	fn raw_slice_sum(&mut self, #[pass_by(raw_ptr)] data: &[u8]) -> u32 {
+		let mut sum = 0;
+		for i in 0..data.len {
+			let mut byte = [0u8; 1];
+			self.read_memory_into(data.ptr.offset(i).expect("Offset is in bounds"), &mut byte)
+				.expect("`data` is valid wasm memory");
+			sum += byte[0] as u32;
+		}
+		sum
+	}
+}
+
+/// An interface with native and wasm only methods for testing.
+#[runtime_interface]
+pub trait TestApiMixed {
+	/// Returns the value stored under `key`.
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.storage(key)
+	}
+
+	/// Returns the length of `data`, as seen in the wasm memory.
+	#[wasm_only]
+	fn raw_slice_len(#[pass_by(raw_ptr)] data: &[u8]) -> u32 {
+		data.len
+	}
+
+	/// Returns the first byte of `data`, read directly from the wasm memory.
+	#[wasm_only]
+	fn raw_slice_first(&mut self, #[pass_by(raw_ptr)] data: &[u8]) -> Option<u8> {
+		if data.len == 0 {
+			return None
+		}
+
+		let mut byte = [0u8; 1];
+		self.read_memory_into(data.ptr, &mut byte).expect("`data` is valid wasm memory");
+		Some(byte[0])
+	}
+}
+
+/// Returns the double of `x`, after yielding once to the executor.
+#[cfg(feature = "std")]
+async fn double_later(x: u32) -> u32 {
+	let mut yielded = false;
+	futures::future::poll_fn(|cx| if yielded {
+		futures::task::Poll::Ready(x * 2)
+	} else {
+		yielded = true;
+		cx.waker().wake_by_ref();
+		futures::task::Poll::Pending
+	}).await
+}
+
+/// An interface with `async` implementations for testing.
+#[runtime_interface(async)]
+pub trait TestApiAsync {
+	/// Returns the double of `x`.
+	fn double(x: u32) -> u32 {
+		double_later(x).await
+	}
+
+	/// Returns the value stored under `key`, doubled.
+	fn double_storage(&self, key: &[u8]) -> Option<u32> {
+		let value = u32::decode(&mut &self.storage(key)?[..]).ok()?;
+		Some(double_later(value).await)
+	}
+}
+
+/// An interface that records its calls for testing.
+#[runtime_interface(record)]
+pub trait TestApiRecord {
+	/// Returns the sum of `a` and `b`.
+	fn sum(a: u32, b: u32) -> u32 {
+		a + b
+	}
+
+	/// Returns the length of `data`.
+	fn length(data: &[u8]) -> u32 {
+		data.len() as u32
+	}
+}
+
+/// An interface that reports its calls to the installed audit sink for testing.
+#[runtime_interface(audit)]
+pub trait TestApiAudit {
+	/// Returns the product of `a` and `b`.
+	fn product(a: u32, b: u32) -> u32 {
+		a * b
+	}
+
+	/// Returns the first byte of `data`.
+	fn first(data: &[u8]) -> Option<u8> {
+		data.first().copied()
+	}
+}
+
+/// An interface that prefixes the exported names of its host functions with `crypto`.
+#[runtime_interface(prefix = "crypto")]
+pub trait TestApiPrefix {
+	/// Returns the XOR of all bytes of `data`.
+	fn checksum(data: &[u8]) -> u8 {
+		data.iter().fold(0, |checksum, byte| checksum ^ byte)
+	}
+}
+
+/// An interface with a skipped version.
+#[runtime_interface]
+pub trait TestApiSkipVersion {
+	/// Returns the called version.
+	///
+	/// Version 2 was only released on another branch and is skipped.
+	#[skip_version(2)]
+	fn skipped_version() -> u32 {
+		1
+	}
+
+	/// Returns the called version.
+	#[version(3)]
+	fn skipped_version() -> u32 {
+		3
+	}
+}
+
+/// Re-exports `sp-runtime-interface` under a different name, like a framework would do.
+pub mod framework {
+	pub use sp_runtime_interface as runtime_interface;
+}
+
+/// An interface that accesses `sp-runtime-interface` through the re-export in [`framework`].
+#[runtime_interface(crate = crate::framework::runtime_interface)]
+pub trait TestApiReexported {
+	/// Returns the given `data` reversed.
+	fn reverse(data: Vec<u8>) -> Vec<u8> {
+		data.into_iter().rev().collect()
+	}
+}
+
+/// An interface of pure functions that are called without any `Externalities`.
+#[runtime_interface(no_externalities)]
+pub trait TestApiPure {
+	/// Returns the product of `a` and `b`.
+	fn multiply(a: u32, b: u32) -> u64 {
+		a as u64 * b as u64
+	}
+}
+
+/// An interface with re-entrant calls, that are detected by the reentrancy guard.
+#[runtime_interface(reentrancy_guard)]
+pub trait TestApiReentrant {
+	/// Returns `depth` by calling itself recursively `depth` times.
+	fn countdown(depth: u32) -> u32 {
+		if depth == 0 {
+			0
+		} else {
+			countdown(depth - 1) + 1
+		}
+	}
+}
+
+/// The error returned by [`TestApiNoPanic`].
+#[derive(Encode, Decode, Debug, PartialEq)]
+pub enum StorageError {
+	/// The function was called without `Externalities`.
+	NoExternalities,
+}
+
+impl From<NoExternalities> for StorageError {
+	fn from(_: NoExternalities) -> Self {
+		StorageError::NoExternalities
+	}
+}
+
+/// An interface that returns an error instead of panicking without `Externalities`.
+#[runtime_interface(no_panic)]
+pub trait TestApiNoPanic {
+	/// Returns the value of `key` in the storage.
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+		Ok(self.storage(key))
+	}
+}
+
+/// An interface that is only registered on the host side with the `registration-only` feature.
+#[runtime_interface(registration_only)]
+pub trait TestApiRegistrationOnly {
+	/// Returns `data` in reverse order.
+	fn reverse(data: Vec<u8>) -> Vec<u8> {
+		data.into_iter().rev().collect()
+	}
+}
+
+/// An interface whose host functions can be disabled by their `disable_hostfn_<name>` feature.
+///
+/// The tests only enable `disable_hostfn_test_api_features_disabled`.
+#[runtime_interface(host_function_features)]
+pub trait TestApiFeatures {
+	/// Returns `1`.
+	fn enabled() -> u32 {
+		1
+	}
+
+	/// Returns `2`.
+	fn disabled() -> u32 {
+		2
+	}
+}
+
+/// An interface that added an argument with a default value in version 2.
+///
+/// All versions return the same type, which is checked by `strict_versioning`.
+#[runtime_interface(strict_versioning)]
+pub trait TestApiDefaultArgument {
+	/// Returns `value` multiplied by `2`, implemented by version 2.
+	fn scale(value: u32) -> u32;
+
+	/// Returns `value` multiplied by `factor`.
+	#[version(2)]
+	fn scale(value: u32, #[default_when_missing(2)] factor: u32) -> u32 {
+		value * factor
+	}
+}
+
+/// The function context of an alternative executor, which mirrors `FunctionContext`.
+#[cfg(feature = "std")]
+pub trait StubContext {
+	/// Returns the byte at `ptr` in the wasm memory.
+	fn read_byte(&mut self, ptr: sp_runtime_interface::sp_wasm_interface::Pointer<u8>) -> u8;
+}
+
+#[cfg(feature = "std")]
+impl StubContext for &mut dyn sp_runtime_interface::sp_wasm_interface::FunctionContext {
+	fn read_byte(&mut self, ptr: sp_runtime_interface::sp_wasm_interface::Pointer<u8>) -> u8 {
+		let mut byte = [0u8; 1];
+		self.read_memory_into(ptr, &mut byte).expect("`ptr` is valid wasm memory");
+		byte[0]
+	}
+}
+
+/// A wasm only interface that gets the function context as `StubContext`.
+#[runtime_interface(wasm_only, context = crate::StubContext)]
+pub trait TestApiCustomContext {
+	/// Returns the first byte of `data`, read through the `StubContext`.
+	fn first_byte(&mut self, #[pass_by(raw_ptr)] data: &[u8]) -> u8 {
+		self.read_byte(data.ptr)
+	}
+}
+
+/// Provides fake `Externalities` to [`TestApiStubExternalities`], instead of the `Externalities`
+/// of the executor.
+///
+/// The fake storage only contains `stub_key`.
+#[cfg(feature = "std")]
+pub fn stub_externalities<R>(
+	f: impl FnOnce(&mut dyn sp_runtime_interface::Externalities) -> R,
+) -> Option<R> {
+	let mut ext = sp_io::TestExternalities::new_empty();
+	ext.insert(b"stub_key".to_vec(), b"stub_value".to_vec());
+	let mut ext = ext.ext();
+	Some(f(&mut ext))
+}
+
+/// An interface that gets its `Externalities` from [`stub_externalities`].
+#[runtime_interface(with_externalities = crate::stub_externalities)]
+pub trait TestApiStubExternalities {
+	/// Returns the value of `key` in the storage of the fake `Externalities`.
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.storage(key)
+	}
+}
+
+/// Counts the calls of [`TestApiWrapped`] in [`WRAPPED_CALLS`].
+#[cfg(feature = "std")]
+pub fn count_call<R>(_function: &str, call: impl FnOnce() -> R) -> R {
+	WRAPPED_CALLS.with(|calls| calls.set(calls.get() + 1));
+	call()
+}
+
+/// An interface whose calls on the host side are wrapped by [`count_call`].
+#[runtime_interface(wrap = crate::count_call)]
+pub trait TestApiWrapped {
+	/// Returns the sum of `a` and `b`.
+	fn add(a: u32, b: u32) -> u32 {
+		a + b
+	}
+
+	/// Returns the number of calls wrapped by `count_call` so far, including this call.
+	fn wrapped_calls() -> u32 {
+		WRAPPED_CALLS.with(|calls| calls.get())
+	}
+}
+
+/// The argument allocator used in the `test_argument_allocator` test.
+///
+/// It counts the allocations and deallocations and allocates with a `Vec`.
+#[cfg(not(feature = "std"))]
+mod counting_allocator {
+	use super::*;
+	use core::sync::atomic::{AtomicUsize, Ordering};
+	use sp_runtime_interface::wasm::ArgumentAllocator;
+
+	pub static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+	pub static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+	pub static ALLOCATOR: ArgumentAllocator = ArgumentAllocator { allocate, deallocate };
+
+	fn allocate(len: usize) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		mem::ManuallyDrop::new(Vec::<u8>::with_capacity(len)).as_mut_ptr()
+	}
+
+	fn deallocate(ptr: *mut u8, len: usize) {
+		DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		drop(unsafe { Vec::from_raw_parts(ptr, 0, len) });
+	}
 }
 
 /// This function is not used, but we require it for the compiler to include `sp-io`.
@@ -129,6 +888,27 @@ wasm_export_functions! {
 		assert_eq!(input, res);
 	}
 
+	fn test_return_data_with_span_name() {
+		let input = vec![1, 2, 3, 4, 5, 6];
+		let res = test_api::return_input_with_span_name(input.clone());
+
+		assert_eq!(input, res);
+	}
+
+	fn test_return_data_without_tracing() {
+		let input = vec![1, 2, 3, 4, 5, 6];
+		let res = test_api::return_input_without_tracing(input.clone());
+
+		assert_eq!(input, res);
+	}
+
+	fn test_return_data_with_export_name() {
+		let input = vec![1, 2, 3, 4, 5, 6];
+		let res = test_api::return_input_with_export_name(input.clone());
+
+		assert_eq!(input, res);
+	}
+
 	fn test_return_option_data() {
 		let input = vec![1, 2, 3, 4, 5, 6];
 		let res = test_api::return_option_input(input.clone());
@@ -136,6 +916,93 @@ wasm_export_functions! {
 		assert_eq!(Some(input), res);
 	}
 
+	fn test_result_with_domain_error() {
+		assert_eq!(Ok(vec![1, 2]), test_api::check_data(vec![1, 2]));
+		assert_eq!(Err(DomainError::Empty), test_api::check_data(Vec::new()));
+		assert_eq!(
+			Err(DomainError::Rejected { code: 5, reason: vec![5, 6] }),
+			test_api::check_data(vec![5, 6]),
+		);
+
+		let rejected = Err(DomainError::Rejected { code: 7, reason: b"reason".to_vec() });
+		assert_eq!(rejected.clone(), test_api::return_result(rejected));
+		assert_eq!(Ok(vec![3]), test_api::return_result(Ok(vec![3])));
+	}
+
+	fn test_option_vec_as_parameter_and_return_value() {
+		assert_eq!(None, test_api::return_option_vec(None));
+		assert_eq!(Some(Vec::new()), test_api::return_option_vec(Some(Vec::new())));
+		assert_eq!(Some(vec![1, 2, 3]), test_api::return_option_vec(Some(vec![1, 2, 3])));
+	}
+
+	fn test_chunks() {
+		let chunks = test_api::return_chunks(1000);
+		let len = chunks.iter().map(|chunk| chunk.len() as u32).sum();
+
+		assert_eq!(test_api::return_chunks_by_codec(1000), chunks);
+		assert_eq!((1000, len), test_api::count_chunks(chunks));
+		assert_eq!(Vec::<Vec<u8>>::new(), test_api::return_chunks(0));
+		assert_eq!((2, 0), test_api::count_chunks(vec![Vec::new(), Vec::new()]));
+	}
+
+	fn test_bitmap() {
+		let bools = test_api::return_bools(100);
+		assert_eq!(100, bools.len());
+		assert_eq!((0..100).map(|i| i % 3 == 0 || i % 7 == 0).collect::<Vec<_>>(), bools);
+		assert_eq!(test_api::return_bools_by_codec(100), bools);
+
+		for count in &[0, 1, 8, 9] {
+			assert_eq!(test_api::return_bools_by_codec(*count), test_api::return_bools(*count));
+		}
+
+		let items = (0..100u32).map(|i| (vec![i as u8], i + i % 2)).collect::<Vec<_>>();
+		let expected = (0..100).map(|i| i % 2 == 0).collect::<Vec<_>>();
+		assert_eq!(expected, test_api::check_sum_batch(items));
+	}
+
+	fn test_byte_slices() {
+		let data = [1u8, 2, 3, 4, 5];
+		let slices = [&data[..2], &[][..], &data[2..], &[][..]];
+
+		assert_eq!(
+			vec![vec![1, 2], Vec::new(), vec![3, 4, 5], Vec::new()],
+			test_api::return_slices(&slices),
+		);
+		assert_eq!(Vec::<Vec<u8>>::new(), test_api::return_slices(&[]));
+		assert_eq!(vec![Vec::<u8>::new()], test_api::return_slices(&[&[]]));
+	}
+
+	fn test_borrowed_return_value() {
+		let haystack = b"a needle in a haystack";
+
+		let found = test_api::find(haystack, b"needle").unwrap();
+		assert_eq!(b"needle", found);
+		// The wasm side takes the returned slice from its own `haystack`.
+		assert_eq!(haystack[2..].as_ptr(), found.as_ptr());
+		assert_eq!(None, test_api::find(haystack, b"pin"));
+		assert_eq!(Some(&[][..]), test_api::find(haystack, b""));
+
+		let data = [0, 0, 1, 0, 2];
+		let trimmed = test_api::trim_leading_zeros(&data);
+		assert_eq!(&[1, 0, 2], trimmed);
+		assert_eq!(data[2..].as_ptr(), trimmed.as_ptr());
+		assert!(test_api::trim_leading_zeros(&[0, 0]).is_empty());
+	}
+
+	fn test_compressed_argument() {
+		let data = (0..256 * 1024u32)
+			.map(|i| (i % 251) as u8 ^ (i / 4096) as u8)
+			.collect::<Vec<_>>();
+
+		assert_eq!(data, test_api::return_compressed_input(&data));
+		assert_eq!(Vec::<u8>::new(), test_api::return_compressed_input(&[]));
+	}
+
+	fn test_option_array_return_value() {
+		assert_eq!(None, test_api::maybe_hash(&[]));
+		assert_eq!(Some([7; 32]), test_api::maybe_hash(&[7, 8]));
+	}
+
 	fn test_set_storage() {
 		let key = "hello";
 		let value = "world";
@@ -164,6 +1031,29 @@ wasm_export_functions! {
 		assert_eq!(&res, &input[..16]);
 	}
 
+	fn test_handle() {
+		let hasher = test_api::create_hasher();
+		let other = test_api::create_hasher();
+		assert_ne!(hasher, other);
+
+		test_api::hasher_update(hasher, b"hello ");
+		test_api::hasher_update(other, b"other");
+		test_api::hasher_update(hasher, b"world");
+
+		assert_eq!(test_api::blake(b"hello world"), test_api::hasher_finish(hasher));
+		assert_eq!(test_api::blake(b"other"), test_api::hasher_finish(other));
+	}
+
+	fn test_array_by_value() {
+		let mut pubkey = test_api::blake(b"message");
+
+		assert!(test_api::verify(pubkey, b"message"));
+		assert!(!test_api::verify(pubkey, b"other message"));
+
+		pubkey[31] ^= 1;
+		assert!(!test_api::verify(pubkey, b"message"));
+	}
+
 	fn test_array_as_mutable_reference() {
 		let mut array = [0u8; 16];
 		test_api::array_as_mutable_reference(&mut array);
@@ -193,6 +1083,13 @@ wasm_export_functions! {
 		test_api::invalid_utf8_data(data_str);
 	}
 
+	fn test_string_argument() {
+		let data = String::from("Grüße, 世界 🦀");
+		let bytes = data.as_bytes().to_vec();
+		assert_eq!((11, bytes), test_api::inspect_string(data));
+		assert_eq!((0, Vec::new()), test_api::inspect_string(String::new()));
+	}
+
 	fn test_overwrite_native_function_implementation() {
 		fn new_implementation() -> bool {
 			true
@@ -217,6 +1114,142 @@ wasm_export_functions! {
 		}
 	}
 
+	fn test_u128_big_add() {
+		assert_eq!(u128::max_value(), test_api::big_add(u128::max_value() - 1, 1));
+		assert_eq!(
+			u64::max_value() as u128 + 1,
+			test_api::big_add(u64::max_value() as u128, 1),
+		);
+	}
+
+	fn test_batch_function() {
+		let items = (0..100u32).map(|i| (vec![i as u8, 1], i + 1)).collect::<Vec<_>>();
+		assert_eq!(vec![true; 100], test_api::check_sum_batch(items));
+
+		assert_eq!(
+			vec![true, false],
+			test_api::check_sum_batch(vec![(vec![1, 2], 3), (vec![1, 2], 4)]),
+		);
+		assert!(test_api::check_sum_batch(Vec::new()).is_empty());
+	}
+
+	fn test_array_return_value() {
+		assert_eq!(sp_io::hashing::blake2_256(&[]), test_api::blake(&[]));
+		assert_eq!(sp_io::hashing::blake2_256(&TEST_ARRAY), test_api::blake(&TEST_ARRAY));
+		assert_ne!(test_api::blake(&[]), test_api::blake(&TEST_ARRAY));
+	}
+
+	fn test_cow_return_value() {
+		assert_eq!(&[1, 2, 3][..], &test_api::return_cow(false)[..]);
+		assert_eq!(&[4, 5, 6][..], &test_api::return_cow(true)[..]);
+	}
+
+	fn test_enum_slice_argument() {
+		let ops = [Op::Add(2), Op::Mul(3), Op::Reset, Op::Add(4), Op::Mul(5)];
+		assert_eq!(20, test_api::apply_ops(1, &ops));
+		assert_eq!(15, test_api::apply_ops(1, &ops[..2]));
+		assert_eq!(1, test_api::apply_ops(1, &[]));
+	}
+
+	fn test_btree_map_argument() {
+		let mut map = BTreeMap::new();
+		map.insert(3, vec![3]);
+		map.insert(1, vec![1, 1]);
+		map.insert(2, vec![]);
+
+		assert_eq!((vec![1, 2, 3], vec![1, 1, 3]), test_api::keys_and_values(map));
+		assert_eq!((vec![], vec![]), test_api::keys_and_values(BTreeMap::new()));
+	}
+
+	fn test_integer_slice_arguments() {
+		let u32s = [0, 1, 0x1234_5678, u32::max_value()];
+		assert_eq!(u32s.to_vec(), test_api::return_u32_slice(&u32s));
+		assert_eq!(Vec::<u32>::new(), test_api::return_u32_slice(&[]));
+
+		let u64s = [0, 1, 0x0102_0304_0506_0708, u64::max_value()];
+		assert_eq!(u64s.to_vec(), test_api::return_u64_slice(&u64s));
+		assert_eq!(Vec::<u64>::new(), test_api::return_u64_slice(&[]));
+
+		let u16s = vec![1, 2, 3, u16::max_value()];
+		assert_eq!(u16::max_value() as u32 + 6, test_api::sum_u16_vec(u16s));
+		assert_eq!(0, test_api::sum_u16_vec(Vec::new()));
+	}
+
+	fn test_tuple_return_values() {
+		assert_eq!((1, 2), test_api::split(1 << 32 | 2));
+		assert_eq!(
+			((u32::max_value(), 0), 32),
+			test_api::split_and_count_ones(u64::max_value() << 32),
+		);
+		assert_eq!((), test_api::return_empty_tuple());
+	}
+
+	fn test_codec_struct_by_reference() {
+		let point = Point { x: 3, y: 4 };
+		assert_eq!(25, test_api::squared_distance(&point));
+		assert_eq!(Point { x: 3, y: 4 }, point);
+	}
+
+	fn test_sum_of_arguments() {
+		assert_eq!(10, test_api::sum_of_arguments(1, 6, &[1, 2, 3]));
+	}
+
+	fn test_duration() {
+		assert_eq!(
+			Duration::new(3, 1),
+			test_api::add_one_and_a_half_seconds(Duration::new(1, 500_000_001)),
+		);
+		assert_eq!(
+			Duration::new(u64::max_value(), 999_999_999),
+			test_api::add_one_and_a_half_seconds(Duration::new(u64::max_value() - 2, 499_999_999)),
+		);
+	}
+
+	fn test_prefixed_host_function() {
+		assert_eq!(7, test_api_prefix::checksum(&[1, 2, 4]));
+	}
+
+	fn test_stream() {
+		let mut squares = test_api::squares(u32::max_value());
+		assert_eq!(Some(0), squares.next());
+		assert_eq!(vec![1, 4, 9], squares.by_ref().take(3).collect::<Vec<_>>());
+		drop(squares);
+
+		assert_eq!(vec![0, 1], test_api::squares(2).collect::<Vec<_>>());
+	}
+
+	fn test_many_arguments() {
+		assert_eq!(45, test_api::sum_of_nine(1, 2, 3, 4, 5, 6, 7, 8, &[0; 9]));
+	}
+
+	fn test_zero_sized_argument() {
+		assert_eq!(7, test_api::add_with_marker(3, PhantomData, 4));
+	}
+
+	fn test_codec_forced_for_copy_struct() {
+		assert_eq!(1, test_api::counter_value(Counter(1)));
+		assert_eq!(Counter(2), test_api::increment_counter(Counter(1)));
+	}
+
+	fn test_associated_constant() {
+		assert_eq!(3, test_api::PROTOCOL_VERSION);
+	}
+
+	fn test_version_alias() {
+		assert_eq!(6, test_api::sum_bytes(&[1, 2, 3]));
+		assert_eq!(0, test_api::sum_bytes(&[]));
+	}
+
+	fn test_out_vec_argument() {
+		let mut data = vec![1, 2, 3];
+		test_api::append_to_vec(&mut data);
+		assert_eq!(vec![1, 2, 3, 4, 5, 6], data);
+
+		let mut data = Vec::new();
+		test_api::append_to_vec(&mut data);
+		assert_eq!(vec![4, 5, 6], data);
+	}
+
 	fn test_vec_return_value_memory_is_freed() {
 		let mut len = 0;
 		for _ in 0..1024 {
@@ -225,6 +1258,16 @@ wasm_export_functions! {
 		assert_eq!(1024 * 1024 * 4, len);
 	}
 
+	fn test_large_vec_return_value_is_read_and_freed() {
+		// The host allocates the returned vector in the wasm memory and the runtime takes
+		// ownership of it, so every buffer is freed once when it is dropped.
+		for _ in 0..64 {
+			let data = test_api::return_large_buffer(256 * 1024);
+			assert_eq!(256 * 1024, data.len());
+			assert!(data.iter().enumerate().all(|(i, b)| *b == i as u8));
+		}
+	}
+
 	fn test_encoded_return_value_memory_is_freed() {
 		let mut len = 0;
 		for _ in 0..1024 {
@@ -241,6 +1284,162 @@ wasm_export_functions! {
 		assert_eq!(0, len);
 	}
 
+	fn test_error_enum() {
+		use test_api::TestApiError;
+
+		let validate = |x| test_api::validate(x).map_err(|()| TestApiError::Validate);
+
+		match validate(42).and_then(validate).and_then(|_| validate(43)) {
+			Err(TestApiError::Validate) => {},
+			res => panic!("Expected `validate` to fail, got: {:?}", res),
+		}
+		assert_eq!("validate", <&'static str>::from(TestApiError::Validate));
+		assert_eq!("return_input", <&'static str>::from(TestApiError::ReturnInput));
+	}
+
+	fn test_raw_slice_argument() {
+		assert_eq!(0, test_api_wasm_only::raw_slice_sum(&[]));
+		assert_eq!(136, test_api_wasm_only::raw_slice_sum(&TEST_ARRAY));
+	}
+
+	fn test_callback_argument() {
+		assert_eq!(0, test_api_wasm_only::callback_index(0));
+		assert_eq!(7, test_api_wasm_only::callback_index(7));
+	}
+
+	fn test_mixed_interface() {
+		sp_io::storage::set(b"key", b"value");
+		assert_eq!(Some(b"value".to_vec()), test_api_mixed::get(b"key"));
+
+		assert_eq!(0, test_api_mixed::raw_slice_len(&[]));
+		assert_eq!(16, test_api_mixed::raw_slice_len(&TEST_ARRAY));
+		assert_eq!(None, test_api_mixed::raw_slice_first(&[]));
+		assert_eq!(Some(1), test_api_mixed::raw_slice_first(&TEST_ARRAY));
+	}
+
+	fn test_async_implementation() {
+		assert_eq!(42, test_api_async::double(21));
+
+		sp_io::storage::set(b"key", &21u32.encode());
+		assert_eq!(Some(42), test_api_async::double_storage(b"key"));
+		assert_eq!(None, test_api_async::double_storage(b"unknown"));
+	}
+
+	fn test_recorded_calls() {
+		assert_eq!(3, test_api_record::sum(1, 2));
+		assert_eq!(4, test_api_record::length(&[1, 2, 3, 4]));
+	}
+
+	fn test_audited_calls() {
+		assert_eq!(6, test_api_audit::product(2, 3));
+		assert_eq!(Some(7), test_api_audit::first(&[7, 8]));
+	}
+
+	fn test_skipped_version() {
+		assert_eq!(3, test_api_skip_version::skipped_version());
+	}
+
+	fn test_reexported_crate() {
+		assert_eq!(vec![3, 2, 1], test_api_reexported::reverse(vec![1, 2, 3]));
+	}
+
+	fn test_no_externalities() {
+		assert_eq!(42, test_api_pure::multiply(6, 7));
+		assert_eq!(
+			0xffff_fffe_0000_0001,
+			test_api_pure::multiply(u32::max_value(), u32::max_value()),
+		);
+	}
+
+	fn test_reentrancy_guard() {
+		assert_eq!(0, test_api_reentrant::countdown(0));
+		assert_eq!(3, test_api_reentrant::countdown(3));
+	}
+
+	fn test_no_panic() {
+		assert_eq!(Ok(None), test_api_no_panic::get(b"no_panic"));
+		sp_io::storage::set(b"no_panic", &[1, 2]);
+		assert_eq!(Ok(Some(vec![1, 2])), test_api_no_panic::get(b"no_panic"));
+	}
+
+	fn test_registration_only() {
+		assert_eq!(vec![3, 2, 1], test_api_registration_only::reverse(vec![1, 2, 3]));
+	}
+
+	fn test_custom_context() {
+		assert_eq!(5, test_api_custom_context::first_byte(&TEST_ARRAY[4..]));
+	}
+
+	fn test_wrap() {
+		let calls = test_api_wrapped::wrapped_calls();
+		assert_eq!(5, test_api_wrapped::add(2, 3));
+		assert_eq!(7, test_api_wrapped::add(3, 4));
+		assert_eq!(calls + 3, test_api_wrapped::wrapped_calls());
+	}
+
+	fn test_stub_externalities() {
+		sp_io::storage::set(b"key", &[1, 2]);
+		assert_eq!(None, test_api_stub_externalities::get(b"key"));
+		assert_eq!(Some(b"stub_value".to_vec()), test_api_stub_externalities::get(b"stub_key"));
+	}
+
+	fn test_argument_allocator() {
+		use counting_allocator::{ALLOCATOR, ALLOCATIONS, DEALLOCATIONS};
+		use core::sync::atomic::Ordering;
+		use sp_runtime_interface::wasm::set_argument_allocator;
+
+		set_argument_allocator(Some(&ALLOCATOR));
+
+		// Encoded arguments are stored in a buffer of the allocator.
+		let point = Point { x: 3, y: 4 };
+		assert_eq!(25, test_api::squared_distance(&point));
+		assert_eq!(Some(vec![1, 2]), test_api::return_option_vec(Some(vec![1, 2])));
+		assert_eq!(2, ALLOCATIONS.load(Ordering::Relaxed));
+		assert_eq!(2, DEALLOCATIONS.load(Ordering::Relaxed));
+
+		// A `&[u8]` is passed as pointer to its own memory and doesn't need a buffer.
+		assert_eq!(6, test_api::sum_bytes(&[1, 2, 3]));
+		assert_eq!(2, ALLOCATIONS.load(Ordering::Relaxed));
+
+		set_argument_allocator(None);
+		assert_eq!(Some(vec![1, 2]), test_api::return_option_vec(Some(vec![1, 2])));
+		assert_eq!(2, ALLOCATIONS.load(Ordering::Relaxed));
+	}
+
+	fn test_no_alloc_arguments() {
+		use counting_allocator::{ALLOCATOR, ALLOCATIONS};
+		use core::sync::atomic::Ordering;
+		use sp_runtime_interface::wasm::set_argument_allocator;
+
+		set_argument_allocator(Some(&ALLOCATOR));
+		let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+
+		// The encoded `point` is stored in the scratch region, the allocator is not called.
+		let point = Point { x: 3, y: 4 };
+		assert_eq!(25, test_api::scratch_squared_distance(&point));
+		assert_eq!(1, test_api::scratch_count_points(vec![Point { x: 1, y: 2 }]));
+		assert_eq!(allocations, ALLOCATIONS.load(Ordering::Relaxed));
+
+		// The allocator is used again after the call.
+		assert_eq!(25, test_api::squared_distance(&point));
+		assert_eq!(allocations + 1, ALLOCATIONS.load(Ordering::Relaxed));
+
+		set_argument_allocator(None);
+	}
+
+	fn test_no_alloc_arguments_exceed_the_scratch_region() {
+		test_api::scratch_count_points(vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }]);
+	}
+
+	fn test_default_argument() {
+		assert_eq!(63, test_api_default_argument::scale(21, 3));
+	}
+
+	fn test_minimum_version() {
+		assert_eq!(3, test_api::called_version());
+		assert_eq!(test_api::called_version(), test_api::called_version_latest_version());
+	}
+
 	fn test_versionning_works() {
 		// we fix new api to accept only 42 as a proper input
 		// as opposed to sp-runtime-interface-test-wasm-deprecated::test_api::verify_input
@@ -249,5 +1448,8 @@ wasm_export_functions! {
 
 		assert!(!test_api::test_versionning(50));
 		assert!(!test_api::test_versionning(102));
+
+		// version 3 is `register_only`, so it isn't called
+		assert!(!test_api::test_versionning(60));
 	}
 }
@@ -24,7 +24,7 @@ use sp_runtime_interface::*;
 use sp_runtime_interface_test_wasm::{WASM_BINARY, test_api::HostFunctions};
 use sp_runtime_interface_test_wasm_deprecated::WASM_BINARY as WASM_BINARY_DEPRECATED;
 
-use sp_wasm_interface::HostFunctions as HostFunctionsT;
+use sp_wasm_interface::{HostFunctions as HostFunctionsT, Function};
 use sc_executor::CallInWasm;
 
 use std::{collections::HashSet, sync::{Arc, Mutex}};
@@ -32,10 +32,65 @@ use std::{collections::HashSet, sync::{Arc, Mutex}};
 type TestExternalities = sp_state_machine::TestExternalities<sp_runtime::traits::BlakeTwo256, u64>;
 
 fn call_wasm_method<HF: HostFunctionsT>(binary: &[u8], method: &str) -> TestExternalities {
+	call_wasm_method_with_host_functions(binary, method, HF::host_functions())
+}
+
+fn call_wasm_method_with_host_functions(
+	binary: &[u8],
+	method: &str,
+	mut host_functions: Vec<&'static dyn Function>,
+) -> TestExternalities {
 	let mut ext = TestExternalities::default();
 	let mut ext_ext = ext.ext();
-	let mut host_functions = HF::host_functions();
 	host_functions.extend(sp_io::SubstrateHostFunctions::host_functions());
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_wasm_only::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_mixed::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_async::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_record::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_audit::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_prefix::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_skip_version::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_reexported::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_pure::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_reentrant::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_no_panic::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_registration_only::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_default_argument::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_custom_context::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_stub_externalities::HostFunctions::host_functions()
+	);
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_wrapped::HostFunctions::host_functions()
+	);
 
 	let executor = sc_executor::WasmExecutor::new(
 		sc_executor::WasmExecutionMethod::Interpreted,
@@ -55,16 +110,146 @@ fn call_wasm_method<HF: HostFunctionsT>(binary: &[u8], method: &str) -> TestExte
 	ext
 }
 
+/// A tracing subscriber that records all spans and the messages of all events.
+#[derive(Clone, Default)]
+struct TracingSubscriber(Arc<Mutex<RecordedTraces>>);
+
+/// The spans and events recorded by [`TracingSubscriber`].
+#[derive(Default)]
+struct RecordedTraces {
+	/// The names of all spans.
+	spans: HashSet<&'static str>,
+	/// The names of all spans with a `version` field, together with this version.
+	versions: HashSet<(&'static str, u64)>,
+	/// The messages of all events.
+	messages: Vec<String>,
+}
+
+/// Collects the `version` and the `message` field of a span or event.
+#[derive(Default)]
+struct FieldVisitor {
+	version: Option<u64>,
+	message: Option<String>,
+}
+
+impl tracing::field::Visit for FieldVisitor {
+	fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+		if field.name() == "version" {
+			self.version = Some(value);
+		}
+	}
+
+	fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+		if field.name() == "message" {
+			self.message = Some(format!("{:?}", value));
+		}
+	}
+}
+
+impl tracing::subscriber::Subscriber for TracingSubscriber {
+	fn enabled(&self, _: &tracing::Metadata) -> bool { true }
+
+	fn new_span(&self, span: &tracing::span::Attributes) -> tracing::Id {
+		let mut traces = self.0.lock().unwrap();
+		let id = tracing::Id::from_u64((traces.spans.len() + 1) as _);
+		let mut visitor = FieldVisitor::default();
+		span.record(&mut visitor);
+
+		let name = span.metadata().name();
+		traces.spans.insert(name);
+		if let Some(version) = visitor.version {
+			traces.versions.insert((name, version));
+		}
+		id
+	}
+
+	fn record(&self, _: &tracing::Id, _: &tracing::span::Record) {}
+
+	fn record_follows_from(&self, _: &tracing::Id, _: &tracing::Id) {}
+
+	fn event(&self, event: &tracing::Event) {
+		let mut visitor = FieldVisitor::default();
+		event.record(&mut visitor);
+		self.0.lock().unwrap().messages.push(visitor.message.unwrap_or_default());
+	}
+
+	fn enter(&self, _: &tracing::Id) {}
+
+	fn exit(&self, _: &tracing::Id) {}
+}
+
 #[test]
 fn test_return_data() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_return_data");
 }
 
+#[test]
+fn test_return_data_with_export_name() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_return_data_with_export_name");
+
+	let names = HostFunctions::host_functions().iter().map(|f| f.name()).collect::<Vec<_>>();
+	assert!(names.contains(&"ext_test_api_return_input_legacy_version_1"));
+	assert!(!names.contains(&"ext_test_api_return_input_with_export_name_version_1"));
+}
+
 #[test]
 fn test_return_option_data() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_return_option_data");
 }
 
+#[test]
+fn test_result_with_domain_error() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_result_with_domain_error");
+}
+
+#[test]
+fn test_chunks() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_chunks");
+}
+
+#[test]
+fn test_bitmap() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_bitmap");
+}
+
+#[test]
+fn test_byte_slices() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_byte_slices");
+}
+
+#[test]
+fn test_borrowed_return_value() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_borrowed_return_value");
+
+	// Natively, the returned slice is a borrow of the argument without any copy.
+	let haystack = b"a needle in a haystack";
+	let found = sp_runtime_interface_test_wasm::test_api::find(haystack, b"needle").unwrap();
+	assert_eq!(haystack[2..8].as_ptr(), found.as_ptr());
+	assert_eq!(b"needle", found);
+
+	let data = [0, 0, 1, 0, 2];
+	let trimmed = sp_runtime_interface_test_wasm::test_api::trim_leading_zeros(&data);
+	assert_eq!(data[2..].as_ptr(), trimmed.as_ptr());
+}
+
+#[test]
+fn test_compressed_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_compressed_argument");
+}
+
+#[test]
+fn test_option_array_return_value() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_option_array_return_value");
+}
+
+#[test]
+fn test_option_vec_as_parameter_and_return_value() {
+	call_wasm_method::<HostFunctions>(
+		&WASM_BINARY[..],
+		"test_option_vec_as_parameter_and_return_value",
+	);
+}
+
 #[test]
 fn test_set_storage() {
 	let mut ext = call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_set_storage");
@@ -83,6 +268,16 @@ fn test_get_and_return_array() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_get_and_return_array");
 }
 
+#[test]
+fn test_handle() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_handle");
+}
+
+#[test]
+fn test_array_by_value() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_array_by_value");
+}
+
 #[test]
 fn test_array_as_mutable_reference() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_array_as_mutable_reference");
@@ -112,6 +307,11 @@ fn test_invalid_utf8_data_should_return_an_error() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_invalid_utf8_data_should_return_an_error");
 }
 
+#[test]
+fn test_string_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_string_argument");
+}
+
 #[test]
 fn test_overwrite_native_function_implementation() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_overwrite_native_function_implementation");
@@ -122,11 +322,272 @@ fn test_u128_i128_as_parameter_and_return_value() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_u128_i128_as_parameter_and_return_value");
 }
 
+#[test]
+fn test_u128_big_add() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_u128_big_add");
+}
+
+#[test]
+fn test_raw_slice_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_raw_slice_argument");
+}
+
+#[test]
+fn test_callback_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_callback_argument");
+}
+
+#[test]
+fn test_mixed_interface() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_mixed_interface");
+
+	let mut ext = TestExternalities::default();
+	ext.insert(b"key".to_vec(), b"value".to_vec());
+	ext.execute_with(|| {
+		assert_eq!(
+			Some(b"value".to_vec()),
+			sp_runtime_interface_test_wasm::test_api_mixed::get(b"key"),
+		);
+	});
+}
+
+#[test]
+fn test_async_implementation() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_async_implementation");
+
+	let mut ext = TestExternalities::default();
+	ext.insert(b"key".to_vec(), codec::Encode::encode(&21u32));
+	ext.execute_with(|| {
+		use sp_runtime_interface_test_wasm::test_api_async;
+
+		assert_eq!(42, test_api_async::double(21));
+		assert_eq!(Some(42), test_api_async::double_storage(b"key"));
+	});
+}
+
+#[test]
+fn test_async_functions() {
+	use sp_runtime_interface::futures::executor::block_on;
+	use sp_runtime_interface_test_wasm::test_api_async;
+
+	assert_eq!(42, block_on(test_api_async::double_async(21)));
+
+	let mut ext = TestExternalities::default();
+	ext.insert(b"key".to_vec(), codec::Encode::encode(&21u32));
+	assert_eq!(Some(42), block_on(test_api_async::double_storage_async(&mut ext.ext(), b"key")));
+	assert_eq!(None, block_on(test_api_async::double_storage_async(&mut ext.ext(), b"unknown")));
+}
+
+#[test]
+fn test_recorded_calls() {
+	use sp_runtime_interface_test_wasm::test_api_record;
+
+	let expected = vec![
+		("sum_version_1", codec::Encode::encode(&(1u32, 2u32))),
+		("length_version_1", codec::Encode::encode(&vec![1u8, 2, 3, 4])),
+	];
+
+	let mut recorder = Vec::new();
+	record::record_calls(&mut recorder, || {
+		call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_recorded_calls");
+	});
+	assert_eq!(expected, recorder);
+
+	let mut recorder = Vec::new();
+	record::record_calls(&mut recorder, || {
+		assert_eq!(3, test_api_record::sum(1, 2));
+		assert_eq!(4, test_api_record::length(&[1, 2, 3, 4]));
+	});
+	assert_eq!(expected, recorder);
+}
+
+#[test]
+fn test_audited_calls() {
+	use sp_runtime_interface::audit::{self, AuditSink};
+	use std::sync::{Arc, Mutex};
+
+	#[derive(Clone, Default)]
+	struct Calls(Arc<Mutex<Vec<(&'static str, u32, Vec<u8>)>>>);
+
+	impl AuditSink for Calls {
+		fn audit(&self, function: &'static str, version: u32, args: &[u8]) {
+			self.0.lock().unwrap().push((function, version, args.to_vec()));
+		}
+	}
+
+	let calls = Calls::default();
+	audit::set_audit_sink(Some(Box::new(calls.clone())));
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_audited_calls");
+	audit::set_audit_sink(None);
+
+	let expected = vec![
+		("product", 1, codec::Encode::encode(&(2u32, 3u32))),
+		("first", 1, codec::Encode::encode(&vec![7u8, 8])),
+	];
+	assert_eq!(expected, *calls.0.lock().unwrap());
+}
+
+#[test]
+fn test_cow_return_value() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_cow_return_value");
+}
+
+#[test]
+fn test_minimum_version() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_minimum_version");
+
+	assert_eq!(3, sp_runtime_interface_test_wasm::test_api::called_version());
+
+	let called_versions = sp_runtime_interface_test_wasm::test_api::INTERFACE_FUNCTIONS.iter()
+		.filter(|(name, _)| *name == "called_version")
+		.map(|(_, version)| *version)
+		.collect::<Vec<_>>();
+	assert_eq!(vec![1, 2, 3, 4], called_versions);
+
+	let host_functions = HostFunctions::host_functions()
+		.iter()
+		.map(|f| f.name().to_string())
+		.filter(|name| name.starts_with("ext_test_api_called_version_version_"))
+		.count();
+	assert_eq!(4, host_functions);
+}
+
+#[test]
+fn test_dispatch_by_version() {
+	use sp_runtime_interface_test_wasm::test_api::called_version_dispatch;
+
+	assert_eq!(Some(1), called_version_dispatch(1));
+	assert_eq!(Some(2), called_version_dispatch(2));
+	assert_eq!(Some(4), called_version_dispatch(4));
+	assert_eq!(None, called_version_dispatch(5));
+}
+
+#[test]
+fn test_enum_slice_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_enum_slice_argument");
+}
+
+#[test]
+fn test_duration() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_duration");
+}
+
+#[test]
+fn test_prefixed_host_function() {
+	use sp_runtime_interface_test_wasm::test_api_prefix;
+
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_prefixed_host_function");
+
+	let names = test_api_prefix::HostFunctions::host_functions()
+		.iter()
+		.map(|f| f.name().to_string())
+		.collect::<Vec<_>>();
+	assert_eq!(vec!["ext_test_api_prefix_crypto_checksum_version_1"], names);
+}
+
+#[test]
+fn test_stream() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_stream");
+}
+
+#[test]
+fn test_many_arguments() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_many_arguments");
+}
+
+#[test]
+fn test_btree_map_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_btree_map_argument");
+}
+
+#[test]
+fn test_integer_slice_arguments() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_integer_slice_arguments");
+}
+
+#[test]
+fn test_tuple_return_values() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_tuple_return_values");
+}
+
+#[test]
+fn test_codec_struct_by_reference() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_codec_struct_by_reference");
+}
+
+#[test]
+fn test_codec_forced_for_copy_struct() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_codec_forced_for_copy_struct");
+}
+
+#[test]
+fn test_version_alias() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_version_alias");
+
+	assert_eq!(6, sp_runtime_interface_test_wasm::test_api::sum_bytes(&[1, 2, 3]));
+}
+
+#[test]
+fn test_out_vec_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_out_vec_argument");
+}
+
+#[test]
+fn test_batch_function_calls_into_the_host_once() {
+	/// Wraps a host function and records the name of the function on every call.
+	struct RecordCalls(&'static dyn Function, &'static Mutex<Vec<String>>);
+
+	impl Function for RecordCalls {
+		fn name(&self) -> &str {
+			self.0.name()
+		}
+
+		fn signature(&self) -> sp_wasm_interface::Signature {
+			self.0.signature()
+		}
+
+		fn execute(
+			&self,
+			context: &mut dyn sp_wasm_interface::FunctionContext,
+			args: &mut dyn Iterator<Item = sp_wasm_interface::Value>,
+		) -> sp_wasm_interface::Result<Option<sp_wasm_interface::Value>> {
+			self.1.lock().unwrap().push(self.0.name().into());
+			self.0.execute(context, args)
+		}
+	}
+
+	let calls: &'static Mutex<Vec<String>> = Box::leak(Box::new(Mutex::new(Vec::new())));
+	let host_functions = HostFunctions::host_functions()
+		.into_iter()
+		.map(|f| &*Box::leak(Box::new(RecordCalls(f, calls))) as &'static dyn Function)
+		.collect();
+
+	call_wasm_method_with_host_functions(&WASM_BINARY[..], "test_batch_function", host_functions);
+
+	let calls = calls.lock().unwrap();
+	// `test_batch_function` calls the batch function three times, with 100, 2 and 0 items.
+	assert_eq!(3, calls.iter().filter(|c| *c == "ext_test_api_check_sum_batch_version_1").count());
+	assert!(!calls.iter().any(|c| c == "ext_test_api_check_sum_version_1"));
+}
+
+#[test]
+fn test_array_return_value() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_array_return_value");
+}
+
 #[test]
 fn test_vec_return_value_memory_is_freed() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_vec_return_value_memory_is_freed");
 }
 
+#[test]
+fn test_large_vec_return_value_is_read_and_freed() {
+	call_wasm_method::<HostFunctions>(
+		&WASM_BINARY[..],
+		"test_large_vec_return_value_is_read_and_freed",
+	);
+}
+
 #[test]
 fn test_encoded_return_value_memory_is_freed() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_encoded_return_value_memory_is_freed");
@@ -137,6 +598,145 @@ fn test_array_return_value_memory_is_freed() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_array_return_value_memory_is_freed");
 }
 
+#[test]
+fn test_error_enum() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_error_enum");
+}
+
+#[test]
+fn cfg_attributes_are_forwarded() {
+	assert!(
+		!sp_runtime_interface_test_wasm::test_api::INTERFACE_FUNCTIONS.iter()
+			.any(|(name, _)| *name == "never_compiled")
+	);
+	assert!(
+		!HostFunctions::host_functions().iter()
+			.any(|f| f.name().contains("never_compiled"))
+	);
+}
+
+#[test]
+fn test_mocked_implementation() {
+	use sp_runtime_interface_test_wasm::test_api;
+
+	{
+		let _guard = test_api::mock_validate(|x| Ok(x + 1));
+		assert_eq!(Ok(43), test_api::validate(42));
+	}
+
+	sp_io::TestExternalities::default().execute_with(|| {
+		assert_eq!(Ok(42), test_api::validate(42));
+	});
+}
+
+#[test]
+fn interface_functions_lists_all_versions() {
+	let functions = sp_runtime_interface_test_wasm::test_api::INTERFACE_FUNCTIONS;
+
+	assert!(functions.contains(&("return_input", 1)));
+	assert!(functions.contains(&("test_versionning", 1)));
+	assert!(functions.contains(&("test_versionning", 2)));
+	assert!(functions.contains(&("test_versionning", 3)));
+	assert!(functions.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn latest_versions_are_exposed() {
+	use sp_runtime_interface_test_wasm::test_api;
+
+	assert_eq!(1, test_api::return_input_latest_version());
+	assert_eq!(2, test_api::test_versionning_latest_version());
+	// Version 4 is `register_only`, so version 3 is the latest version that is called.
+	assert_eq!(3, test_api::called_version_latest_version());
+	assert!(test_api::latest_version() >= test_api::called_version_latest_version());
+}
+
+#[test]
+fn versioned_function_names_are_exposed() {
+	use sp_runtime_interface_test_wasm::test_api;
+
+	assert_eq!(test_api::RETURN_INPUT_VERSION_1, "return_input_version_1");
+	assert_eq!(test_api::TEST_VERSIONNING_VERSION_2, "test_versionning_version_2");
+}
+
+#[test]
+fn test_sum_of_arguments() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_sum_of_arguments");
+}
+
+#[test]
+fn ffi_size_of_arguments_is_exposed() {
+	use sp_runtime_interface_test_wasm::test_api;
+
+	// `a: u32` takes one `u32` slot, `b: u64` and `c: &[u8]` take one `u64` slot each.
+	assert_eq!(3, test_api::sum_of_arguments_version_1_signature().args.len());
+	assert_eq!(4 + 8 + 8, test_api::SUM_OF_ARGUMENTS_VERSION_1_FFI_SIZE);
+	assert_eq!(8, test_api::INCREMENT_COUNTER_VERSION_1_FFI_SIZE);
+}
+
+#[test]
+fn test_zero_sized_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_zero_sized_argument");
+}
+
+#[test]
+fn zero_sized_argument_is_not_passed() {
+	use sp_runtime_interface_test_wasm::test_api;
+
+	// Only `a: u32` and `b: u32` are passed, the `PhantomData` is created by the host.
+	assert_eq!(2, test_api::add_with_marker_version_1_signature().args.len());
+	assert_eq!(4 + 4, test_api::ADD_WITH_MARKER_VERSION_1_FFI_SIZE);
+}
+
+#[test]
+fn codec_argument_is_passed_as_pointer() {
+	use sp_runtime_interface_test_wasm::test_api;
+	use sp_wasm_interface::{Signature, ValueType};
+
+	assert_eq!(
+		Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+		test_api::counter_value_version_1_signature(),
+	);
+	assert_eq!(
+		Signature::new(&[ValueType::I64][..], Some(ValueType::I64)),
+		test_api::increment_counter_version_1_signature(),
+	);
+}
+
+#[test]
+fn host_functions_contain_all_versions() {
+	use sp_runtime_interface_test_wasm::test_api;
+
+	assert_eq!(test_api::INTERFACE_FUNCTIONS.len(), test_api::host_functions().len());
+}
+
+#[test]
+fn host_function_signature_is_exposed() {
+	use sp_runtime_interface_test_wasm::test_api;
+	use sp_wasm_interface::{Signature, ValueType};
+
+	let signature = test_api::add_version_1_signature();
+
+	assert_eq!(
+		Signature::new(&[ValueType::I32, ValueType::I64][..], Some(ValueType::I32)),
+		signature,
+	);
+	assert_eq!(
+		Some(signature),
+		HostFunctions::host_functions().iter()
+			.find(|f| f.name() == "ext_test_api_add_version_1")
+			.map(|f| f.signature()),
+	);
+}
+
+#[test]
+fn register_only_version_is_registered() {
+	let names = HostFunctions::host_functions().iter().map(|f| f.name()).collect::<Vec<_>>();
+
+	assert!(names.contains(&"ext_test_api_test_versionning_version_2"));
+	assert!(names.contains(&"ext_test_api_test_versionning_version_3"));
+}
+
 #[test]
 fn test_versionining_with_new_host_works() {
 	// We call to the new wasm binary with new host function.
@@ -154,45 +754,223 @@ fn test_versionining_with_new_host_works() {
 }
 
 #[test]
-fn test_tracing() {
-	use tracing::span::Id as SpanId;
+fn test_reexported_crate() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_reexported_crate");
+}
 
-	#[derive(Clone)]
-	struct TracingSubscriber(Arc<Mutex<Inner>>);
+#[test]
+fn test_no_externalities() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_no_externalities");
+}
 
-	#[derive(Default)]
-	struct Inner {
-		spans: HashSet<&'static str>,
-	}
+#[test]
+fn no_externalities_interface_works_without_externalities() {
+	use sp_runtime_interface_test_wasm::test_api_pure;
 
-	impl tracing::subscriber::Subscriber for TracingSubscriber {
-		fn enabled(&self, _: &tracing::Metadata) -> bool { true }
+	assert_eq!(42, test_api_pure::multiply(6, 7));
+}
 
-		fn new_span(&self, span: &tracing::span::Attributes) -> tracing::Id {
-			let mut inner = self.0.lock().unwrap();
-			let id = SpanId::from_u64((inner.spans.len() + 1) as _);
-			inner.spans.insert(span.metadata().name());
-			id
-		}
+#[test]
+fn test_reentrancy_guard() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_reentrancy_guard");
+}
 
-		fn record(&self, _: &SpanId, _: &tracing::span::Record) {}
+#[test]
+fn reentrant_calls_are_detected() {
+	use sp_runtime_interface::host::enter_function;
+	use sp_runtime_interface_test_wasm::test_api_reentrant;
 
-		fn record_follows_from(&self, _: &SpanId, _: &SpanId) {}
+	assert!(!enter_function(test_api_reentrant::COUNTDOWN_VERSION_1).is_reentrant());
+	assert_eq!(5, test_api_reentrant::countdown(5));
 
-		fn event(&self, _: &tracing::Event) {}
+	let _entered = enter_function(test_api_reentrant::COUNTDOWN_VERSION_1);
+	assert!(enter_function(test_api_reentrant::COUNTDOWN_VERSION_1).is_reentrant());
+}
 
-		fn enter(&self, _: &SpanId) {}
+#[test]
+fn test_associated_constant() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_associated_constant");
+	assert_eq!(3, sp_runtime_interface_test_wasm::test_api::PROTOCOL_VERSION);
+}
 
-		fn exit(&self, _: &SpanId) {}
-	}
+#[test]
+fn test_no_panic() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_no_panic");
+}
+
+#[test]
+fn no_panic_returns_an_error_without_externalities() {
+	use sp_runtime_interface_test_wasm::{test_api_no_panic, StorageError};
+
+	assert_eq!(Err(StorageError::NoExternalities), test_api_no_panic::get(b"key"));
+}
+
+#[test]
+fn abi_hash_depends_on_the_signature() {
+	use sp_runtime_interface_test_wasm::test_api;
+
+	// Same signature, but a different function.
+	assert_eq!(
+		test_api::RETURN_INPUT_VERSION_1_ABI_HASH,
+		test_api::RETURN_INPUT_WITH_SPAN_NAME_VERSION_1_ABI_HASH,
+	);
+	assert_ne!(
+		test_api::RETURN_INPUT_VERSION_1_ABI_HASH,
+		test_api::SET_STORAGE_VERSION_1_ABI_HASH,
+	);
+}
+
+#[test]
+fn native_only_method_takes_trait_object() {
+	use sp_runtime_interface_test_wasm::test_api;
+
+	assert_eq!(vec![1, 2, 3], test_api::native_only_bytes(&[1u8, 2, 3]));
+	assert_eq!(b"native".to_vec(), test_api::native_only_bytes(&"native"));
+
+	let names = test_api::host_functions().iter().map(|f| f.name()).collect::<Vec<_>>();
+	assert!(!names.iter().any(|name| name.contains("native_only_bytes")));
+}
+
+#[test]
+fn test_custom_context() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_custom_context");
+}
+
+#[test]
+fn test_wrap() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_wrap");
+}
+
+#[test]
+fn wrap_counts_the_native_calls() {
+	use sp_runtime_interface_test_wasm::test_api_wrapped;
+
+	let calls = test_api_wrapped::wrapped_calls();
+	assert_eq!(5, test_api_wrapped::add(2, 3));
+	assert_eq!(calls + 2, test_api_wrapped::wrapped_calls());
+}
+
+#[test]
+fn test_stub_externalities() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_stub_externalities");
+}
+
+#[test]
+fn stub_externalities_are_used_without_externalities() {
+	use sp_runtime_interface_test_wasm::test_api_stub_externalities;
+
+	assert_eq!(Some(b"stub_value".to_vec()), test_api_stub_externalities::get(b"stub_key"));
+}
+
+#[test]
+fn test_argument_allocator() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_argument_allocator");
+}
 
-	let subscriber = TracingSubscriber(Default::default());
+#[test]
+fn test_no_alloc_arguments() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_no_alloc_arguments");
+}
+
+#[test]
+#[should_panic(
+	expected = "Executes `test_no_alloc_arguments_exceed_the_scratch_region`: \
+		\"Trap: Trap { kind: Unreachable }\""
+)]
+fn test_no_alloc_arguments_exceed_the_scratch_region() {
+	call_wasm_method::<HostFunctions>(
+		&WASM_BINARY[..],
+		"test_no_alloc_arguments_exceed_the_scratch_region",
+	);
+}
+
+#[test]
+fn test_default_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_default_argument");
+}
+
+#[test]
+fn previous_version_is_called_with_the_default_argument() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY_DEPRECATED[..], "test_default_argument");
+}
+
+#[test]
+fn test_skipped_version() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_skipped_version");
+}
+
+#[test]
+#[should_panic(expected = "Version 2 of `skipped_version` is skipped and must not be called")]
+fn skipped_version_traps() {
+	// The old wasm binary calls version 2, which is skipped by the new host functions.
+	call_wasm_method::<HostFunctions>(&WASM_BINARY_DEPRECATED[..], "test_skipped_version");
+}
+
+#[test]
+fn skipped_version_is_registered() {
+	use sp_runtime_interface_test_wasm::test_api_skip_version;
+
+	let names = test_api_skip_version::host_functions().iter()
+		.map(|f| f.name().to_string())
+		.collect::<Vec<_>>();
+
+	assert_eq!(
+		vec![
+			"ext_test_api_skip_version_skipped_version_version_1",
+			"ext_test_api_skip_version_skipped_version_version_3",
+			"ext_test_api_skip_version_skipped_version_version_2",
+		],
+		names,
+	);
+}
+
+#[test]
+fn test_tracing() {
+	let subscriber = TracingSubscriber::default();
 	let _guard = tracing::subscriber::set_default(subscriber.clone());
 
 	// Call some method to generate a trace
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_return_data");
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_return_data_with_span_name");
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_return_data_without_tracing");
 
 	let inner = subscriber.0.lock().unwrap();
 	assert!(inner.spans.contains("return_input_version_1"));
 	assert!(inner.spans.contains("ext_test_api_return_input_version_1"));
+	assert!(inner.spans.contains("custom_span_name"));
+	assert!(!inner.spans.contains("return_input_with_span_name_version_1"));
+	assert!(!inner.spans.contains("return_input_without_tracing_version_1"));
+	assert!(!inner.spans.contains("ext_test_api_return_input_without_tracing_version_1"));
+}
+
+#[test]
+fn test_tracing_span_records_version() {
+	let subscriber = TracingSubscriber::default();
+	let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+	// Calls version 2 of `test_versionning`.
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_versionning_works");
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_return_data_with_span_name");
+
+	let traces = subscriber.0.lock().unwrap();
+	assert!(traces.versions.contains(&("test_versionning_version_2", 2)));
+	assert!(traces.versions.contains(&("ext_test_api_test_versionning_version_2", 2)));
+	// A custom span name still records the version.
+	assert!(traces.versions.contains(&("custom_span_name", 1)));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_debug_args_are_logged() {
+	let subscriber = TracingSubscriber::default();
+	let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_return_data");
+
+	let traces = subscriber.0.lock().unwrap();
+	assert!(
+		traces.messages.contains(
+			&"Calling `return_input_version_1` with arguments: data = [1, 2, 3, 4, 5, 6]".into()
+		)
+	);
 }
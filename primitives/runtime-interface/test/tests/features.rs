@@ -0,0 +1,78 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the interface options that depend on features of the test wasm crate.
+//!
+//! The features change the generated code of the interfaces, so they are only enabled for these
+//! tests with the `test-wasm-features` feature.
+
+use sp_runtime_interface_test_wasm::WASM_BINARY;
+
+use sp_runtime_interface::sp_wasm_interface::HostFunctions as HostFunctionsT;
+use sc_executor::CallInWasm;
+
+type TestExternalities = sp_state_machine::TestExternalities<sp_runtime::traits::BlakeTwo256, u64>;
+
+#[test]
+fn conversion_benches_compile() {
+	use sp_runtime_interface_test_wasm::test_api::benches;
+
+	let _ = benches::return_input_version_1_arguments;
+	let _ = benches::return_input_version_1_return_value;
+	let _ = benches::set_storage_version_1_arguments;
+}
+
+#[test]
+#[should_panic(
+	expected = "`ext_test_api_registration_only_reverse_version_1` is compiled with the \
+		`registration-only` feature and can not be called"
+)]
+fn registration_only_host_function_traps() {
+	let mut host_functions = sp_io::SubstrateHostFunctions::host_functions();
+	host_functions.extend(
+		sp_runtime_interface_test_wasm::test_api_registration_only::HostFunctions::host_functions()
+	);
+	let executor = sc_executor::WasmExecutor::new(
+		sc_executor::WasmExecutionMethod::Interpreted,
+		Some(8),
+		host_functions,
+		8,
+	);
+
+	// The host implementation of the interface is not compiled, only the host functions are
+	// registered.
+	let mut ext = TestExternalities::default();
+	executor.call_in_wasm(
+		&WASM_BINARY[..],
+		None,
+		"test_registration_only",
+		&[],
+		&mut ext.ext(),
+		sp_core::traits::MissingHostFunctions::Allow,
+	).expect("Executes `test_registration_only`");
+}
+
+#[test]
+fn host_functions_are_left_out_with_their_feature() {
+	use sp_runtime_interface_test_wasm::test_api_features;
+
+	let names = test_api_features::host_functions().iter()
+		.map(|function| function.name().to_string())
+		.collect::<Vec<_>>();
+
+	// Only `disable_hostfn_test_api_features_disabled` is enabled.
+	assert_eq!(vec!["ext_test_api_features_enabled_version_1".to_string()], names);
+}
@@ -0,0 +1,43 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The tests generated by `#[runtime_interface(gen_tests)]`, run with the `gen-tests` feature.
+
+#![cfg(feature = "gen-tests")]
+
+use sp_runtime_interface::runtime_interface;
+
+/// An interface with generated roundtrip tests for its arguments.
+///
+/// `values`, `text`, the `#[compressed]` data and the `&[&[u8]]` slices don't support the roundtrip
+/// on the host side and are skipped.
+#[runtime_interface(gen_tests)]
+pub trait TestApiGenTests {
+	/// Returns the sum of `value`, `flag` and the lengths of the other arguments.
+	fn sum(value: u32, flag: bool, data: &[u8], values: Vec<u32>, text: &str) -> u32 {
+		value + flag as u32 + data.len() as u32 + values.len() as u32 + text.len() as u32
+	}
+
+	/// Returns the length of the decompressed `data`.
+	fn compressed_len(#[compressed] data: &[u8]) -> u32 {
+		data.len() as u32
+	}
+
+	/// Returns the number of `slices`.
+	fn count_slices(slices: &[&[u8]]) -> u32 {
+		slices.len() as u32
+	}
+}
@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+pub struct NoConversion;
+
+#[runtime_interface]
+trait Test {
+	fn test(_data: NoConversion) {}
+}
+
+fn main() {}
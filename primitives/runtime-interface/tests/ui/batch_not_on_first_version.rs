@@ -0,0 +1,12 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test(data: u32) {}
+
+	#[batch]
+	#[version(2)]
+	fn test(data: u32) {}
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[batch]
+	#[wasm_only]
+	fn test(data: u32) {}
+}
+
+fn main() {}
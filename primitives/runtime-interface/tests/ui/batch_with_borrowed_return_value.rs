@@ -0,0 +1,11 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[batch]
+	fn test<'a>(data: &'a [u8]) -> &'a [u8] {
+		data
+	}
+}
+
+fn main() {}
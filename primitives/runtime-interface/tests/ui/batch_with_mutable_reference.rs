@@ -0,0 +1,9 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[batch]
+	fn test(data: &mut [u8]) {}
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test(#[callback] callback: u32) {}
+}
+
+fn main() {}
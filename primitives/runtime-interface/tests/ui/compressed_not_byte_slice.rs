@@ -0,0 +1,8 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test(#[compressed] data: Vec<u8>) {}
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	const VERSION: u32;
+
+	fn test() {}
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test(data: u32) {}
+
+	#[version(2)]
+	fn test(data: u32, #[default_when_missing(0)] offset: u32) {}
+}
+
+fn main() {}
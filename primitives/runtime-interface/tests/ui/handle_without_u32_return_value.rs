@@ -0,0 +1,11 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[handle]
+	fn test() -> u64 {
+		0
+	}
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#![deny(unused_must_use)]
+
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[must_use]
+	fn verify(data: &[u8]) -> bool {
+		data.is_empty()
+	}
+}
+
+fn main() {
+	test::verify(&[]);
+}
@@ -0,0 +1,9 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[export_name = "not an ident"]
+	fn test() {}
+}
+
+fn main() {}
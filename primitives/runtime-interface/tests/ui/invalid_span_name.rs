@@ -0,0 +1,9 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[span_name = 1]
+	fn test() {}
+}
+
+fn main() {}
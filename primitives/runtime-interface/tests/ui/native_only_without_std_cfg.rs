@@ -0,0 +1,9 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[native_only]
+	fn test(data: &dyn AsRef<[u8]>) {}
+}
+
+fn main() {}
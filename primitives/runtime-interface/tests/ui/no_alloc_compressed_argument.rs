@@ -0,0 +1,9 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[no_alloc(max = 64)]
+	fn test(#[compressed] data: &[u8]) {}
+}
+
+fn main() {}
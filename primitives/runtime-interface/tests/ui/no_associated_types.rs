@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	type Value;
+
+	fn test() {}
+}
+
+fn main() {}
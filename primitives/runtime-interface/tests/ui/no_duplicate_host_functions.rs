@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test(data: u32) { }
+	#[export_name = "test"]
+	fn renamed_test(data: u32, other: u32) { }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface(no_externalities)]
+trait Test {
+	fn test(&mut self, data: u32) -> u32 {
+		self.storage(&[]);
+		data
+	}
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	const VERSION: u32 = 1;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface(no_panic)]
+trait Test {
+	fn test(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.storage(key)
+	}
+}
+
+fn main() {}
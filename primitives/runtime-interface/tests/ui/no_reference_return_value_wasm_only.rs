@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface(wasm_only)]
+trait Test {
+	fn scratch(&mut self) -> &mut [u8] {
+		unimplemented!()
+	}
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test() {}
+
+	#[version(2, register_only, min = true)]
+	fn test() {}
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[version(1, register_only)]
+	fn test() {}
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use sp_runtime_interface::runtime_interface;
+
+trait BaseIface {
+	fn base() {}
+}
+
+#[runtime_interface]
+trait MyIface: BaseIface {
+	fn test() {}
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+struct Test;
+
+fn main() {}
@@ -0,0 +1,11 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[pass_by(codec)]
+	fn test<'a>(data: &'a [u8]) -> Option<&'a [u8]> {
+		Some(data)
+	}
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface(wasm_only)]
+trait Test {
+	fn test(&mut self, #[pass_by(raw_ptr)] data: &[u32]) {}
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test(#[pass_by(raw_ptr)] data: &[u8]) {}
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[pass_by(width)]
+	fn test() -> Vec<u32> {
+		Vec::new()
+	}
+}
+
+fn main() {}
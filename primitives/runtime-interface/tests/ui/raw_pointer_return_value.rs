@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test() -> Option<*const u8> {
+		None
+	}
+}
+
+fn main() {}
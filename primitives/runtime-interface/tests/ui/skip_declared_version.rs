@@ -0,0 +1,12 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	#[skip_version(2)]
+	fn test() {}
+
+	#[version(2)]
+	fn test() {}
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface(no_externalities)]
+trait Test {
+	#[stream]
+	fn numbers(count: u32) -> impl Iterator<Item = u32> {
+		0..count
+	}
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface(strict_versioning)]
+trait Test {
+	fn test(data: u32) -> u32 {
+		data
+	}
+
+	#[version(2)]
+	fn test(data: u64) -> u64 {
+		data
+	}
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface(deny_unused_self)]
+trait Test {
+	fn test(&self, data: u32) -> u32 {
+		data
+	}
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+#![deny(deprecated)]
+
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface(warn_wide = 6)]
+trait Test {
+	fn wide(a: u32, b: u32, c: u32, d: u32, e: u32, f: u32, g: u32) -> u32 {
+		a + b + c + d + e + f + g
+	}
+
+	fn narrow(a: u32, b: u32, c: u32, d: u32, e: u32) -> u32 {
+		a + b + c + d + e
+	}
+}
+
+fn main() {}
@@ -53,18 +53,23 @@ macro_rules! tracing_span {
 
 /// Enter a span.
 ///
-/// The span will be valid, until the scope is left.
+/// The span will be valid, until the scope is left. Fields of the span can be given after the
+/// name, using the syntax of `tracing::span!`.
 ///
 /// # Example
 ///
 /// ```
 /// sp_tracing::enter_span!("test-span");
+/// sp_tracing::enter_span!("test-span-with-fields", version = 2);
 /// ```
 #[macro_export]
 macro_rules! enter_span {
 	( $name:expr ) => {
+		$crate::enter_span!($name,)
+	};
+	( $name:expr, $( $fields:tt )* ) => {
 		let __tracing_span__ = $crate::if_tracing!(
-			$crate::tracing::span!($crate::tracing::Level::TRACE, $name)
+			$crate::tracing::span!($crate::tracing::Level::TRACE, $name, $( $fields )*)
 		);
 		let __tracing_guard__ = $crate::if_tracing!(__tracing_span__.enter());
 	}